@@ -0,0 +1,21 @@
+-- +migrate Up
+CREATE TABLE unit_defs (
+  id               TEXT PRIMARY KEY,
+  cost_energy      INT  NOT NULL DEFAULT 0 CHECK (cost_energy >= 0),
+  cost_biomass     INT  NOT NULL DEFAULT 0 CHECK (cost_biomass >= 0),
+  cost_gene_seeds  INT  NOT NULL DEFAULT 0 CHECK (cost_gene_seeds >= 0),
+  atk              INT  NOT NULL CHECK (atk >= 0),
+  hp               INT  NOT NULL CHECK (hp > 0),
+  speed            INT  NOT NULL DEFAULT 1 CHECK (speed >= 0),
+  attack_range     INT  NOT NULL DEFAULT 1 CHECK (attack_range >= 0),
+  abilities        TEXT[] NOT NULL DEFAULT '{}'
+);
+
+-- The four starting archetypes
+INSERT INTO unit_defs
+  (id, cost_energy, cost_biomass, cost_gene_seeds, atk, hp, speed, attack_range, abilities)
+VALUES
+  ('Light',  1, 0, 0, 1, 1, 3, 1, '{}'),
+  ('Ranged', 2, 1, 0, 2, 1, 2, 3, '{Volley}'),
+  ('Heavy',  0, 3, 0, 3, 3, 1, 1, '{}'),
+  ('Seeder', 1, 0, 1, 0, 2, 1, 1, '{Regrow}');
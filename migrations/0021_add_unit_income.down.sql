@@ -0,0 +1,5 @@
+-- +migrate Down
+ALTER TABLE unit_defs
+    DROP COLUMN IF EXISTS income_energy,
+    DROP COLUMN IF EXISTS income_biomass,
+    DROP COLUMN IF EXISTS income_gene_seeds;
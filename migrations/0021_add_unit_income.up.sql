@@ -0,0 +1,8 @@
+-- +migrate Up
+ALTER TABLE unit_defs
+    ADD COLUMN income_energy     INT NOT NULL DEFAULT 0 CHECK (income_energy >= 0),
+    ADD COLUMN income_biomass    INT NOT NULL DEFAULT 0 CHECK (income_biomass >= 0),
+    ADD COLUMN income_gene_seeds INT NOT NULL DEFAULT 0 CHECK (income_gene_seeds >= 0);
+
+-- Seeders cultivate the field: +1 energy, +1 biomass per turn alive
+UPDATE unit_defs SET income_energy = 1, income_biomass = 1 WHERE id = 'Seeder';
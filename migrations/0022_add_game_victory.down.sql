@@ -0,0 +1,5 @@
+-- +migrate Down
+ALTER TABLE games
+    DROP COLUMN IF EXISTS score_breakdown,
+    DROP COLUMN IF EXISTS victory_rule,
+    DROP COLUMN IF EXISTS mode;
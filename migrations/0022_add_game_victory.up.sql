@@ -0,0 +1,6 @@
+-- +migrate Up
+ALTER TABLE games
+    ADD COLUMN mode            TEXT  NOT NULL DEFAULT 'duel'
+        CHECK (mode IN ('duel', 'siege', 'skirmish')),
+    ADD COLUMN victory_rule    TEXT,
+    ADD COLUMN score_breakdown JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,3 @@
+-- +migrate Down
+DROP TABLE IF EXISTS trade_offer_items;
+DROP TABLE IF EXISTS trade_offers;
@@ -0,0 +1,28 @@
+-- +migrate Up
+-- Two-party trade offers. The proposer's side (`offer_*`) is escrowed when
+-- the offer is made; the recipient's side (`ask_*`) is taken on accept.
+CREATE TABLE trade_offers (
+  id             UUID PRIMARY KEY     DEFAULT uuid_generate_v4(),
+  proposer_id    UUID NOT NULL        REFERENCES players(id) ON DELETE CASCADE,
+  recipient_id   UUID NOT NULL        REFERENCES players(id) ON DELETE CASCADE,
+  offer_credits  BIGINT NOT NULL      DEFAULT 0 CHECK (offer_credits >= 0),
+  ask_credits    BIGINT NOT NULL      DEFAULT 0 CHECK (ask_credits >= 0),
+  status         TEXT NOT NULL        DEFAULT 'open'
+                 CHECK (status IN ('open', 'accepted', 'cancelled', 'countered', 'expired')),
+  parent_id      UUID                 REFERENCES trade_offers(id) ON DELETE SET NULL,
+  created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at     TIMESTAMPTZ NOT NULL,
+  closed_at      TIMESTAMPTZ,
+  CHECK (proposer_id <> recipient_id)
+);
+CREATE INDEX trade_offers_proposer_idx  ON trade_offers(proposer_id)  WHERE status = 'open';
+CREATE INDEX trade_offers_recipient_idx ON trade_offers(recipient_id) WHERE status = 'open';
+CREATE INDEX trade_offers_expiry_idx    ON trade_offers(expires_at)   WHERE status = 'open';
+
+CREATE TABLE trade_offer_items (
+  offer_id  UUID NOT NULL REFERENCES trade_offers(id) ON DELETE CASCADE,
+  side      TEXT NOT NULL CHECK (side IN ('offer', 'ask')),
+  item_id   INT  NOT NULL REFERENCES items(id),
+  qty       INT  NOT NULL CHECK (qty > 0),
+  PRIMARY KEY (offer_id, side, item_id)
+);
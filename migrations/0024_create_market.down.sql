@@ -0,0 +1,8 @@
+-- +migrate Down
+DROP TABLE IF EXISTS economy_sinks;
+ALTER TABLE trades
+    DROP COLUMN IF EXISTS fee,
+    DROP COLUMN IF EXISTS sell_order_id,
+    DROP COLUMN IF EXISTS buy_order_id,
+    ALTER COLUMN price TYPE INT;
+DROP TABLE IF EXISTS market_orders;
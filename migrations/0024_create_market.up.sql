@@ -0,0 +1,34 @@
+-- +migrate Up
+-- Player marketplace: one limit order book per item. Buy orders escrow
+-- `price * qty` credits, sell orders escrow the items; fills are written
+-- to `trades` and the fee is moved into the `market_fees` sink.
+CREATE TABLE market_orders (
+  id          UUID PRIMARY KEY     DEFAULT uuid_generate_v4(),
+  player_id   UUID NOT NULL        REFERENCES players(id) ON DELETE CASCADE,
+  item_id     INT  NOT NULL        REFERENCES items(id),
+  side        TEXT NOT NULL        CHECK (side IN ('buy', 'sell')),
+  price       BIGINT NOT NULL      CHECK (price > 0),
+  qty         INT  NOT NULL        CHECK (qty > 0),
+  remaining   INT  NOT NULL        CHECK (remaining >= 0 AND remaining <= qty),
+  status      TEXT NOT NULL        DEFAULT 'open'
+              CHECK (status IN ('open', 'filled', 'cancelled')),
+  created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  closed_at   TIMESTAMPTZ
+);
+CREATE INDEX market_orders_book_idx   ON market_orders(item_id, side, price, created_at)
+  WHERE status = 'open';
+CREATE INDEX market_orders_player_idx ON market_orders(player_id) WHERE status = 'open';
+
+ALTER TABLE trades
+    ALTER COLUMN price TYPE BIGINT,
+    ADD COLUMN buy_order_id  UUID REFERENCES market_orders(id) ON DELETE SET NULL,
+    ADD COLUMN sell_order_id UUID REFERENCES market_orders(id) ON DELETE SET NULL,
+    ADD COLUMN fee           BIGINT NOT NULL DEFAULT 0 CHECK (fee >= 0);
+
+-- Credits removed from players by the economy (fees, taxes, …).
+CREATE TABLE economy_sinks (
+  name        TEXT PRIMARY KEY,
+  balance     BIGINT NOT NULL DEFAULT 0,
+  updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+INSERT INTO economy_sinks (name) VALUES ('market_fees');
@@ -0,0 +1,33 @@
+-- +migrate Up
+-- Per-item shop price curve. Items without a row use the defaults in
+-- `pricing::Curve::default_for`.
+CREATE TABLE item_price_curves (
+  item_id     INT PRIMARY KEY  REFERENCES items(id) ON DELETE CASCADE,
+  floor       INT NOT NULL     CHECK (floor > 0),
+  ceiling     INT NOT NULL,
+  -- relative price change per unit of net (buy − sell) volume
+  elasticity  DOUBLE PRECISION NOT NULL CHECK (elasticity >= 0),
+  -- per-hour exponential decay of past volume (pulls price back to base)
+  decay       DOUBLE PRECISION NOT NULL CHECK (decay >= 0),
+  CHECK (ceiling >= floor)
+);
+
+-- Every shop buy/sell; recent rows drive the price curve.
+CREATE TABLE shop_transactions (
+  id          BIGSERIAL PRIMARY KEY,
+  item_id     INT  NOT NULL        REFERENCES items(id) ON DELETE CASCADE,
+  player_id   UUID NOT NULL        REFERENCES players(id) ON DELETE CASCADE,
+  side        TEXT NOT NULL        CHECK (side IN ('buy', 'sell')),
+  qty         INT  NOT NULL        CHECK (qty > 0),
+  unit_price  INT  NOT NULL,
+  created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX shop_transactions_item_time_idx ON shop_transactions(item_id, created_at);
+
+-- Periodic snapshots of the shop price.
+CREATE TABLE price_history (
+  item_id      INT NOT NULL         REFERENCES items(id) ON DELETE CASCADE,
+  price        INT NOT NULL,
+  recorded_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (item_id, recorded_at)
+);
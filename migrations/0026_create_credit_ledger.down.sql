@@ -0,0 +1,5 @@
+-- +migrate Down
+DROP TABLE IF EXISTS ledger_drift;
+DROP TABLE IF EXISTS credit_ledger;
+DROP FUNCTION IF EXISTS credit_ledger_balanced();
+DROP FUNCTION IF EXISTS credit_ledger_append_only();
@@ -0,0 +1,68 @@
+-- +migrate Up
+-- Double-entry credits ledger. Every movement is a transaction (`txn_id`)
+-- of entries that sum to zero; an entry belongs to either a player or a
+-- system account. `players.credits` is the cached sum of a player's
+-- entries. No FK on player_id: history outlives deleted players.
+CREATE TABLE credit_ledger (
+  id          BIGSERIAL PRIMARY KEY,
+  txn_id      UUID NOT NULL,
+  player_id   UUID,
+  system      TEXT CHECK (system IN ('shop', 'escrow', 'market_fees', 'rewards', 'admin')),
+  amount      BIGINT NOT NULL CHECK (amount <> 0),
+  reason      TEXT NOT NULL CHECK (reason IN ('shop', 'trade', 'reward', 'fee', 'admin')),
+  ref_id      TEXT,
+  created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  CHECK ((player_id IS NULL) <> (system IS NULL))
+);
+CREATE INDEX credit_ledger_player_idx ON credit_ledger(player_id, id) WHERE player_id IS NOT NULL;
+CREATE INDEX credit_ledger_txn_idx    ON credit_ledger(txn_id);
+
+-- entries are never edited or removed
+CREATE OR REPLACE FUNCTION credit_ledger_append_only()
+RETURNS TRIGGER AS $$
+BEGIN
+  RAISE EXCEPTION 'credit_ledger is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER credit_ledger_append_only
+  BEFORE UPDATE OR DELETE ON credit_ledger
+  FOR EACH ROW
+  EXECUTE PROCEDURE credit_ledger_append_only();
+
+-- each transaction must balance by commit time
+CREATE OR REPLACE FUNCTION credit_ledger_balanced()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF (SELECT SUM(amount) FROM credit_ledger WHERE txn_id = NEW.txn_id) <> 0 THEN
+    RAISE EXCEPTION 'unbalanced credit_ledger transaction %', NEW.txn_id;
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER credit_ledger_balanced
+  AFTER INSERT ON credit_ledger
+  DEFERRABLE INITIALLY DEFERRED
+  FOR EACH ROW
+  EXECUTE PROCEDURE credit_ledger_balanced();
+
+-- Balances found by the reconciliation job that disagree with the ledger.
+CREATE TABLE ledger_drift (
+  id           BIGSERIAL PRIMARY KEY,
+  player_id    UUID,
+  system       TEXT,
+  cached       BIGINT NOT NULL,
+  ledger       BIGINT NOT NULL,
+  detected_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Opening balances for credits that predate the ledger.
+WITH opening AS (
+  SELECT id AS player_id, credits, uuid_generate_v4() AS txn_id
+    FROM players WHERE credits <> 0
+)
+INSERT INTO credit_ledger (txn_id, player_id, system, amount, reason, ref_id)
+SELECT txn_id, player_id, NULL, credits, 'admin', 'opening_balance' FROM opening
+UNION ALL
+SELECT txn_id, NULL, 'admin', -credits, 'admin', 'opening_balance' FROM opening;
@@ -0,0 +1,15 @@
+-- +migrate Up
+-- Stored outcomes of requests sent with an `Idempotency-Key` header, per
+-- player. `status` is NULL while the first request is still running.
+CREATE TABLE idempotency_keys (
+  player_id      UUID NOT NULL        REFERENCES players(id) ON DELETE CASCADE,
+  key            TEXT NOT NULL,
+  request_hash   TEXT NOT NULL,
+  status         SMALLINT,
+  content_type   TEXT,
+  response_body  BYTEA,
+  created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  expires_at     TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (player_id, key)
+);
+CREATE INDEX idempotency_keys_expiry_idx ON idempotency_keys(expires_at);
@@ -0,0 +1,5 @@
+-- +migrate Down
+DROP TABLE IF EXISTS duel_boosts;
+DROP TABLE IF EXISTS player_unit_unlocks;
+ALTER TABLE unit_defs DROP COLUMN IF EXISTS requires_unlock;
+ALTER TABLE items DROP COLUMN IF EXISTS effects;
@@ -0,0 +1,24 @@
+-- +migrate Up
+-- What using an item does: a JSON array of effects (see `server/src/effects`).
+ALTER TABLE items
+  ADD COLUMN effects JSONB NOT NULL DEFAULT '[]'::jsonb
+  CHECK (jsonb_typeof(effects) = 'array');
+
+-- Unit types that must be unlocked (e.g. by an item) before deploying.
+ALTER TABLE unit_defs
+  ADD COLUMN requires_unlock BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE player_unit_unlocks (
+  player_id    UUID NOT NULL REFERENCES players(id)   ON DELETE CASCADE,
+  unit_type    TEXT NOT NULL REFERENCES unit_defs(id) ON DELETE CASCADE,
+  unlocked_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (player_id, unit_type)
+);
+
+-- Resources queued by items for the player's next duel; consumed at its start.
+CREATE TABLE duel_boosts (
+  player_id   UUID PRIMARY KEY REFERENCES players(id) ON DELETE CASCADE,
+  energy      INT NOT NULL DEFAULT 0 CHECK (energy >= 0),
+  biomass     INT NOT NULL DEFAULT 0 CHECK (biomass >= 0),
+  gene_seeds  INT NOT NULL DEFAULT 0 CHECK (gene_seeds >= 0)
+);
@@ -0,0 +1,8 @@
+-- +migrate Down
+ALTER TABLE items
+  DROP COLUMN IF EXISTS icon_key,
+  DROP COLUMN IF EXISTS soulbound,
+  DROP COLUMN IF EXISTS tradable,
+  DROP COLUMN IF EXISTS max_stack,
+  DROP COLUMN IF EXISTS rarity,
+  DROP COLUMN IF EXISTS category;
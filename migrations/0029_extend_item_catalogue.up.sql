@@ -0,0 +1,16 @@
+-- +migrate Up
+-- Richer catalogue: category, rarity, stacking and binding rules, icon.
+ALTER TABLE items
+  ADD COLUMN category  TEXT    NOT NULL DEFAULT 'material'
+    CHECK (category IN ('consumable', 'material', 'cosmetic', 'blueprint')),
+  ADD COLUMN rarity    TEXT    NOT NULL DEFAULT 'common'
+    CHECK (rarity IN ('common', 'uncommon', 'rare', 'epic', 'legendary')),
+  ADD COLUMN max_stack INT     NOT NULL DEFAULT 999 CHECK (max_stack > 0),
+  -- May change hands between players (trade offers, market).
+  ADD COLUMN tradable  BOOLEAN NOT NULL DEFAULT TRUE,
+  -- Bound to its owner: no trading and no selling back to the shop.
+  ADD COLUMN soulbound BOOLEAN NOT NULL DEFAULT FALSE,
+  ADD COLUMN icon_key  TEXT;
+
+-- Items that do something when used are consumables.
+UPDATE items SET category = 'consumable' WHERE jsonb_array_length(effects) > 0;
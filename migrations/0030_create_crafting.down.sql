@@ -0,0 +1,5 @@
+-- +migrate Down
+DROP TABLE IF EXISTS craft_jobs;
+DROP TABLE IF EXISTS recipe_outputs;
+DROP TABLE IF EXISTS recipe_inputs;
+DROP TABLE IF EXISTS recipes;
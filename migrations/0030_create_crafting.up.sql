@@ -0,0 +1,41 @@
+-- +migrate Up
+-- Crafting: a recipe turns input items into output items and/or a unit
+-- unlock. Inputs are consumed when a job starts; outputs are handed over
+-- when the player claims the finished job.
+CREATE TABLE recipes (
+  id                  SERIAL PRIMARY KEY,
+  name                TEXT NOT NULL UNIQUE,
+  description         TEXT,
+  -- Structure type the crafter must own on a land parcel, if any.
+  required_structure  TEXT,
+  craft_secs          INT  NOT NULL DEFAULT 0 CHECK (craft_secs >= 0),
+  unlock_unit         TEXT REFERENCES unit_defs(id) ON DELETE SET NULL
+);
+
+CREATE TABLE recipe_inputs (
+  recipe_id  INT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+  item_id    INT NOT NULL REFERENCES items(id),
+  qty        INT NOT NULL CHECK (qty > 0),
+  PRIMARY KEY (recipe_id, item_id)
+);
+
+CREATE TABLE recipe_outputs (
+  recipe_id  INT NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+  item_id    INT NOT NULL REFERENCES items(id),
+  qty        INT NOT NULL CHECK (qty > 0),
+  PRIMARY KEY (recipe_id, item_id)
+);
+
+CREATE TABLE craft_jobs (
+  id            UUID PRIMARY KEY     DEFAULT uuid_generate_v4(),
+  player_id     UUID NOT NULL        REFERENCES players(id) ON DELETE CASCADE,
+  recipe_id     INT  NOT NULL        REFERENCES recipes(id),
+  structure_id  INT                  REFERENCES structures(id) ON DELETE SET NULL,
+  status        TEXT NOT NULL        DEFAULT 'in_progress'
+                CHECK (status IN ('in_progress', 'ready', 'claimed')),
+  started_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  ready_at      TIMESTAMPTZ NOT NULL,
+  claimed_at    TIMESTAMPTZ
+);
+CREATE INDEX craft_jobs_player_idx ON craft_jobs(player_id) WHERE status <> 'claimed';
+CREATE INDEX craft_jobs_due_idx    ON craft_jobs(ready_at)  WHERE status = 'in_progress';
@@ -0,0 +1,4 @@
+-- +migrate Down
+DROP TABLE IF EXISTS match_rewards;
+DROP TABLE IF EXISTS loot_entries;
+DROP TABLE IF EXISTS loot_tables;
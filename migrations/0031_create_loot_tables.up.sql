@@ -0,0 +1,45 @@
+-- +migrate Up
+-- Match rewards. A loot table applies to one game mode (NULL = any), one
+-- outcome and a rating band; the most specific match wins. Each finished
+-- game rolls one table per seat with a seed derived from the game and
+-- player ids, recorded in `match_rewards` so any roll can be replayed.
+CREATE TABLE loot_tables (
+  id           SERIAL PRIMARY KEY,
+  mode         TEXT   CHECK (mode IN ('duel', 'siege', 'skirmish')),
+  outcome      TEXT   NOT NULL CHECK (outcome IN ('win', 'draw', 'loss')),
+  min_rating   INT    NOT NULL DEFAULT 0,
+  max_rating   INT,
+  credits_min  BIGINT NOT NULL DEFAULT 0 CHECK (credits_min >= 0),
+  credits_max  BIGINT NOT NULL DEFAULT 0 CHECK (credits_max >= credits_min),
+  -- Item drops rolled from `loot_entries`.
+  rolls        INT    NOT NULL DEFAULT 1 CHECK (rolls >= 0),
+  CHECK (max_rating IS NULL OR max_rating >= min_rating)
+);
+
+-- Weighted drops; a NULL item is an empty slot.
+CREATE TABLE loot_entries (
+  id        SERIAL PRIMARY KEY,
+  table_id  INT NOT NULL REFERENCES loot_tables(id) ON DELETE CASCADE,
+  item_id   INT REFERENCES items(id),
+  weight    INT NOT NULL CHECK (weight > 0),
+  qty_min   INT NOT NULL DEFAULT 1 CHECK (qty_min > 0),
+  qty_max   INT NOT NULL DEFAULT 1 CHECK (qty_max >= qty_min)
+);
+CREATE INDEX loot_entries_table_idx ON loot_entries(table_id);
+
+CREATE TABLE match_rewards (
+  game_id     UUID   NOT NULL REFERENCES games(id)   ON DELETE CASCADE,
+  player_id   UUID   NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+  table_id    INT    REFERENCES loot_tables(id) ON DELETE SET NULL,
+  seed        BIGINT NOT NULL,
+  credits     BIGINT NOT NULL,
+  items       JSONB  NOT NULL DEFAULT '[]'::jsonb,
+  awarded_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (game_id, player_id)
+);
+
+-- Defaults for every mode and rating.
+INSERT INTO loot_tables (mode, outcome, credits_min, credits_max, rolls) VALUES
+  (NULL, 'win',  40, 80, 1),
+  (NULL, 'draw', 20, 40, 0),
+  (NULL, 'loss', 5,  15, 0);
@@ -0,0 +1,3 @@
+-- +migrate Down
+DROP TABLE IF EXISTS player_quests;
+DROP TABLE IF EXISTS quests;
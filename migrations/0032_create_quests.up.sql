@@ -0,0 +1,41 @@
+-- +migrate Up
+-- Quests and achievements. Each definition counts one event kind (see
+-- `server/src/progression`) up to `target`. Daily quests rotate at UTC
+-- midnight: a seeded pick of `kind = 'daily'` rows is offered each day and
+-- progress is kept per day. Achievements count for the player's lifetime
+-- and use the fixed period 1970-01-01.
+CREATE TABLE quests (
+  id              TEXT PRIMARY KEY,
+  kind            TEXT   NOT NULL CHECK (kind IN ('daily', 'achievement')),
+  name            TEXT   NOT NULL,
+  description     TEXT,
+  event           TEXT   NOT NULL,
+  target          INT    NOT NULL CHECK (target > 0),
+  reward_credits  BIGINT NOT NULL DEFAULT 0 CHECK (reward_credits >= 0),
+  reward_item_id  INT    REFERENCES items(id),
+  reward_qty      INT    NOT NULL DEFAULT 0 CHECK (reward_qty >= 0),
+  active          BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE TABLE player_quests (
+  player_id     UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+  quest_id      TEXT NOT NULL REFERENCES quests(id)  ON DELETE CASCADE,
+  period        DATE NOT NULL,
+  progress      INT  NOT NULL DEFAULT 0 CHECK (progress >= 0),
+  completed_at  TIMESTAMPTZ,
+  claimed_at    TIMESTAMPTZ,
+  PRIMARY KEY (player_id, quest_id, period)
+);
+
+INSERT INTO quests (id, kind, name, event, target, reward_credits) VALUES
+  ('daily_play_3',       'daily',       'Warm-up',         'match_played',     3,  30),
+  ('daily_win_1',        'daily',       'First blood',     'match_won',        1,  40),
+  ('daily_turns_20',     'daily',       'Tactician',       'turn_played',      20, 25),
+  ('daily_trade_1',      'daily',       'Merchant',        'trade_completed',  1,  25),
+  ('daily_chat_5',       'daily',       'Diplomat',        'chat_sent',        5,  10),
+  ('daily_build_1',      'daily',       'Architect',       'structure_built',  1,  30),
+  ('ach_first_win',      'achievement', 'Victorious',      'match_won',        1,  100),
+  ('ach_wins_50',        'achievement', 'Veteran',         'match_won',        50, 1000),
+  ('ach_trades_25',      'achievement', 'Trade baron',     'trade_completed',  25, 500),
+  ('ach_first_claim',    'achievement', 'Settler',         'land_claimed',     1,  150),
+  ('ach_structures_10',  'achievement', 'Master builder',  'structure_built',  10, 400);
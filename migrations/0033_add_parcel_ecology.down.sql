@@ -0,0 +1,7 @@
+-- +migrate Down
+DROP TABLE IF EXISTS structure_eco_effects;
+ALTER TABLE land_parcels
+  DROP COLUMN IF EXISTS population,
+  DROP COLUMN IF EXISTS pollution,
+  DROP COLUMN IF EXISTS biomass,
+  DROP COLUMN IF EXISTS fertility;
@@ -0,0 +1,25 @@
+-- +migrate Up
+-- World model state per parcel, evolved by `ecosystem::simulation`.
+ALTER TABLE land_parcels
+  ADD COLUMN fertility   DOUBLE PRECISION NOT NULL DEFAULT 0.5
+    CHECK (fertility BETWEEN 0 AND 1),
+  ADD COLUMN biomass     DOUBLE PRECISION NOT NULL DEFAULT 10 CHECK (biomass >= 0),
+  ADD COLUMN pollution   DOUBLE PRECISION NOT NULL DEFAULT 0
+    CHECK (pollution BETWEEN 0 AND 1),
+  ADD COLUMN population  DOUBLE PRECISION NOT NULL DEFAULT 10 CHECK (population >= 0);
+
+-- How each structure type changes the parcel it stands on, per tick.
+-- Types without a row have no ecological effect.
+CREATE TABLE structure_eco_effects (
+  structure_type  TEXT PRIMARY KEY,
+  pollution       DOUBLE PRECISION NOT NULL DEFAULT 0,
+  fertility       DOUBLE PRECISION NOT NULL DEFAULT 0,
+  growth_mult     DOUBLE PRECISION NOT NULL DEFAULT 1 CHECK (growth_mult >= 0)
+);
+
+INSERT INTO structure_eco_effects (structure_type, pollution, fertility, growth_mult) VALUES
+  ('Extractor',  0.020, -0.010, 1.00),
+  ('Factory',    0.030, -0.005, 0.90),
+  ('Purifier',  -0.030,  0.000, 1.00),
+  ('Greenhouse', 0.000,  0.005, 1.25),
+  ('Nursery',    0.000,  0.010, 1.10);
@@ -0,0 +1,5 @@
+-- +migrate Down
+DROP TABLE IF EXISTS structure_costs;
+DROP TABLE IF EXISTS structure_yields;
+DROP TABLE IF EXISTS faction_treasury_ledger;
+DROP TABLE IF EXISTS faction_treasuries;
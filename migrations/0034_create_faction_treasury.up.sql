@@ -0,0 +1,51 @@
+-- +migrate Up
+-- Faction resource treasuries, filled by parcel yields on each world tick
+-- and spent on structures and wars. `faction_treasury_ledger` records every
+-- movement; yields of one tick share its `created_at`.
+CREATE TABLE faction_treasuries (
+  faction_id  UUID PRIMARY KEY REFERENCES factions(id) ON DELETE CASCADE,
+  energy      BIGINT NOT NULL DEFAULT 0 CHECK (energy >= 0),
+  biomass     BIGINT NOT NULL DEFAULT 0 CHECK (biomass >= 0),
+  gene_seeds  BIGINT NOT NULL DEFAULT 0 CHECK (gene_seeds >= 0),
+  updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE faction_treasury_ledger (
+  id          BIGSERIAL PRIMARY KEY,
+  faction_id  UUID NOT NULL REFERENCES factions(id) ON DELETE CASCADE,
+  resource    TEXT NOT NULL CHECK (resource IN ('energy', 'biomass', 'gene_seeds')),
+  amount      BIGINT NOT NULL CHECK (amount <> 0),
+  reason      TEXT NOT NULL CHECK (reason IN ('yield', 'structure', 'war', 'admin')),
+  ref_id      TEXT,
+  created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX faction_treasury_ledger_faction_idx
+  ON faction_treasury_ledger(faction_id, created_at);
+
+-- Yield multiplier of each structure type on the parcel it stands on;
+-- several structures multiply. Types without a row leave yield unchanged.
+CREATE TABLE structure_yields (
+  structure_type  TEXT PRIMARY KEY,
+  yield_mult      DOUBLE PRECISION NOT NULL CHECK (yield_mult >= 0)
+);
+
+INSERT INTO structure_yields (structure_type, yield_mult) VALUES
+  ('Extractor',  1.50),
+  ('Factory',    1.25),
+  ('Greenhouse', 1.20),
+  ('Nursery',    1.10);
+
+-- Treasury cost of building a structure. Types without a row can't be built.
+CREATE TABLE structure_costs (
+  structure_type  TEXT PRIMARY KEY,
+  energy          BIGINT NOT NULL DEFAULT 0 CHECK (energy >= 0),
+  biomass         BIGINT NOT NULL DEFAULT 0 CHECK (biomass >= 0),
+  gene_seeds      BIGINT NOT NULL DEFAULT 0 CHECK (gene_seeds >= 0)
+);
+
+INSERT INTO structure_costs (structure_type, energy, biomass, gene_seeds) VALUES
+  ('Extractor',  20, 10,  0),
+  ('Factory',    40, 20,  0),
+  ('Purifier',   15,  5,  5),
+  ('Greenhouse', 10, 25,  5),
+  ('Nursery',     5, 15, 10);
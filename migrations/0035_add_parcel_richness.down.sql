@@ -0,0 +1,2 @@
+-- +migrate Down
+ALTER TABLE land_parcels DROP COLUMN IF EXISTS rich;
@@ -0,0 +1,5 @@
+-- +migrate Up
+-- Parcels are materialised from the seeded world map (`ecosystem::worldgen`)
+-- on first claim; `rich` marks the rare resource-rich tiles.
+ALTER TABLE land_parcels
+  ADD COLUMN rich BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,14 @@
+-- +migrate Down
+DROP INDEX IF EXISTS land_parcels_owner_faction_idx;
+
+ALTER TABLE faction_treasury_ledger DROP CONSTRAINT faction_treasury_ledger_reason_check;
+ALTER TABLE faction_treasury_ledger ADD CONSTRAINT faction_treasury_ledger_reason_check
+  CHECK (reason IN ('yield', 'structure', 'war', 'admin'));
+
+ALTER TABLE credit_ledger DROP CONSTRAINT credit_ledger_reason_check;
+ALTER TABLE credit_ledger ADD CONSTRAINT credit_ledger_reason_check
+  CHECK (reason IN ('shop', 'trade', 'reward', 'fee', 'admin'));
+
+ALTER TABLE credit_ledger DROP CONSTRAINT credit_ledger_system_check;
+ALTER TABLE credit_ledger ADD CONSTRAINT credit_ledger_system_check
+  CHECK (system IN ('shop', 'escrow', 'market_fees', 'rewards', 'admin'));
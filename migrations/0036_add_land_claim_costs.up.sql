@@ -0,0 +1,17 @@
+-- +migrate Up
+-- Territory claims beyond a faction's first are paid for, either from the
+-- faction treasury or with the claiming player's credits (to the `land`
+-- system account).
+ALTER TABLE credit_ledger DROP CONSTRAINT credit_ledger_system_check;
+ALTER TABLE credit_ledger ADD CONSTRAINT credit_ledger_system_check
+  CHECK (system IN ('shop', 'escrow', 'market_fees', 'rewards', 'admin', 'land'));
+
+ALTER TABLE credit_ledger DROP CONSTRAINT credit_ledger_reason_check;
+ALTER TABLE credit_ledger ADD CONSTRAINT credit_ledger_reason_check
+  CHECK (reason IN ('shop', 'trade', 'reward', 'fee', 'admin', 'land'));
+
+ALTER TABLE faction_treasury_ledger DROP CONSTRAINT faction_treasury_ledger_reason_check;
+ALTER TABLE faction_treasury_ledger ADD CONSTRAINT faction_treasury_ledger_reason_check
+  CHECK (reason IN ('yield', 'structure', 'war', 'admin', 'land'));
+
+CREATE INDEX land_parcels_owner_faction_idx ON land_parcels(owner_faction_id);
@@ -0,0 +1,4 @@
+-- +migrate Down
+DROP TABLE IF EXISTS structure_defense;
+DROP TABLE IF EXISTS parcel_ownership_history;
+DROP TABLE IF EXISTS contests;
@@ -0,0 +1,67 @@
+-- +migrate Up
+-- Territory contests: a faction attacks a bordering enemy parcel. The
+-- defenders may answer within the response window by naming a champion
+-- (settled by a siege duel) or not (settled by strength). Ownership changes
+-- of every parcel are kept in `parcel_ownership_history`.
+CREATE TABLE contests (
+  id                   UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+  parcel_id            INT  NOT NULL REFERENCES land_parcels(id) ON DELETE CASCADE,
+  x                    INT  NOT NULL,
+  y                    INT  NOT NULL,
+  attacker_faction_id  UUID NOT NULL REFERENCES factions(id) ON DELETE CASCADE,
+  defender_faction_id  UUID NOT NULL REFERENCES factions(id) ON DELETE CASCADE,
+  declared_by          UUID REFERENCES players(id) ON DELETE SET NULL,
+  attacker_champion    UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+  defender_champion    UUID REFERENCES players(id) ON DELETE SET NULL,
+  -- energy the attacker committed from its treasury
+  energy               BIGINT NOT NULL CHECK (energy > 0),
+  status               TEXT NOT NULL DEFAULT 'open'
+                         CHECK (status IN ('open', 'dueling', 'resolved')),
+  method               TEXT CHECK (method IN ('duel', 'strength')),
+  game_id              UUID REFERENCES games(id) ON DELETE SET NULL,
+  respond_by           TIMESTAMPTZ NOT NULL,
+  duel_started_at      TIMESTAMPTZ,
+  winner_faction_id    UUID REFERENCES factions(id) ON DELETE SET NULL,
+  attack_strength      DOUBLE PRECISION,
+  defense_strength     DOUBLE PRECISION,
+  created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  resolved_at          TIMESTAMPTZ,
+  CHECK (attacker_faction_id <> defender_faction_id)
+);
+-- at most one live contest per parcel
+CREATE UNIQUE INDEX contests_live_parcel_idx
+  ON contests(parcel_id) WHERE status <> 'resolved';
+CREATE INDEX contests_attacker_idx ON contests(attacker_faction_id, created_at);
+CREATE INDEX contests_defender_idx ON contests(defender_faction_id, created_at);
+
+CREATE TABLE parcel_ownership_history (
+  id               BIGSERIAL PRIMARY KEY,
+  parcel_id        INT  NOT NULL REFERENCES land_parcels(id) ON DELETE CASCADE,
+  from_faction_id  UUID,
+  to_faction_id    UUID,
+  reason           TEXT NOT NULL CHECK (reason IN ('claim', 'contest')),
+  contest_id       UUID REFERENCES contests(id) ON DELETE SET NULL,
+  changed_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX parcel_ownership_history_parcel_idx
+  ON parcel_ownership_history(parcel_id, changed_at);
+
+-- Defense each structure type adds to the parcel it stands on. Types
+-- without a row add nothing.
+CREATE TABLE structure_defense (
+  structure_type  TEXT PRIMARY KEY,
+  defense         DOUBLE PRECISION NOT NULL CHECK (defense >= 0)
+);
+
+INSERT INTO structure_defense (structure_type, defense) VALUES
+  ('Wall',    10),
+  ('Turret',  15),
+  ('Bunker',  25),
+  ('Factory',  2);
+
+-- Left in place on down: the rows may predate this migration.
+INSERT INTO structure_costs (structure_type, energy, biomass, gene_seeds) VALUES
+  ('Wall',   10, 10, 0),
+  ('Turret', 25,  5, 0),
+  ('Bunker', 40, 20, 0)
+ON CONFLICT (structure_type) DO NOTHING;
@@ -0,0 +1,11 @@
+-- +migrate Up
+-- Duel items a player brought into a game. They leave `player_items` when
+-- the loadout is loaded, each use in the duel takes one, and whatever is
+-- left goes back to the player when the game ends.
+CREATE TABLE duel_item_escrow (
+  game_id    UUID NOT NULL REFERENCES games(id)   ON DELETE CASCADE,
+  player_id  UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+  item_id    INT  NOT NULL REFERENCES items(id)   ON DELETE CASCADE,
+  quantity   INT  NOT NULL CHECK (quantity >= 0),
+  PRIMARY KEY (game_id, player_id, item_id)
+);
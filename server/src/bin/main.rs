@@ -1,5 +1,8 @@
 use actix_web::{middleware::Logger, web, App, HttpServer};
-use biotonic_server::{cache, ecosystem, http, matchmaking, metrics, ws};
+use biotonic_server::{
+    cache, contests, crafting, ecosystem, http, ledger, matchmaking, metrics, pricing, progression,
+    trading, ws,
+};
 use redis::Client as RedisClient;
 use sqlx::postgres::PgPoolOptions;
 use std::env;
@@ -27,9 +30,16 @@ async fn main() -> std::io::Result<()> {
     // ───── Sprint 1: Warm in-memory caches ─────
     cache::warm_all(&db_pool).await;
 
-    // Start the background matchmaking & ecosystem loops
+    // Start the background matchmaking, ecosystem, trade-expiry, price,
+    // ledger, crafting, quest-progress & contest loops
     matchmaking::start(redis_client.clone(), db_pool.clone());
     ecosystem::simulation::start(db_pool.clone(), redis_client.clone());
+    trading::start(db_pool.clone(), redis_client.clone());
+    pricing::start(db_pool.clone());
+    ledger::start(db_pool.clone());
+    crafting::start(db_pool.clone(), redis_client.clone());
+    progression::start(db_pool.clone());
+    contests::start(db_pool.clone(), redis_client.clone());
 
     // Start HTTP + WS server
     HttpServer::new(move || {
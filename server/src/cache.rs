@@ -4,11 +4,108 @@
 //! read-only endpoints (shop catalogue, item look-ups) no longer hit Postgres
 //! on every request.  This keeps latency low and is an easy first-step perf
 //! win while we explore Redis or CDN-based caches later in Beta.
+//!
+//! The `unit_defs` catalogue lives here too; combat reads unit stats from it
+//! and admins can hot-reload it after a balance change. Active quest
+//! definitions are cached the same way for the progression worker.
 
 use once_cell::sync::Lazy;
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
+use crate::{
+    db::quest_repo::{self, QuestDef},
+    effects::{self, Effect},
+    game::types::{Ability, ResourcePool, UnitType},
+};
+
+/// What kind of thing an item is (`items.category`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemCategory {
+    Consumable,
+    Material,
+    Cosmetic,
+    Blueprint,
+}
+
+impl ItemCategory {
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "consumable" => ItemCategory::Consumable,
+            "material" => ItemCategory::Material,
+            "cosmetic" => ItemCategory::Cosmetic,
+            "blueprint" => ItemCategory::Blueprint,
+            _ => return None,
+        })
+    }
+}
+
+/// Rarity tier (`items.rarity`); orders from common to legendary.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "common" => Rarity::Common,
+            "uncommon" => Rarity::Uncommon,
+            "rare" => Rarity::Rare,
+            "epic" => Rarity::Epic,
+            "legendary" => Rarity::Legendary,
+            _ => return None,
+        })
+    }
+}
+
+/// Catalogue attributes shared by every item listing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ItemMeta {
+    pub category: ItemCategory,
+    pub rarity: Rarity,
+    /// Most of this item one player may hold.
+    pub max_stack: i32,
+    /// May change hands between players (trade offers, market).
+    pub tradable: bool,
+    /// Bound to its owner: no trading and no selling back to the shop.
+    pub soulbound: bool,
+    pub icon_key: Option<String>,
+}
+
+impl ItemMeta {
+    /// Build from raw `items` columns (the table’s CHECKs keep them valid).
+    pub fn from_row(
+        category: &str,
+        rarity: &str,
+        max_stack: i32,
+        tradable: bool,
+        soulbound: bool,
+        icon_key: Option<String>,
+    ) -> Self {
+        ItemMeta {
+            category: ItemCategory::parse(category).unwrap_or(ItemCategory::Material),
+            rarity: Rarity::parse(rarity).unwrap_or(Rarity::Common),
+            max_stack,
+            tradable,
+            soulbound,
+            icon_key,
+        }
+    }
+
+    /// Whether players may swap it between themselves.
+    pub fn player_tradable(&self) -> bool {
+        self.tradable && !self.soulbound
+    }
+}
+
 /// One immutable row from the `items` table.
 #[derive(Debug, Clone)]
 pub struct ItemDef {
@@ -16,6 +113,8 @@ pub struct ItemDef {
     pub name: String,
     pub description: Option<String>,
     pub base_price: i32,
+    pub effects: Vec<Effect>,
+    pub meta: ItemMeta,
 }
 
 /// Global map id → ItemDef (read-only once warmed).
@@ -24,12 +123,18 @@ pub static ITEMS: Lazy<DashMap<i32, ItemDef>> = Lazy::new(DashMap::new);
 /// Fetch the `items` table and populate [`ITEMS`]. Idempotent.
 pub async fn warm_items(db: &PgPool) -> anyhow::Result<()> {
     let rows = sqlx::query!(
-        r#"SELECT id, name, description, base_price FROM items"#
+        r#"SELECT id, name, description, base_price, effects,
+                  category, rarity, max_stack, tradable, soulbound, icon_key
+             FROM items"#
     )
     .fetch_all(db)
     .await?;
 
     for r in rows {
+        let effects = effects::parse(r.effects).unwrap_or_else(|e| {
+            log::warn!("items {}: bad effects: {e}", r.id);
+            Vec::new()
+        });
         ITEMS.insert(
             r.id,
             ItemDef {
@@ -37,6 +142,15 @@ pub async fn warm_items(db: &PgPool) -> anyhow::Result<()> {
                 name: r.name,
                 description: r.description,
                 base_price: r.base_price,
+                effects,
+                meta: ItemMeta::from_row(
+                    &r.category,
+                    &r.rarity,
+                    r.max_stack,
+                    r.tradable,
+                    r.soulbound,
+                    r.icon_key,
+                ),
             },
         );
     }
@@ -48,9 +162,121 @@ pub fn get_item(id: i32) -> Option<ItemDef> {
     ITEMS.get(&id).map(|e| e.value().clone())
 }
 
+/// Resources needed to spawn one unit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnitCost {
+    pub energy: u32,
+    pub biomass: u32,
+    pub gene_seeds: u32,
+}
+
+/// One row from the `unit_defs` table.
+#[derive(Debug, Clone)]
+pub struct UnitDef {
+    pub id: UnitType,
+    pub cost: UnitCost,
+    pub atk: u32,
+    pub hp: u32,
+    /// Initiative and max cells moved per turn.
+    pub speed: u32,
+    /// Max distance to an attack target.
+    pub range: u32,
+    pub abilities: Vec<Ability>,
+    /// Added to the owner’s pool every turn the unit is alive.
+    pub income: ResourcePool,
+    /// Only deployable by players who unlocked it (see `effects`).
+    pub requires_unlock: bool,
+}
+
+/// Global map unit type → UnitDef.
+pub static UNIT_DEFS: Lazy<DashMap<UnitType, UnitDef>> = Lazy::new(DashMap::new);
+
+/// (Re)load the `unit_defs` table into [`UNIT_DEFS`] and return the row count.
+///
+/// Rows are upserted before stale ids are dropped, so lookups never see an
+/// empty catalogue mid-reload.
+pub async fn warm_unit_defs(db: &PgPool) -> anyhow::Result<usize> {
+    let rows = sqlx::query!(
+        r#"SELECT id, cost_energy, cost_biomass, cost_gene_seeds,
+                  atk, hp, speed, attack_range, abilities,
+                  income_energy, income_biomass, income_gene_seeds, requires_unlock
+             FROM unit_defs"#
+    )
+    .fetch_all(db)
+    .await?;
+
+    let n = |v: i32| u32::try_from(v).unwrap_or(0);
+    let mut seen = Vec::with_capacity(rows.len());
+    for r in rows {
+        let abilities = r
+            .abilities
+            .iter()
+            .filter_map(|tag| match serde_json::from_value(tag.as_str().into()) {
+                Ok(a) => Some(a),
+                Err(_) => {
+                    log::warn!("unit_defs {}: unknown ability tag {tag:?}", r.id);
+                    None
+                }
+            })
+            .collect();
+        let id = UnitType::new(r.id);
+        seen.push(id.clone());
+        UNIT_DEFS.insert(
+            id.clone(),
+            UnitDef {
+                id,
+                cost: UnitCost {
+                    energy: n(r.cost_energy),
+                    biomass: n(r.cost_biomass),
+                    gene_seeds: n(r.cost_gene_seeds),
+                },
+                atk: n(r.atk),
+                hp: n(r.hp),
+                speed: n(r.speed),
+                range: n(r.attack_range),
+                abilities,
+                income: ResourcePool {
+                    energy: n(r.income_energy),
+                    biomass: n(r.income_biomass),
+                    gene_seeds: n(r.income_gene_seeds),
+                },
+                requires_unlock: r.requires_unlock,
+            },
+        );
+    }
+    UNIT_DEFS.retain(|k, _| seen.contains(k));
+    Ok(seen.len())
+}
+
+/// Retrieve a cached unit definition by type.
+pub fn get_unit_def(id: &UnitType) -> Option<UnitDef> {
+    UNIT_DEFS.get(id).map(|e| e.value().clone())
+}
+
+/// Global map quest id → active QuestDef.
+pub static QUESTS: Lazy<DashMap<String, QuestDef>> = Lazy::new(DashMap::new);
+
+/// (Re)load the active `quests` rows into [`QUESTS`] and return the count.
+/// Upserts before dropping stale ids, like [`warm_unit_defs`].
+pub async fn warm_quests(db: &PgPool) -> anyhow::Result<usize> {
+    let defs = quest_repo::active_defs(db).await?;
+    let seen: Vec<String> = defs.iter().map(|d| d.id.clone()).collect();
+    for def in defs {
+        QUESTS.insert(def.id.clone(), def);
+    }
+    QUESTS.retain(|k, _| seen.contains(k));
+    Ok(seen.len())
+}
+
 /// Warm every in-memory cache we have (called once at startup).
 pub async fn warm_all(db: &PgPool) {
     if let Err(e) = warm_items(db).await {
         log::warn!("cache warm-up failed: {e:?}");
     }
+    if let Err(e) = warm_unit_defs(db).await {
+        log::warn!("unit catalogue warm-up failed: {e:?}");
+    }
+    if let Err(e) = warm_quests(db).await {
+        log::warn!("quest catalogue warm-up failed: {e:?}");
+    }
 }
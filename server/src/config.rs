@@ -11,6 +11,35 @@ pub struct Settings {
     pub presence_ttl: u64,
     /// Seconds a player may stay disconnected before forfeit.
     pub disconnect_grace: u64,
+    /// Seconds a connected player has to submit each turn.
+    pub turn_timeout: u64,
+    /// Consecutive timed-out turns before forfeit.
+    pub max_timeouts: u32,
+    /// Client frames accepted per socket per second.
+    pub ws_rate_limit: u32,
+    /// Seconds a trade offer stays open before its escrow is refunded.
+    pub trade_offer_ttl: u64,
+    /// Market fee on each fill, in basis points of the gross price.
+    pub market_fee_bps: u32,
+    /// Seconds between shop price-history snapshots.
+    pub price_snapshot_secs: u64,
+    /// Seconds between credit-ledger reconciliation passes.
+    pub ledger_reconcile_secs: u64,
+    /// Daily quests offered per UTC day.
+    pub daily_quests: u32,
+    /// Seed of the generated world map.
+    pub world_seed: u64,
+    /// World map size in tiles (`0..width` × `0..height`).
+    pub world_width: i32,
+    pub world_height: i32,
+    /// Seconds defenders have to answer a territory contest (and champions
+    /// to start their duel).
+    pub contest_response_secs: u64,
+    /// Seconds a champions’ duel may run past that before its contest is
+    /// settled by strength instead.
+    pub contest_duel_secs: u64,
+    /// Key for match-reward loot seeds; keep it private.
+    pub loot_secret: String,
 }
 
 impl Settings {
@@ -30,10 +59,96 @@ impl Settings {
             .and_then(|v| v.parse::<u64>().ok())
             .unwrap_or(120); // 2 min default
 
+        let turn_timeout = env::var("TURN_TIMEOUT")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(60);
+
+        let max_timeouts = env::var("MAX_TIMEOUTS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(3);
+
+        let ws_rate_limit = env::var("WS_RATE_LIMIT")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(20);
+
+        let trade_offer_ttl = env::var("TRADE_OFFER_TTL")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(86_400); // 24 h
+
+        let market_fee_bps = env::var("MARKET_FEE_BPS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(200); // 2 %
+
+        let price_snapshot_secs = env::var("PRICE_SNAPSHOT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(300);
+
+        let ledger_reconcile_secs = env::var("LEDGER_RECONCILE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(3_600);
+
+        let daily_quests = env::var("DAILY_QUESTS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(3);
+
+        let world_seed = env::var("WORLD_SEED")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(42);
+
+        let world_width = env::var("WORLD_WIDTH")
+            .ok()
+            .and_then(|v| v.parse::<i32>().ok())
+            .filter(|&v| v > 0)
+            .unwrap_or(128);
+
+        let world_height = env::var("WORLD_HEIGHT")
+            .ok()
+            .and_then(|v| v.parse::<i32>().ok())
+            .filter(|&v| v > 0)
+            .unwrap_or(128);
+
+        let contest_response_secs = env::var("CONTEST_RESPONSE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(3_600);
+
+        let contest_duel_secs = env::var("CONTEST_DUEL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(3_600);
+
+        let loot_secret = env::var("LOOT_SECRET").unwrap_or_default();
+        if loot_secret.is_empty() {
+            log::warn!("LOOT_SECRET is not set; match-reward rolls are predictable");
+        }
+
         Settings {
             max_turns,
             presence_ttl,
             disconnect_grace,
+            turn_timeout,
+            max_timeouts,
+            ws_rate_limit,
+            trade_offer_ttl,
+            market_fee_bps,
+            price_snapshot_secs,
+            ledger_reconcile_secs,
+            daily_quests,
+            world_seed,
+            world_width,
+            world_height,
+            contest_response_secs,
+            contest_duel_secs,
+            loot_secret,
         }
     }
 }
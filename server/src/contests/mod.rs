@@ -0,0 +1,72 @@
+//! Territory-contest notifications and the worker that settles due
+//! contests.
+//
+//  Redis channels
+//  --------------
+//  player:<player_id>:events – `ServerMsg::ContestDeclared` / `ContestDuel`
+//                              / `ContestResolved`
+
+use chrono::Duration as ChronoDuration;
+use redis::Client as RedisClient;
+use sqlx::PgPool;
+use tokio::time::{sleep, Duration};
+use uuid::Uuid;
+
+use crate::{
+    config::settings,
+    db::{contest_repo, faction_repo},
+    protocol::ServerMsg,
+    trading,
+};
+
+/// How long defenders have to respond, and champions to start their duel.
+pub fn response_window() -> ChronoDuration {
+    ChronoDuration::seconds(settings().contest_response_secs as i64)
+}
+
+/// Longest a started duel may run before its contest is settled anyway.
+pub fn duel_limit() -> ChronoDuration {
+    ChronoDuration::seconds(settings().contest_duel_secs as i64)
+}
+
+/// Best-effort push to every member of `faction`.
+pub async fn notify_faction(db: &PgPool, redis: &RedisClient, faction: Uuid, msg: &ServerMsg) {
+    match faction_repo::member_ids(db, faction).await {
+        Ok(members) => {
+            for player in members {
+                trading::notify(redis, player, msg).await;
+            }
+        }
+        Err(e) => log::warn!("notifying faction {faction} failed: {e:?}"),
+    }
+}
+
+/// Tell both sides how `contest` ended.
+pub async fn notify_resolved(db: &PgPool, redis: &RedisClient, contest: &contest_repo::Contest) {
+    let msg = ServerMsg::ContestResolved {
+        contest: contest.clone(),
+    };
+    notify_faction(db, redis, contest.attacker_faction_id, &msg).await;
+    notify_faction(db, redis, contest.defender_faction_id, &msg).await;
+}
+
+async fn tick(db: &PgPool, redis: &RedisClient) {
+    match contest_repo::resolve_due(db, response_window(), duel_limit()).await {
+        Ok(done) => {
+            for contest in &done {
+                notify_resolved(db, redis, contest).await;
+            }
+        }
+        Err(e) => log::error!("contest sweep failed: {e:?}"),
+    }
+}
+
+/// Spawn the loop that settles due contests.
+pub fn start(db: PgPool, redis: RedisClient) {
+    tokio::spawn(async move {
+        loop {
+            tick(&db, &redis).await;
+            sleep(Duration::from_secs(10)).await;
+        }
+    });
+}
@@ -0,0 +1,36 @@
+//! Craft-job worker: moves finished jobs to `ready` and tells their owner.
+//
+//  Redis channels
+//  --------------
+//  player:<player_id>:events – `ServerMsg::CraftReady`
+
+use redis::Client as RedisClient;
+use sqlx::PgPool;
+use tokio::time::{sleep, Duration};
+
+use crate::{db::craft_repo, protocol::ServerMsg, trading};
+
+async fn tick(db: &PgPool, redis: &RedisClient) {
+    match craft_repo::finish_due(db).await {
+        Ok(done) => {
+            for job in &done {
+                let msg = ServerMsg::CraftReady {
+                    job_id: job.id,
+                    recipe_id: job.recipe_id,
+                };
+                trading::notify(redis, job.player_id, &msg).await;
+            }
+        }
+        Err(e) => log::error!("craft sweep failed: {e:?}"),
+    }
+}
+
+/// Spawn the loop that finishes due craft jobs.
+pub fn start(db: PgPool, redis: RedisClient) {
+    tokio::spawn(async move {
+        loop {
+            tick(&db, &redis).await;
+            sleep(Duration::from_secs(10)).await;
+        }
+    });
+}
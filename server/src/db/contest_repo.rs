@@ -0,0 +1,581 @@
+//! Territory contests: attacking a bordering enemy parcel, the defenders’
+//! answer and the resolution that may move ownership.
+//!
+//! Declaring commits energy from the attacker’s treasury. Within the
+//! response window the defenders either name a champion, which opens a
+//! siege duel between the two champions (played through `game::session`),
+//! or accept a strength check on the spot. Unanswered contests, duels
+//! nobody started and duels that never finish are settled by strength once
+//! due (see the `contests` worker). The rules themselves are in
+//! `ecosystem::territory`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        land_repo,
+        treasury_repo::{self, Resources, TreasuryReason},
+    },
+    ecosystem::territory::{self, MIN_ATTACK_ENERGY},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContestStatus {
+    /// Waiting for the defenders until `respond_by`.
+    Open,
+    /// Champions are fighting `game_id`.
+    Dueling,
+    Resolved,
+}
+
+impl ContestStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContestStatus::Open => "open",
+            ContestStatus::Dueling => "dueling",
+            ContestStatus::Resolved => "resolved",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "open" => ContestStatus::Open,
+            "dueling" => ContestStatus::Dueling,
+            "resolved" => ContestStatus::Resolved,
+            other => bail!("unknown contest status {other}"),
+        })
+    }
+}
+
+/// How a contest was (or is being) settled.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContestMethod {
+    Duel,
+    Strength,
+}
+
+impl ContestMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContestMethod::Duel => "duel",
+            ContestMethod::Strength => "strength",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "duel" => ContestMethod::Duel,
+            "strength" => ContestMethod::Strength,
+            other => bail!("unknown contest method {other}"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Contest {
+    pub id: Uuid,
+    pub parcel_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub attacker_faction_id: Uuid,
+    pub defender_faction_id: Uuid,
+    pub attacker_champion: Uuid,
+    pub defender_champion: Option<Uuid>,
+    /// Energy the attacker committed.
+    pub energy: i64,
+    pub status: ContestStatus,
+    pub method: Option<ContestMethod>,
+    pub game_id: Option<Uuid>,
+    pub respond_by: DateTime<Utc>,
+    pub duel_started_at: Option<DateTime<Utc>>,
+    pub winner_faction_id: Option<Uuid>,
+    /// Set when settled by strength.
+    pub attack_strength: Option<f64>,
+    pub defense_strength: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+struct ContestRow {
+    id: Uuid,
+    parcel_id: i32,
+    x: i32,
+    y: i32,
+    attacker_faction_id: Uuid,
+    defender_faction_id: Uuid,
+    attacker_champion: Uuid,
+    defender_champion: Option<Uuid>,
+    energy: i64,
+    status: String,
+    method: Option<String>,
+    game_id: Option<Uuid>,
+    respond_by: DateTime<Utc>,
+    duel_started_at: Option<DateTime<Utc>>,
+    winner_faction_id: Option<Uuid>,
+    attack_strength: Option<f64>,
+    defense_strength: Option<f64>,
+    created_at: DateTime<Utc>,
+    resolved_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ContestRow> for Contest {
+    type Error = anyhow::Error;
+
+    fn try_from(r: ContestRow) -> Result<Self> {
+        Ok(Contest {
+            id: r.id,
+            parcel_id: r.parcel_id,
+            x: r.x,
+            y: r.y,
+            attacker_faction_id: r.attacker_faction_id,
+            defender_faction_id: r.defender_faction_id,
+            attacker_champion: r.attacker_champion,
+            defender_champion: r.defender_champion,
+            energy: r.energy,
+            status: ContestStatus::parse(&r.status)?,
+            method: r.method.as_deref().map(ContestMethod::parse).transpose()?,
+            game_id: r.game_id,
+            respond_by: r.respond_by,
+            duel_started_at: r.duel_started_at,
+            winner_faction_id: r.winner_faction_id,
+            attack_strength: r.attack_strength,
+            defense_strength: r.defense_strength,
+            created_at: r.created_at,
+            resolved_at: r.resolved_at,
+        })
+    }
+}
+
+/// An attack as declared by the attacking faction.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Declaration {
+    pub faction_id: Uuid,
+    pub x: i32,
+    pub y: i32,
+    /// Attacker’s duel champion; must belong to `faction_id`.
+    pub champion_id: Uuid,
+    /// Energy committed from the treasury; drives attack strength.
+    pub energy: i64,
+}
+
+async fn role(conn: &mut PgConnection, faction: Uuid, player: Uuid) -> Result<Option<String>> {
+    Ok(sqlx::query_scalar!(
+        "SELECT role FROM faction_members WHERE faction_id = $1 AND player_id = $2",
+        faction,
+        player
+    )
+    .fetch_optional(&mut *conn)
+    .await?)
+}
+
+fn commands(role: Option<&str>) -> bool {
+    matches!(role, Some("leader" | "officer"))
+}
+
+async fn load(conn: &mut PgConnection, id: Uuid) -> Result<Contest> {
+    sqlx::query_as!(
+        ContestRow,
+        "SELECT id, parcel_id, x, y, attacker_faction_id, defender_faction_id,
+                attacker_champion, defender_champion, energy, status, method, game_id,
+                respond_by, duel_started_at, winner_faction_id,
+                attack_strength, defense_strength, created_at, resolved_at
+           FROM contests
+          WHERE id = $1
+          FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| anyhow!("unknown contest"))?
+    .try_into()
+}
+
+pub async fn get(db: &PgPool, id: Uuid) -> Result<Option<Contest>> {
+    sqlx::query_as!(
+        ContestRow,
+        "SELECT id, parcel_id, x, y, attacker_faction_id, defender_faction_id,
+                attacker_champion, defender_champion, energy, status, method, game_id,
+                respond_by, duel_started_at, winner_faction_id,
+                attack_strength, defense_strength, created_at, resolved_at
+           FROM contests
+          WHERE id = $1",
+        id
+    )
+    .fetch_optional(db)
+    .await?
+    .map(Contest::try_from)
+    .transpose()
+}
+
+/// Unresolved contests the faction attacks or defends, oldest first.
+pub async fn live_for(db: &PgPool, faction: Uuid) -> Result<Vec<Contest>> {
+    sqlx::query_as!(
+        ContestRow,
+        "SELECT id, parcel_id, x, y, attacker_faction_id, defender_faction_id,
+                attacker_champion, defender_champion, energy, status, method, game_id,
+                respond_by, duel_started_at, winner_faction_id,
+                attack_strength, defense_strength, created_at, resolved_at
+           FROM contests
+          WHERE status <> 'resolved'
+            AND (attacker_faction_id = $1 OR defender_faction_id = $1)
+          ORDER BY created_at",
+        faction
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(Contest::try_from)
+    .collect()
+}
+
+/// Declare an attack; `player` must command the attacking faction. The
+/// defenders have `window` to respond.
+pub async fn declare(
+    db: &PgPool,
+    player: Uuid,
+    d: &Declaration,
+    window: Duration,
+) -> Result<Contest> {
+    let mut tx = db.begin().await?;
+    let attacker = d.faction_id;
+    if !commands(role(&mut tx, attacker, player).await?.as_deref()) {
+        bail!("only officers and leaders may declare attacks");
+    }
+    if role(&mut tx, attacker, d.champion_id).await?.is_none() {
+        bail!("champion must belong to the attacking faction");
+    }
+    if d.energy < MIN_ATTACK_ENERGY {
+        bail!("an attack needs at least {MIN_ATTACK_ENERGY} energy");
+    }
+
+    let parcel = sqlx::query!(
+        "SELECT id, owner_faction_id FROM land_parcels
+          WHERE x = $1 AND y = $2
+          FOR UPDATE",
+        d.x,
+        d.y
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .context("no parcel at that tile")?;
+    let defender = match parcel.owner_faction_id {
+        Some(f) if f == attacker => bail!("cannot attack your own parcel"),
+        Some(f) => f,
+        None => bail!("parcel is unowned; claim it instead"),
+    };
+    if land_repo::bordering(&mut tx, attacker, d.x, d.y).await? == 0 {
+        bail!("target must border your territory");
+    }
+    let contested = sqlx::query_scalar!(
+        r#"SELECT EXISTS(
+               SELECT 1 FROM contests WHERE parcel_id = $1 AND status <> 'resolved'
+           ) AS "contested!""#,
+        parcel.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if contested {
+        bail!("parcel is already contested");
+    }
+
+    let id = Uuid::new_v4();
+    treasury_repo::spend(
+        &mut tx,
+        attacker,
+        &Resources {
+            energy: d.energy,
+            ..Resources::default()
+        },
+        TreasuryReason::War,
+        Some(&format!("contest:{id}")),
+    )
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO contests
+                (id, parcel_id, x, y, attacker_faction_id, defender_faction_id,
+                 declared_by, attacker_champion, energy, respond_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        id,
+        parcel.id,
+        d.x,
+        d.y,
+        attacker,
+        defender,
+        player,
+        d.champion_id,
+        d.energy,
+        Utc::now() + window
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let contest = load(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(contest)
+}
+
+/// The defenders’ answer; `player` must command the defending faction.
+/// With a champion the contest goes to a siege duel, without one it is
+/// settled by strength at once.
+pub async fn respond(
+    db: &PgPool,
+    id: Uuid,
+    player: Uuid,
+    champion: Option<Uuid>,
+) -> Result<Contest> {
+    let mut tx = db.begin().await?;
+    let c = load(&mut tx, id).await?;
+    if c.status != ContestStatus::Open {
+        bail!("contest is not awaiting a response");
+    }
+    if Utc::now() >= c.respond_by {
+        bail!("response window closed");
+    }
+    if !commands(
+        role(&mut tx, c.defender_faction_id, player)
+            .await?
+            .as_deref(),
+    ) {
+        bail!("only officers and leaders of the defending faction may respond");
+    }
+
+    match champion {
+        Some(champion) => {
+            if role(&mut tx, c.defender_faction_id, champion)
+                .await?
+                .is_none()
+            {
+                bail!("champion must belong to the defending faction");
+            }
+            if champion == c.attacker_champion {
+                bail!("champions must be different players");
+            }
+            let game_id = sqlx::query_scalar!(
+                "INSERT INTO games (player1_id, player2_id, state, mode)
+                 VALUES ($1, $2, 'Lobby', 'siege')
+                 RETURNING id",
+                c.attacker_champion,
+                champion
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            sqlx::query!(
+                "UPDATE contests
+                    SET status = 'dueling', method = 'duel', defender_champion = $2,
+                        game_id = $3, duel_started_at = NOW()
+                  WHERE id = $1",
+                id,
+                champion,
+                game_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        None => settle_by_strength(&mut tx, &c).await?,
+    }
+
+    let contest = load(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(contest)
+}
+
+/// Compare attack and defense and finish the contest.
+async fn settle_by_strength(conn: &mut PgConnection, c: &Contest) -> Result<()> {
+    let structures = sqlx::query_scalar!(
+        r#"SELECT COALESCE(SUM(d.defense), 0)::FLOAT8 AS "defense!"
+             FROM structures s
+             JOIN structure_defense d ON d.structure_type = s.type
+            WHERE s.x = $1 AND s.y = $2"#,
+        c.x,
+        c.y
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let attack = territory::attack_strength(
+        c.energy,
+        land_repo::bordering(conn, c.attacker_faction_id, c.x, c.y).await?,
+    );
+    let defense = territory::defense_strength(
+        structures,
+        land_repo::bordering(conn, c.defender_faction_id, c.x, c.y).await?,
+    );
+    let winner = if territory::attacker_wins(attack, defense) {
+        c.attacker_faction_id
+    } else {
+        c.defender_faction_id
+    };
+    finish(
+        conn,
+        c,
+        winner,
+        ContestMethod::Strength,
+        Some((attack, defense)),
+    )
+    .await
+}
+
+/// Record the result; an attacker win moves the parcel and the structures
+/// the defenders had on it, and is written to the ownership history.
+async fn finish(
+    conn: &mut PgConnection,
+    c: &Contest,
+    winner: Uuid,
+    method: ContestMethod,
+    strengths: Option<(f64, f64)>,
+) -> Result<()> {
+    if winner == c.attacker_faction_id {
+        let moved = sqlx::query!(
+            "UPDATE land_parcels SET owner_faction_id = $2
+              WHERE id = $1 AND owner_faction_id = $3",
+            c.parcel_id,
+            c.attacker_faction_id,
+            c.defender_faction_id
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        if moved > 0 {
+            sqlx::query!(
+                "UPDATE structures SET owner_faction_id = $3
+                  WHERE x = $1 AND y = $2 AND owner_faction_id = $4",
+                c.x,
+                c.y,
+                c.attacker_faction_id,
+                c.defender_faction_id
+            )
+            .execute(&mut *conn)
+            .await?;
+            land_repo::record_transfer(
+                conn,
+                c.parcel_id,
+                Some(c.defender_faction_id),
+                Some(c.attacker_faction_id),
+                Some(c.id),
+            )
+            .await?;
+        }
+    }
+
+    sqlx::query!(
+        "UPDATE contests
+            SET status = 'resolved', method = $2, winner_faction_id = $3,
+                attack_strength = $4, defense_strength = $5, resolved_at = NOW()
+          WHERE id = $1",
+        c.id,
+        method.as_str(),
+        winner,
+        strengths.map(|s| s.0),
+        strengths.map(|s| s.1)
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Settle every contest that is due: unanswered ones past `respond_by`,
+/// finished duels, duels still in the lobby `window` after they were set
+/// up, and duels still unfinished `window + max_duel` after that, whatever
+/// their game’s state (e.g. one champion joined and the other never did).
+/// Returns the contests resolved.
+pub async fn resolve_due(
+    db: &PgPool,
+    window: Duration,
+    max_duel: Duration,
+) -> Result<Vec<Contest>> {
+    let due = sqlx::query_scalar!(
+        "SELECT c.id
+           FROM contests c
+           LEFT JOIN games g ON g.id = c.game_id
+          WHERE (c.status = 'open' AND c.respond_by <= NOW())
+             OR (c.status = 'dueling'
+                 AND (g.id IS NULL
+                      OR g.state = 'Finished'
+                      OR g.winner_id IS NOT NULL
+                      OR (g.state = 'Lobby'
+                          AND c.duel_started_at + make_interval(secs => $1) <= NOW())
+                      OR c.duel_started_at + make_interval(secs => $2) <= NOW()))
+          ORDER BY c.created_at",
+        window.num_seconds() as f64,
+        (window + max_duel).num_seconds() as f64
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut resolved = Vec::with_capacity(due.len());
+    for id in due {
+        match resolve_one(db, id, window, max_duel).await {
+            Ok(Some(c)) => resolved.push(c),
+            Ok(None) => {}
+            Err(e) => log::error!("resolving contest {id} failed: {e:?}"),
+        }
+    }
+    Ok(resolved)
+}
+
+async fn resolve_one(
+    db: &PgPool,
+    id: Uuid,
+    window: Duration,
+    max_duel: Duration,
+) -> Result<Option<Contest>> {
+    let mut tx = db.begin().await?;
+    let c = load(&mut tx, id).await?;
+    let now = Utc::now();
+    let overdue = c
+        .duel_started_at
+        .is_none_or(|t| t + window + max_duel <= now);
+    match c.status {
+        ContestStatus::Open if c.respond_by <= now => settle_by_strength(&mut tx, &c).await?,
+        ContestStatus::Dueling => {
+            let game = match c.game_id {
+                Some(g) => {
+                    sqlx::query!(
+                        "SELECT state, winner_id, victory_rule FROM games WHERE id = $1",
+                        g
+                    )
+                    .fetch_optional(&mut *tx)
+                    .await?
+                }
+                None => None,
+            };
+            match game {
+                // A recorded winner decides even if the row’s state was
+                // since touched. Only the attacker’s champion winning takes
+                // the parcel; a draw holds it, an abandoned duel decided
+                // nothing and falls back to strength.
+                Some(g)
+                    if g.winner_id.is_some()
+                        || (g.state == "Finished"
+                            && g.victory_rule.as_deref() != Some("abandoned")) =>
+                {
+                    let winner = if g.winner_id == Some(c.attacker_champion) {
+                        c.attacker_faction_id
+                    } else {
+                        c.defender_faction_id
+                    };
+                    finish(&mut tx, &c, winner, ContestMethod::Duel, None).await?;
+                }
+                Some(g)
+                    if !overdue
+                        && (g.state != "Lobby"
+                            || c.duel_started_at.is_some_and(|t| t + window > now)) =>
+                {
+                    return Ok(None)
+                }
+                _ => settle_by_strength(&mut tx, &c).await?,
+            }
+        }
+        _ => return Ok(None),
+    }
+    let contest = load(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(Some(contest))
+}
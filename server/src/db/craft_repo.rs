@@ -0,0 +1,316 @@
+//! Crafting: recipes and craft jobs.
+//!
+//! Lifecycle: `in_progress` → `ready` → `claimed`. Starting a job consumes
+//! the recipe’s inputs; claiming it hands over the outputs (and any unit
+//! unlock). Jobs with a craft time are moved to `ready` by the `crafting`
+//! worker, which also notifies the player; instant recipes start `ready`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    db::trade_repo::{self, Bundle, BundleItem},
+    game::types::UnitType,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Recipe {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    /// Structure type the crafter must own on a land parcel, if any.
+    pub required_structure: Option<String>,
+    pub craft_secs: i32,
+    pub inputs: Vec<BundleItem>,
+    pub outputs: Vec<BundleItem>,
+    /// Unit type unlocked for the crafter on claim.
+    pub unlock_unit: Option<UnitType>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CraftStatus {
+    InProgress,
+    Ready,
+    Claimed,
+}
+
+impl CraftStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CraftStatus::InProgress => "in_progress",
+            CraftStatus::Ready => "ready",
+            CraftStatus::Claimed => "claimed",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "in_progress" => CraftStatus::InProgress,
+            "ready" => CraftStatus::Ready,
+            "claimed" => CraftStatus::Claimed,
+            other => bail!("unknown craft status {other}"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CraftJob {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub recipe_id: i32,
+    pub structure_id: Option<i32>,
+    pub status: CraftStatus,
+    pub started_at: DateTime<Utc>,
+    pub ready_at: DateTime<Utc>,
+}
+
+/// What claiming a job handed over.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Claimed {
+    pub job: CraftJob,
+    pub items: Vec<BundleItem>,
+    pub unlocked: Option<UnitType>,
+}
+
+struct JobRow {
+    id: Uuid,
+    player_id: Uuid,
+    recipe_id: i32,
+    structure_id: Option<i32>,
+    status: String,
+    started_at: DateTime<Utc>,
+    ready_at: DateTime<Utc>,
+}
+
+impl TryFrom<JobRow> for CraftJob {
+    type Error = anyhow::Error;
+
+    fn try_from(r: JobRow) -> Result<Self> {
+        Ok(CraftJob {
+            id: r.id,
+            player_id: r.player_id,
+            recipe_id: r.recipe_id,
+            structure_id: r.structure_id,
+            status: CraftStatus::parse(&r.status)?,
+            started_at: r.started_at,
+            ready_at: r.ready_at,
+        })
+    }
+}
+
+//////////////////////////////////////////////////
+// Public operations (each one transaction)
+//////////////////////////////////////////////////
+
+/// Every recipe, by id.
+pub async fn list_recipes(db: &PgPool) -> Result<Vec<Recipe>> {
+    let mut conn = db.acquire().await?;
+    let ids = sqlx::query_scalar!("SELECT id FROM recipes ORDER BY id")
+        .fetch_all(&mut *conn)
+        .await?;
+    let mut out = Vec::with_capacity(ids.len());
+    for id in ids {
+        out.push(load_recipe(&mut conn, id).await?);
+    }
+    Ok(out)
+}
+
+/// Consume a recipe’s inputs and start a job. `structure_id` must name a
+/// structure of the required type that the player owns on a land parcel
+/// (ignored for recipes without a requirement).
+pub async fn start(
+    db: &PgPool,
+    player: Uuid,
+    recipe_id: i32,
+    structure_id: Option<i32>,
+) -> Result<CraftJob> {
+    let mut tx = db.begin().await?;
+    let recipe = load_recipe(&mut tx, recipe_id).await?;
+
+    let structure_id = match &recipe.required_structure {
+        Some(kind) => {
+            let sid = structure_id.ok_or_else(|| anyhow!("recipe needs a {kind} structure"))?;
+            sqlx::query_scalar!(
+                "SELECT s.id FROM structures s
+                   JOIN land_parcels lp ON lp.x = s.x AND lp.y = s.y
+                  WHERE s.id = $1 AND s.owner_player_id = $2 AND s.type = $3",
+                sid,
+                player,
+                kind
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| anyhow!("structure {sid} is not your {kind} on a land parcel"))?;
+            Some(sid)
+        }
+        None => None,
+    };
+
+    for it in &recipe.inputs {
+        trade_repo::take_item(&mut tx, player, it.item_id, it.qty).await?;
+    }
+
+    let status = if recipe.craft_secs == 0 {
+        CraftStatus::Ready
+    } else {
+        CraftStatus::InProgress
+    };
+    let job: CraftJob = sqlx::query_as!(
+        JobRow,
+        "INSERT INTO craft_jobs (player_id, recipe_id, structure_id, status, ready_at)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, player_id, recipe_id, structure_id, status, started_at, ready_at",
+        player,
+        recipe_id,
+        structure_id,
+        status.as_str(),
+        Utc::now() + Duration::seconds(recipe.craft_secs as i64)
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .try_into()?;
+
+    tx.commit().await?;
+    Ok(job)
+}
+
+/// The player’s unclaimed jobs, soonest first.
+pub async fn status(db: &PgPool, player: Uuid) -> Result<Vec<CraftJob>> {
+    sqlx::query_as!(
+        JobRow,
+        "SELECT id, player_id, recipe_id, structure_id, status, started_at, ready_at
+           FROM craft_jobs
+          WHERE player_id = $1 AND status <> 'claimed'
+          ORDER BY ready_at",
+        player
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(CraftJob::try_from)
+    .collect()
+}
+
+/// Hand over a finished job’s outputs. Fails without side effects if the
+/// job is still running or an output would exceed its `max_stack`.
+pub async fn claim(db: &PgPool, player: Uuid, job_id: Uuid) -> Result<Claimed> {
+    let mut tx = db.begin().await?;
+    let mut job: CraftJob = sqlx::query_as!(
+        JobRow,
+        "SELECT id, player_id, recipe_id, structure_id, status, started_at, ready_at
+           FROM craft_jobs
+          WHERE id = $1 AND player_id = $2 AND status <> 'claimed'
+          FOR UPDATE",
+        job_id,
+        player
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| anyhow!("job not found or already claimed"))?
+    .try_into()?;
+    if job.ready_at > Utc::now() {
+        bail!("job not finished until {}", job.ready_at);
+    }
+
+    let recipe = load_recipe(&mut tx, job.recipe_id).await?;
+    for it in &recipe.outputs {
+        trade_repo::ensure_room(&mut tx, player, it.item_id, it.qty).await?;
+    }
+    trade_repo::credit(
+        &mut tx,
+        player,
+        &Bundle {
+            credits: 0,
+            items: recipe.outputs.clone(),
+        },
+        &job.id.to_string(),
+    )
+    .await?;
+    if let Some(unit) = &recipe.unlock_unit {
+        sqlx::query!(
+            "INSERT INTO player_unit_unlocks (player_id, unit_type)
+             VALUES ($1, $2)
+             ON CONFLICT DO NOTHING",
+            player,
+            unit.id()
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query!(
+        "UPDATE craft_jobs SET status = 'claimed', claimed_at = NOW() WHERE id = $1",
+        job.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    job.status = CraftStatus::Claimed;
+
+    tx.commit().await?;
+    Ok(Claimed {
+        job,
+        items: recipe.outputs,
+        unlocked: recipe.unlock_unit,
+    })
+}
+
+/// Mark every running job past its `ready_at` as `ready`.
+pub async fn finish_due(db: &PgPool) -> Result<Vec<CraftJob>> {
+    sqlx::query_as!(
+        JobRow,
+        "UPDATE craft_jobs SET status = 'ready'
+          WHERE status = 'in_progress' AND ready_at <= NOW()
+          RETURNING id, player_id, recipe_id, structure_id, status, started_at, ready_at"
+    )
+    .fetch_all(db)
+    .await
+    .context("finishing due craft jobs")?
+    .into_iter()
+    .map(CraftJob::try_from)
+    .collect()
+}
+
+//////////////////////////////////////////////////
+// Helpers (run inside the caller’s transaction)
+//////////////////////////////////////////////////
+
+async fn load_recipe(conn: &mut PgConnection, recipe_id: i32) -> Result<Recipe> {
+    let row = sqlx::query!(
+        "SELECT id, name, description, required_structure, craft_secs, unlock_unit
+           FROM recipes WHERE id = $1",
+        recipe_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| anyhow!("unknown recipe {recipe_id}"))?;
+
+    let inputs = sqlx::query_as!(
+        BundleItem,
+        "SELECT item_id, qty FROM recipe_inputs WHERE recipe_id = $1 ORDER BY item_id",
+        recipe_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let outputs = sqlx::query_as!(
+        BundleItem,
+        "SELECT item_id, qty FROM recipe_outputs WHERE recipe_id = $1 ORDER BY item_id",
+        recipe_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(Recipe {
+        id: row.id,
+        name: row.name,
+        description: row.description,
+        required_structure: row.required_structure,
+        craft_secs: row.craft_secs,
+        inputs,
+        outputs,
+        unlock_unit: row.unlock_unit.map(UnitType::new),
+    })
+}
@@ -173,3 +173,14 @@ pub async fn kick_member(db: &PgPool, faction: Uuid, actor: Uuid, target: Uuid)
         Ok(())
     }
 }
+
+/// Every member of a faction.
+pub async fn member_ids(db: &PgPool, faction: Uuid) -> Result<Vec<Uuid>> {
+    sqlx::query_scalar!(
+        "SELECT player_id FROM faction_members WHERE faction_id = $1",
+        faction
+    )
+    .fetch_all(db)
+    .await
+    .context("fetching faction members")
+}
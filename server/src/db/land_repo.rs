@@ -1,7 +1,32 @@
-use anyhow::Context;
-use sqlx::PgPool;
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 
+use crate::{
+    db::{
+        ledger_repo::{self, Account, Reason, SystemAccount},
+        treasury_repo::{self, TreasuryReason},
+    },
+    ecosystem::{
+        model::ParcelState,
+        territory::{self, ClaimCost, PayWith, BORDERS},
+        worldgen::Tile,
+    },
+};
+
+/// Result of [`claim`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimOutcome {
+    /// The faction now owns the parcel and paid `paid`.
+    Claimed { parcel_id: i32, paid: ClaimCost },
+    /// The faction already owned it; nothing was charged.
+    AlreadyOwned { parcel_id: i32 },
+    /// Another faction owns it.
+    Taken,
+}
+
 /// Returns the owning faction ID for a land parcel at (x, y), if claimed.
 pub async fn owner_faction_for_tile(db: &PgPool, x: i32, y: i32) -> anyhow::Result<Option<Uuid>> {
     // Note: owner_faction_id is a nullable column ⇒ SQLx maps it to Option<Uuid>
@@ -19,28 +44,277 @@ pub async fn owner_faction_for_tile(db: &PgPool, x: i32, y: i32) -> anyhow::Resu
     Ok(opt.flatten())
 }
 
-/// Inserts a new land parcel; returns the new parcel ID.
+/// Materialises a generated tile as a parcel owned by `faction_id`;
+/// returns the new parcel ID, or `None` if the tile already has a row.
 pub async fn insert_land_parcel(
-    db: &PgPool,
-    biome_type: &str,
+    conn: &mut PgConnection,
+    tile: &Tile,
     faction_id: Uuid,
-    x: i32,
-    y: i32,
-) -> anyhow::Result<i32> {
+) -> anyhow::Result<Option<i32>> {
+    let eco = tile.initial_state();
     let id = sqlx::query_scalar!(
         r#"
-        INSERT INTO land_parcels (biome_type, owner_faction_id, x, y)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO land_parcels
+               (biome_type, rich, owner_faction_id, x, y,
+                fertility, biomass, pollution, population)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (x, y) DO NOTHING
         RETURNING id
         "#,
-        biome_type,
+        tile.biome.as_str(),
+        tile.rich,
         faction_id,
-        x,
-        y
+        tile.x,
+        tile.y,
+        eco.fertility,
+        eco.biomass,
+        eco.pollution,
+        eco.population
     )
-    .fetch_one(db)
+    .fetch_optional(&mut *conn)
     .await
     .context("inserting land parcel")?;
 
     Ok(id)
 }
+
+/// Hands an existing unowned parcel to `faction_id`; false if it is owned.
+pub async fn claim_unowned(
+    conn: &mut PgConnection,
+    parcel_id: i32,
+    faction_id: Uuid,
+) -> anyhow::Result<bool> {
+    let rows = sqlx::query!(
+        "UPDATE land_parcels SET owner_faction_id = $2
+          WHERE id = $1 AND owner_faction_id IS NULL",
+        parcel_id,
+        faction_id
+    )
+    .execute(&mut *conn)
+    .await
+    .context("claiming unowned parcel")?
+    .rows_affected();
+
+    Ok(rows > 0)
+}
+
+/// Parcels held by `faction` that share an edge with `(x, y)`.
+pub async fn bordering(
+    conn: &mut PgConnection,
+    faction: Uuid,
+    x: i32,
+    y: i32,
+) -> anyhow::Result<i64> {
+    let (xs, ys): (Vec<i32>, Vec<i32>) = BORDERS.iter().map(|(dx, dy)| (x + dx, y + dy)).unzip();
+    sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "n!"
+             FROM land_parcels lp
+             JOIN UNNEST($2::INT[], $3::INT[]) AS n(x, y)
+               ON lp.x = n.x AND lp.y = n.y
+            WHERE lp.owner_faction_id = $1"#,
+        faction,
+        &xs,
+        &ys
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .context("counting bordering parcels")
+}
+
+/// One row of `parcel_ownership_history`.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct OwnershipChange {
+    pub from_faction_id: Option<Uuid>,
+    pub to_faction_id: Option<Uuid>,
+    /// `claim` or `contest`.
+    pub reason: String,
+    pub contest_id: Option<Uuid>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Append an ownership change; a `contest_id` marks it as a contest result.
+pub async fn record_transfer(
+    conn: &mut PgConnection,
+    parcel_id: i32,
+    from: Option<Uuid>,
+    to: Option<Uuid>,
+    contest_id: Option<Uuid>,
+) -> anyhow::Result<()> {
+    let reason = if contest_id.is_some() {
+        "contest"
+    } else {
+        "claim"
+    };
+    sqlx::query!(
+        "INSERT INTO parcel_ownership_history
+                (parcel_id, from_faction_id, to_faction_id, reason, contest_id)
+         VALUES ($1, $2, $3, $4, $5)",
+        parcel_id,
+        from,
+        to,
+        reason,
+        contest_id
+    )
+    .execute(&mut *conn)
+    .await
+    .context("recording ownership change")?;
+    Ok(())
+}
+
+/// Ownership changes of the parcel at `(x, y)`, oldest first.
+pub async fn history(db: &PgPool, x: i32, y: i32) -> anyhow::Result<Vec<OwnershipChange>> {
+    sqlx::query_as!(
+        OwnershipChange,
+        "SELECT h.from_faction_id, h.to_faction_id, h.reason, h.contest_id, h.changed_at
+           FROM parcel_ownership_history h
+           JOIN land_parcels lp ON lp.id = h.parcel_id
+          WHERE lp.x = $1 AND lp.y = $2
+          ORDER BY h.id",
+        x,
+        y
+    )
+    .fetch_all(db)
+    .await
+    .context("fetching ownership history")
+}
+
+/// Claim `tile` for `faction` on behalf of `player`, in one transaction.
+///
+/// The first parcel is free; later ones must border the faction’s
+/// territory and cost [`territory::claim_cost`], paid from the treasury or
+/// the player’s credits as `pay` says. Claims of one faction are serialised
+/// on its `factions` row.
+pub async fn claim(
+    db: &PgPool,
+    tile: &Tile,
+    faction: Uuid,
+    player: Uuid,
+    pay: PayWith,
+) -> anyhow::Result<ClaimOutcome> {
+    let mut tx = db.begin().await?;
+
+    sqlx::query_scalar!("SELECT id FROM factions WHERE id = $1 FOR UPDATE", faction)
+        .fetch_optional(&mut *tx)
+        .await?
+        .context("unknown faction")?;
+
+    let existing = sqlx::query!(
+        "SELECT id, owner_faction_id FROM land_parcels
+          WHERE x = $1 AND y = $2
+          FOR UPDATE",
+        tile.x,
+        tile.y
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    match existing.as_ref().map(|r| (r.id, r.owner_faction_id)) {
+        Some((id, Some(owner))) if owner == faction => {
+            return Ok(ClaimOutcome::AlreadyOwned { parcel_id: id });
+        }
+        Some((_, Some(_))) => return Ok(ClaimOutcome::Taken),
+        _ => {}
+    }
+
+    let territory = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "n!" FROM land_parcels WHERE owner_faction_id = $1"#,
+        faction
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if territory > 0 && bordering(&mut tx, faction, tile.x, tile.y).await? == 0 {
+        bail!("claim must border your territory");
+    }
+
+    let paid = territory::claim_cost(tile.biome, tile.rich, territory).charged(pay);
+    let ref_id = format!("claim:{}:{}", tile.x, tile.y);
+    match pay {
+        PayWith::Treasury => {
+            treasury_repo::spend(
+                &mut tx,
+                faction,
+                &paid.resources,
+                TreasuryReason::Land,
+                Some(&ref_id),
+            )
+            .await?
+        }
+        PayWith::Credits => {
+            ledger_repo::transfer(
+                &mut tx,
+                Account::Player(player),
+                Account::System(SystemAccount::Land),
+                paid.credits,
+                Reason::Land,
+                Some(&ref_id),
+            )
+            .await?
+        }
+    }
+
+    let parcel_id = match existing {
+        Some(r) => {
+            if !claim_unowned(&mut tx, r.id, faction).await? {
+                return Ok(ClaimOutcome::Taken);
+            }
+            r.id
+        }
+        None => match insert_land_parcel(&mut tx, tile, faction).await? {
+            Some(id) => id,
+            None => return Ok(ClaimOutcome::Taken),
+        },
+    };
+    record_transfer(&mut tx, parcel_id, None, Some(faction), None).await?;
+
+    tx.commit().await?;
+    Ok(ClaimOutcome::Claimed { parcel_id, paid })
+}
+
+/// A materialised parcel inside a map region.
+pub struct RegionParcel {
+    pub id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub biome_type: String,
+    pub rich: bool,
+    pub owner_faction_id: Option<Uuid>,
+    pub state: ParcelState,
+}
+
+/// Parcels with `x0 <= x <= x1` and `y0 <= y <= y1`.
+pub async fn parcels_in(
+    db: &PgPool,
+    (x0, y0): (i32, i32),
+    (x1, y1): (i32, i32),
+) -> anyhow::Result<Vec<RegionParcel>> {
+    let rows = sqlx::query!(
+        "SELECT id, x, y, biome_type, rich, owner_faction_id,
+                fertility, biomass, pollution, population
+           FROM land_parcels
+          WHERE x BETWEEN $1 AND $2 AND y BETWEEN $3 AND $4",
+        x0,
+        x1,
+        y0,
+        y1
+    )
+    .fetch_all(db)
+    .await
+    .context("fetching parcels in region")?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| RegionParcel {
+            id: r.id,
+            x: r.x,
+            y: r.y,
+            biome_type: r.biome_type,
+            rich: r.rich,
+            owner_faction_id: r.owner_faction_id,
+            state: ParcelState {
+                fertility: r.fertility,
+                biomass: r.biomass,
+                pollution: r.pollution,
+                population: r.population,
+            },
+        })
+        .collect())
+}
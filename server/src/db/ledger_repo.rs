@@ -0,0 +1,240 @@
+//! Double-entry credits ledger.
+//!
+//! Every credit movement goes through [`transfer`], which writes a balanced
+//! pair of `credit_ledger` rows and updates the cached `players.credits` in
+//! the caller’s transaction. The table is append-only and each `txn_id`
+//! must sum to zero at commit (both enforced by triggers).
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Non-player counterparties.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemAccount {
+    /// NPC shop: receives purchases, pays for sales.
+    Shop,
+    /// Credits held for open trade offers and buy orders.
+    Escrow,
+    /// Market fee sink (mirrors `economy_sinks.market_fees`).
+    MarketFees,
+    /// Source of match and quest rewards.
+    Rewards,
+    /// Operator grants and opening balances.
+    Admin,
+    /// Receives credits paid for territory claims.
+    Land,
+}
+
+impl SystemAccount {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SystemAccount::Shop => "shop",
+            SystemAccount::Escrow => "escrow",
+            SystemAccount::MarketFees => "market_fees",
+            SystemAccount::Rewards => "rewards",
+            SystemAccount::Admin => "admin",
+            SystemAccount::Land => "land",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Account {
+    Player(Uuid),
+    System(SystemAccount),
+}
+
+impl Account {
+    fn columns(self) -> (Option<Uuid>, Option<&'static str>) {
+        match self {
+            Account::Player(id) => (Some(id), None),
+            Account::System(s) => (None, Some(s.as_str())),
+        }
+    }
+}
+
+/// Why credits moved (`credit_ledger.reason`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    Shop,
+    Trade,
+    Reward,
+    Fee,
+    Admin,
+    Land,
+}
+
+impl Reason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Reason::Shop => "shop",
+            Reason::Trade => "trade",
+            Reason::Reward => "reward",
+            Reason::Fee => "fee",
+            Reason::Admin => "admin",
+            Reason::Land => "land",
+        }
+    }
+}
+
+/// One ledger line as seen by a player.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LedgerEntry {
+    pub id: i64,
+    pub txn_id: Uuid,
+    /// Positive = credited to the player.
+    pub amount: i64,
+    pub reason: String,
+    pub ref_id: Option<String>,
+    /// Other side of the pair: a system account name or a player id.
+    pub counterparty: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A cached balance that disagrees with the ledger.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Drift {
+    pub player_id: Option<Uuid>,
+    pub system: Option<String>,
+    pub cached: i64,
+    pub ledger: i64,
+}
+
+/// Move `amount` credits from `from` to `to`. A player debit fails (and
+/// the caller’s transaction should be dropped) if it would overdraw.
+/// Zero amounts are a no-op.
+pub async fn transfer(
+    conn: &mut PgConnection,
+    from: Account,
+    to: Account,
+    amount: i64,
+    reason: Reason,
+    ref_id: Option<&str>,
+) -> Result<()> {
+    if amount < 0 {
+        bail!("negative transfer");
+    }
+    if amount == 0 {
+        return Ok(());
+    }
+    if from == to {
+        bail!("transfer to self");
+    }
+
+    if let Account::Player(id) = from {
+        let rows = sqlx::query!(
+            "UPDATE players SET credits = credits - $2 WHERE id = $1 AND credits >= $2",
+            id,
+            amount
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        if rows == 0 {
+            bail!("insufficient credits");
+        }
+    }
+    if let Account::Player(id) = to {
+        let rows = sqlx::query!(
+            "UPDATE players SET credits = credits + $2 WHERE id = $1",
+            id,
+            amount
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        if rows == 0 {
+            bail!("unknown player {id}");
+        }
+    }
+
+    let ((from_pid, from_sys), (to_pid, to_sys)) = (from.columns(), to.columns());
+    sqlx::query!(
+        "INSERT INTO credit_ledger (txn_id, player_id, system, amount, reason, ref_id)
+         VALUES ($1, $2, $3, -$6::BIGINT, $7, $8),
+                ($1, $4, $5,  $6::BIGINT, $7, $8)",
+        Uuid::new_v4(),
+        from_pid,
+        from_sys,
+        to_pid,
+        to_sys,
+        amount,
+        reason.as_str(),
+        ref_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// A player’s entries, newest first; `before` pages by entry id.
+pub async fn entries(
+    db: &PgPool,
+    player: Uuid,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<LedgerEntry>> {
+    Ok(sqlx::query_as!(
+        LedgerEntry,
+        r#"SELECT e.id, e.txn_id, e.amount, e.reason, e.ref_id, e.created_at,
+                  COALESCE(o.system, o.player_id::TEXT) AS counterparty
+             FROM credit_ledger e
+             LEFT JOIN credit_ledger o ON o.txn_id = e.txn_id AND o.id <> e.id
+            WHERE e.player_id = $1 AND ($2::BIGINT IS NULL OR e.id < $2)
+            ORDER BY e.id DESC
+            LIMIT $3"#,
+        player,
+        before,
+        limit
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+/// Players and economy sinks whose cached balance differs from the ledger.
+pub async fn find_drift(db: &PgPool) -> Result<Vec<Drift>> {
+    Ok(sqlx::query_as!(
+        Drift,
+        r#"SELECT p.id AS "player_id?", NULL::TEXT AS system,
+                  p.credits AS "cached!",
+                  COALESCE(l.total, 0)::BIGINT AS "ledger!"
+             FROM players p
+             LEFT JOIN (SELECT player_id, SUM(amount) AS total
+                          FROM credit_ledger
+                         WHERE player_id IS NOT NULL
+                         GROUP BY player_id) l ON l.player_id = p.id
+            WHERE p.credits <> COALESCE(l.total, 0)
+           UNION ALL
+           SELECT NULL::UUID, s.name, s.balance, COALESCE(l.total, 0)::BIGINT
+             FROM economy_sinks s
+             LEFT JOIN (SELECT system, SUM(amount) AS total
+                          FROM credit_ledger
+                         WHERE system IS NOT NULL
+                         GROUP BY system) l ON l.system = s.name
+            WHERE s.balance <> COALESCE(l.total, 0)"#
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+/// Persist flagged balances to `ledger_drift`.
+pub async fn record_drift(db: &PgPool, drift: &[Drift]) -> Result<()> {
+    for d in drift {
+        sqlx::query!(
+            "INSERT INTO ledger_drift (player_id, system, cached, ledger)
+             VALUES ($1, $2, $3, $4)",
+            d.player_id,
+            d.system,
+            d.cached,
+            d.ledger
+        )
+        .execute(db)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Match-reward payout: pick a seat’s loot table, roll it and pay it out.
+//!
+//! Runs inside the transaction that records the game result, so the
+//! reward, the ledger entry and the inventory change land together. Item
+//! drops beyond an item’s `max_stack` are lost; the recorded and announced
+//! reward lists what was actually granted.
+
+use anyhow::Result;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    config::settings,
+    db::{
+        ledger_repo::{self, Account, Reason, SystemAccount},
+        trade_repo::BundleItem,
+    },
+    game::{
+        loot::{loot_seed, LootEntry, LootTable, MatchReward, Outcome},
+        rng::CombatRng,
+        victory::GameMode,
+    },
+};
+
+/// Most specific table for a seat: exact mode before the any-mode
+/// fallback, then the narrowest rating band.
+pub async fn find_table(
+    conn: &mut PgConnection,
+    mode: GameMode,
+    outcome: Outcome,
+    rating: i32,
+) -> Result<Option<LootTable>> {
+    let Some(row) = sqlx::query!(
+        "SELECT id, credits_min, credits_max, rolls
+           FROM loot_tables
+          WHERE (mode = $1 OR mode IS NULL)
+            AND outcome = $2
+            AND min_rating <= $3
+            AND (max_rating IS NULL OR max_rating >= $3)
+          ORDER BY mode NULLS LAST, min_rating DESC, max_rating ASC NULLS LAST, id
+          LIMIT 1",
+        mode.as_str(),
+        outcome.as_str(),
+        rating
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let entries = sqlx::query!(
+        "SELECT item_id, weight, qty_min, qty_max
+           FROM loot_entries WHERE table_id = $1 ORDER BY id",
+        row.id
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|e| LootEntry {
+        item_id: e.item_id,
+        weight: e.weight as u32,
+        qty_min: e.qty_min,
+        qty_max: e.qty_max,
+    })
+    .collect();
+
+    Ok(Some(LootTable {
+        id: row.id,
+        credits_min: row.credits_min,
+        credits_max: row.credits_max,
+        rolls: row.rolls as u32,
+        entries,
+    }))
+}
+
+/// Roll and pay one seat’s reward for `game_id`. `None` if no table
+/// applies or the seat was already rewarded for this game.
+pub async fn award(
+    conn: &mut PgConnection,
+    game_id: Uuid,
+    player: Uuid,
+    mode: GameMode,
+    outcome: Outcome,
+    rating: i32,
+) -> Result<Option<MatchReward>> {
+    let Some(table) = find_table(conn, mode, outcome, rating).await? else {
+        return Ok(None);
+    };
+    let seed = loot_seed(settings().loot_secret.as_bytes(), game_id, player);
+    let roll = table.roll(&mut CombatRng::new(seed));
+
+    let inserted = sqlx::query!(
+        "INSERT INTO match_rewards (game_id, player_id, table_id, seed, credits, items)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT DO NOTHING",
+        game_id,
+        player,
+        table.id,
+        seed as i64,
+        roll.credits,
+        serde_json::to_value(&roll.items)?
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if inserted == 0 {
+        return Ok(None);
+    }
+
+    ledger_repo::transfer(
+        conn,
+        Account::System(SystemAccount::Rewards),
+        Account::Player(player),
+        roll.credits,
+        Reason::Reward,
+        Some(&format!("game:{game_id}")),
+    )
+    .await?;
+    let mut granted = Vec::with_capacity(roll.items.len());
+    for it in &roll.items {
+        let qty = grant_item(conn, player, it.item_id, it.qty).await?;
+        if qty > 0 {
+            granted.push(BundleItem {
+                item_id: it.item_id,
+                qty,
+            });
+        }
+    }
+    if granted != roll.items {
+        sqlx::query!(
+            "UPDATE match_rewards SET items = $3 WHERE game_id = $1 AND player_id = $2",
+            game_id,
+            player,
+            serde_json::to_value(&granted)?
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(Some(MatchReward {
+        player_id: player,
+        outcome,
+        table_id: table.id,
+        seed,
+        credits: roll.credits,
+        items: granted,
+    }))
+}
+
+/// Add up to `qty` of an item, capped at its `max_stack`; returns how many
+/// were added.
+async fn grant_item(conn: &mut PgConnection, player: Uuid, item_id: i32, qty: i32) -> Result<i32> {
+    let held = sqlx::query_scalar!(
+        "SELECT quantity FROM player_items
+          WHERE player_id = $1 AND item_id = $2
+          FOR UPDATE",
+        player,
+        item_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .unwrap_or(0);
+    let now = sqlx::query_scalar!(
+        r#"INSERT INTO player_items (player_id, item_id, quantity)
+           SELECT $1, id, LEAST($3, max_stack) FROM items WHERE id = $2
+           ON CONFLICT (player_id, item_id)
+           DO UPDATE SET quantity = LEAST(
+               player_items.quantity + EXCLUDED.quantity,
+               (SELECT max_stack FROM items WHERE id = $2))
+           RETURNING quantity"#,
+        player,
+        item_id,
+        qty
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(now.map_or(0, |n| (n - held).clamp(0, qty)))
+}
@@ -0,0 +1,520 @@
+//! Player marketplace: one limit order book per item.
+//!
+//! Placing an order escrows its cost (credits for a buy, items for a sell)
+//! and immediately matches it against the opposite side of the book at the
+//! resting order’s price, best price first, oldest first. Each fill is
+//! written to `trades`; the fee is taken from the seller’s proceeds and
+//! moved into the `market_fees` economy sink (and ledger account).
+//!
+//! Soulbound and untradable items have no book; a buy order is checked
+//! against the buyer’s `max_stack` when it is placed and again on every
+//! fill. A resting buy whose owner no longer has room is cancelled and
+//! refunded instead of filled.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::{
+    ledger_repo::{self, Account, Reason, SystemAccount},
+    trade_repo::{self, Bundle, BundleItem},
+};
+
+/// Sink row credited with every market fee.
+pub const FEE_SINK: &str = "market_fees";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl OrderSide {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "buy" => OrderSide::Buy,
+            "sell" => OrderSide::Sell,
+            other => bail!("unknown order side {other}"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Open,
+    Filled,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderStatus::Open => "open",
+            OrderStatus::Filled => "filled",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "open" => OrderStatus::Open,
+            "filled" => OrderStatus::Filled,
+            "cancelled" => OrderStatus::Cancelled,
+            other => bail!("unknown order status {other}"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Order {
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub item_id: i32,
+    pub side: OrderSide,
+    /// Limit price per unit.
+    pub price: i64,
+    pub qty: i32,
+    /// Units not yet filled.
+    pub remaining: i32,
+    pub status: OrderStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One match between a buy and a sell order.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub trade_id: i32,
+    pub buyer_id: Uuid,
+    pub seller_id: Uuid,
+    pub qty: i32,
+    /// Per-unit price paid (the resting order’s limit).
+    pub price: i64,
+    /// Taken from the seller’s proceeds.
+    pub fee: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Placed {
+    pub order: Order,
+    pub fills: Vec<Fill>,
+}
+
+/// Aggregated open quantity at one price.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Level {
+    pub price: i64,
+    pub qty: i64,
+    pub orders: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Depth {
+    pub item_id: i32,
+    /// Highest price first.
+    pub bids: Vec<Level>,
+    /// Lowest price first.
+    pub asks: Vec<Level>,
+}
+
+/// Fee in credits on a `gross` fill, rounded down.
+pub fn fee_for(gross: i64, fee_bps: u32) -> i64 {
+    gross.saturating_mul(fee_bps as i64) / 10_000
+}
+
+struct OrderRow {
+    id: Uuid,
+    player_id: Uuid,
+    item_id: i32,
+    side: String,
+    price: i64,
+    qty: i32,
+    remaining: i32,
+    status: String,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<OrderRow> for Order {
+    type Error = anyhow::Error;
+
+    fn try_from(r: OrderRow) -> Result<Self> {
+        Ok(Order {
+            id: r.id,
+            player_id: r.player_id,
+            item_id: r.item_id,
+            side: OrderSide::parse(&r.side)?,
+            price: r.price,
+            qty: r.qty,
+            remaining: r.remaining,
+            status: OrderStatus::parse(&r.status)?,
+            created_at: r.created_at,
+        })
+    }
+}
+
+//////////////////////////////////////////////////
+// Public operations (each one transaction)
+//////////////////////////////////////////////////
+
+/// Escrow, insert and match a limit order. Whatever is not filled rests
+/// on the book.
+pub async fn place(
+    db: &PgPool,
+    player: Uuid,
+    item_id: i32,
+    side: OrderSide,
+    price: i64,
+    qty: i32,
+    fee_bps: u32,
+) -> Result<Placed> {
+    if price <= 0 || qty <= 0 {
+        bail!("price and qty must be > 0");
+    }
+    let cost = price
+        .checked_mul(qty as i64)
+        .ok_or_else(|| anyhow!("order too large"))?;
+
+    let mut tx = db.begin().await?;
+
+    // Serialises matching per item; also rejects unknown ids.
+    sqlx::query_scalar!("SELECT id FROM items WHERE id = $1 FOR UPDATE", item_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| anyhow!("unknown item {item_id}"))?;
+    trade_repo::ensure_tradable(&mut tx, item_id).await?;
+    if side == OrderSide::Buy {
+        trade_repo::ensure_room(&mut tx, player, item_id, qty).await?;
+    }
+
+    let mut order: Order = sqlx::query_as!(
+        OrderRow,
+        "INSERT INTO market_orders (player_id, item_id, side, price, qty, remaining)
+         VALUES ($1, $2, $3, $4, $5, $5)
+         RETURNING id, player_id, item_id, side, price, qty, remaining, status, created_at",
+        player,
+        item_id,
+        side.as_str(),
+        price,
+        qty
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .try_into()?;
+
+    trade_repo::debit(
+        &mut tx,
+        player,
+        &escrow(side, item_id, cost, qty),
+        &order.id.to_string(),
+    )
+    .await?;
+
+    let mut fills = Vec::new();
+    while order.remaining > 0 {
+        let Some(mut maker) = best_match(&mut tx, &order).await? else {
+            break;
+        };
+        let fill_qty = order.remaining.min(maker.remaining);
+        if maker.side == OrderSide::Buy
+            && !trade_repo::has_room(&mut tx, maker.player_id, item_id, fill_qty).await?
+        {
+            withdraw(&mut tx, &mut maker).await?;
+            continue;
+        }
+        fills.push(settle(&mut tx, &mut order, &mut maker, fee_bps).await?);
+    }
+
+    tx.commit().await?;
+    Ok(Placed { order, fills })
+}
+
+/// Withdraw the unfilled part of an order and refund its escrow.
+pub async fn cancel(db: &PgPool, player: Uuid, order_id: Uuid) -> Result<Order> {
+    let mut tx = db.begin().await?;
+    let mut order: Order = sqlx::query_as!(
+        OrderRow,
+        "SELECT id, player_id, item_id, side, price, qty, remaining, status, created_at
+           FROM market_orders
+          WHERE id = $1 AND player_id = $2 AND status = 'open'
+          FOR UPDATE",
+        order_id,
+        player
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| anyhow!("order not found or no longer open"))?
+    .try_into()?;
+
+    withdraw(&mut tx, &mut order).await?;
+
+    tx.commit().await?;
+    Ok(order)
+}
+
+/// The player’s open orders, newest first.
+pub async fn list_open(db: &PgPool, player: Uuid) -> Result<Vec<Order>> {
+    sqlx::query_as!(
+        OrderRow,
+        "SELECT id, player_id, item_id, side, price, qty, remaining, status, created_at
+           FROM market_orders
+          WHERE player_id = $1 AND status = 'open'
+          ORDER BY created_at DESC",
+        player
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(Order::try_from)
+    .collect()
+}
+
+/// Top `levels` price levels on each side of an item’s book.
+pub async fn depth(db: &PgPool, item_id: i32, levels: i64) -> Result<Depth> {
+    let rows = sqlx::query!(
+        r#"SELECT side, price,
+                  SUM(remaining)::BIGINT AS "qty!",
+                  COUNT(*)               AS "orders!"
+             FROM market_orders
+            WHERE item_id = $1 AND status = 'open'
+            GROUP BY side, price"#,
+        item_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    let (mut bids, mut asks) = (Vec::new(), Vec::new());
+    for r in rows {
+        let level = Level {
+            price: r.price,
+            qty: r.qty,
+            orders: r.orders,
+        };
+        match OrderSide::parse(&r.side)? {
+            OrderSide::Buy => bids.push(level),
+            OrderSide::Sell => asks.push(level),
+        }
+    }
+    bids.sort_by_key(|l| std::cmp::Reverse(l.price));
+    asks.sort_by_key(|l| l.price);
+    bids.truncate(levels.max(0) as usize);
+    asks.truncate(levels.max(0) as usize);
+
+    Ok(Depth {
+        item_id,
+        bids,
+        asks,
+    })
+}
+
+/// Total credits collected by the market fee sink.
+pub async fn fees_collected(db: &PgPool) -> Result<i64> {
+    let balance = sqlx::query_scalar!(
+        "SELECT balance FROM economy_sinks WHERE name = $1",
+        FEE_SINK
+    )
+    .fetch_one(db)
+    .await
+    .context("market fee sink missing")?;
+    Ok(balance)
+}
+
+//////////////////////////////////////////////////
+// Matching helpers (run inside the caller’s transaction)
+//////////////////////////////////////////////////
+
+/// What an order of `side` holds in escrow.
+fn escrow(side: OrderSide, item_id: i32, credits: i64, qty: i32) -> Bundle {
+    match side {
+        OrderSide::Buy => Bundle {
+            credits,
+            items: vec![],
+        },
+        OrderSide::Sell => Bundle {
+            credits: 0,
+            items: vec![BundleItem { item_id, qty }],
+        },
+    }
+}
+
+/// Cancel an open order and refund the escrow of its unfilled part.
+async fn withdraw(conn: &mut PgConnection, order: &mut Order) -> Result<()> {
+    let refund = escrow(
+        order.side,
+        order.item_id,
+        order.price * order.remaining as i64,
+        order.remaining,
+    );
+    trade_repo::credit(conn, order.player_id, &refund, &order.id.to_string()).await?;
+    order.status = OrderStatus::Cancelled;
+    update(conn, order).await
+}
+
+/// Best crossing resting order on the other side, skipping the taker’s own.
+async fn best_match(conn: &mut PgConnection, taker: &Order) -> Result<Option<Order>> {
+    let row = match taker.side {
+        OrderSide::Buy => {
+            sqlx::query_as!(
+                OrderRow,
+                "SELECT id, player_id, item_id, side, price, qty, remaining, status, created_at
+                   FROM market_orders
+                  WHERE item_id = $1 AND side = 'sell' AND status = 'open'
+                    AND price <= $2 AND player_id <> $3
+                  ORDER BY price ASC, created_at ASC
+                  LIMIT 1
+                  FOR UPDATE",
+                taker.item_id,
+                taker.price,
+                taker.player_id
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        OrderSide::Sell => {
+            sqlx::query_as!(
+                OrderRow,
+                "SELECT id, player_id, item_id, side, price, qty, remaining, status, created_at
+                   FROM market_orders
+                  WHERE item_id = $1 AND side = 'buy' AND status = 'open'
+                    AND price >= $2 AND player_id <> $3
+                  ORDER BY price DESC, created_at ASC
+                  LIMIT 1
+                  FOR UPDATE",
+                taker.item_id,
+                taker.price,
+                taker.player_id
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+    };
+    row.map(Order::try_from).transpose()
+}
+
+/// Fill `taker` against `maker` at the maker’s price.
+async fn settle(
+    conn: &mut PgConnection,
+    taker: &mut Order,
+    maker: &mut Order,
+    fee_bps: u32,
+) -> Result<Fill> {
+    let qty = taker.remaining.min(maker.remaining);
+    let price = maker.price;
+    let gross = price * qty as i64;
+    let fee = fee_for(gross, fee_bps);
+
+    let (buy, sell) = match taker.side {
+        OrderSide::Buy => (&*taker, &*maker),
+        OrderSide::Sell => (&*maker, &*taker),
+    };
+    let (buyer_id, seller_id) = (buy.player_id, sell.player_id);
+    let (buy_order, sell_order) = (buy.id, sell.id);
+    // A buy escrowed at its own limit; return the price improvement.
+    let refund = (buy.price - price) * qty as i64;
+
+    // Other fills since the order was placed may have used up the room.
+    trade_repo::ensure_room(conn, buyer_id, taker.item_id, qty).await?;
+
+    let trade_id = sqlx::query_scalar!(
+        r#"INSERT INTO trades
+               (from_player, to_player, item_id, qty, price, buy_order_id, sell_order_id, fee)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+           RETURNING id"#,
+        seller_id,
+        buyer_id,
+        taker.item_id,
+        qty,
+        gross,
+        buy_order,
+        sell_order,
+        fee
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let ref_id = trade_id.to_string();
+
+    trade_repo::credit(
+        conn,
+        buyer_id,
+        &Bundle {
+            credits: refund,
+            items: vec![BundleItem {
+                item_id: taker.item_id,
+                qty,
+            }],
+        },
+        &ref_id,
+    )
+    .await?;
+    trade_repo::credit(
+        conn,
+        seller_id,
+        &Bundle {
+            credits: gross - fee,
+            items: vec![],
+        },
+        &ref_id,
+    )
+    .await?;
+    ledger_repo::transfer(
+        conn,
+        Account::System(SystemAccount::Escrow),
+        Account::System(SystemAccount::MarketFees),
+        fee,
+        Reason::Fee,
+        Some(&ref_id),
+    )
+    .await?;
+    if fee > 0 {
+        sqlx::query!(
+            "UPDATE economy_sinks SET balance = balance + $2, updated_at = NOW()
+              WHERE name = $1",
+            FEE_SINK,
+            fee
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    for o in [&mut *taker, &mut *maker] {
+        o.remaining -= qty;
+        if o.remaining == 0 {
+            o.status = OrderStatus::Filled;
+        }
+    }
+    update(conn, maker).await?;
+    update(conn, taker).await?;
+
+    Ok(Fill {
+        trade_id,
+        buyer_id,
+        seller_id,
+        qty,
+        price,
+        fee,
+    })
+}
+
+async fn update(conn: &mut PgConnection, o: &Order) -> Result<()> {
+    sqlx::query!(
+        "UPDATE market_orders
+            SET remaining = $2, status = $3,
+                closed_at = CASE WHEN $3 = 'open' THEN NULL ELSE NOW() END
+          WHERE id = $1",
+        o.id,
+        o.remaining,
+        o.status.as_str()
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
@@ -1,5 +1,13 @@
+pub mod contest_repo;
+pub mod craft_repo;
 pub mod elo_repo;
 pub mod faction_repo;
 pub mod land_repo;
+pub mod ledger_repo;
+pub mod loot_repo;
+pub mod market_repo;
 pub mod models;
+pub mod quest_repo;
 pub mod schema;
+pub mod trade_repo;
+pub mod treasury_repo;
@@ -0,0 +1,297 @@
+//! Quest and achievement definitions, per-player progress and claiming.
+//!
+//! Progress rows are keyed by period: the UTC date for daily quests, the
+//! fixed [`achievement_period`] for achievements. A finished quest pays
+//! its reward once, from the `rewards` ledger account. Definitions are
+//! served from `cache::QUESTS`, not re-read per event.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    cache,
+    db::{
+        ledger_repo::{self, Account, Reason, SystemAccount},
+        trade_repo::{self, Bundle, BundleItem},
+    },
+    progression::{achievement_period, daily_period, daily_rotation, PlayerEvent},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestKind {
+    Daily,
+    Achievement,
+}
+
+impl QuestKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuestKind::Daily => "daily",
+            QuestKind::Achievement => "achievement",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "daily" => QuestKind::Daily,
+            "achievement" => QuestKind::Achievement,
+            other => bail!("unknown quest kind {other}"),
+        })
+    }
+}
+
+/// One row of `quests`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestDef {
+    pub id: String,
+    pub kind: QuestKind,
+    pub name: String,
+    pub description: Option<String>,
+    /// Event kind counted (`progression::EventKind`).
+    pub event: String,
+    pub target: i32,
+    pub reward_credits: i64,
+    pub reward_item: Option<BundleItem>,
+}
+
+/// A quest as seen by one player in the current period.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestProgress {
+    #[serde(flatten)]
+    pub quest: QuestDef,
+    pub period: NaiveDate,
+    pub progress: i32,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub claimed_at: Option<DateTime<Utc>>,
+}
+
+struct DefRow {
+    id: String,
+    kind: String,
+    name: String,
+    description: Option<String>,
+    event: String,
+    target: i32,
+    reward_credits: i64,
+    reward_item_id: Option<i32>,
+    reward_qty: i32,
+}
+
+impl TryFrom<DefRow> for QuestDef {
+    type Error = anyhow::Error;
+
+    fn try_from(r: DefRow) -> Result<Self> {
+        Ok(QuestDef {
+            id: r.id,
+            kind: QuestKind::parse(&r.kind)?,
+            name: r.name,
+            description: r.description,
+            event: r.event,
+            target: r.target,
+            reward_credits: r.reward_credits,
+            reward_item: r
+                .reward_item_id
+                .filter(|_| r.reward_qty > 0)
+                .map(|item_id| BundleItem {
+                    item_id,
+                    qty: r.reward_qty,
+                }),
+        })
+    }
+}
+
+/// Every active quest definition, ordered by id (feeds `cache::QUESTS`).
+pub async fn active_defs(db: &PgPool) -> Result<Vec<QuestDef>> {
+    sqlx::query_as!(
+        DefRow,
+        "SELECT id, kind, name, description, event, target,
+                reward_credits, reward_item_id, reward_qty
+           FROM quests
+          WHERE active
+          ORDER BY id"
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(QuestDef::try_from)
+    .collect()
+}
+
+/// Quests of `kind` that are live in `day`’s period: today’s rotation for
+/// dailies, every active achievement. Read from the cached catalogue.
+pub fn current(kind: QuestKind, day: NaiveDate, daily_count: u32) -> Vec<QuestDef> {
+    let mut defs: Vec<QuestDef> = cache::QUESTS
+        .iter()
+        .filter(|e| e.kind == kind)
+        .map(|e| e.value().clone())
+        .collect();
+    defs.sort_by(|a, b| a.id.cmp(&b.id));
+
+    match kind {
+        QuestKind::Achievement => defs,
+        QuestKind::Daily => {
+            let ids: Vec<String> = defs.iter().map(|d| d.id.clone()).collect();
+            let picked = daily_rotation(&ids, day, daily_count as usize);
+            defs.into_iter()
+                .filter(|d| picked.contains(&d.id))
+                .collect()
+        }
+    }
+}
+
+fn period(kind: QuestKind, day: NaiveDate) -> NaiveDate {
+    match kind {
+        QuestKind::Daily => day,
+        QuestKind::Achievement => achievement_period(),
+    }
+}
+
+/// Apply one bus event to every quest live when it happened. The event’s
+/// own timestamp picks the period, so a backlog drained after midnight
+/// still counts toward the day it was emitted on.
+pub async fn record(db: &PgPool, ev: &PlayerEvent, daily_count: u32) -> Result<()> {
+    let day = daily_period(ev.at);
+    let mut tx = db.begin().await?;
+    for kind in [QuestKind::Daily, QuestKind::Achievement] {
+        let live = current(kind, day, daily_count);
+        for q in live.iter().filter(|q| q.event == ev.kind.as_str()) {
+            sqlx::query!(
+                "INSERT INTO player_quests (player_id, quest_id, period, progress, completed_at)
+                 VALUES ($1, $2, $3, LEAST($4, $5),
+                         CASE WHEN $4 >= $5 THEN $6 END)
+                 ON CONFLICT (player_id, quest_id, period) DO UPDATE
+                    SET progress     = LEAST(player_quests.progress + $4, $5),
+                        completed_at = COALESCE(player_quests.completed_at,
+                                                CASE WHEN player_quests.progress + $4 >= $5
+                                                     THEN $6 END)",
+                ev.player,
+                q.id,
+                period(kind, day),
+                ev.amount,
+                q.target,
+                ev.at
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Live quests of `kind` with the player’s progress.
+pub async fn list(
+    db: &PgPool,
+    player: Uuid,
+    kind: QuestKind,
+    day: NaiveDate,
+    daily_count: u32,
+) -> Result<Vec<QuestProgress>> {
+    let live = current(kind, day, daily_count);
+    let period = period(kind, day);
+    let rows = sqlx::query!(
+        "SELECT quest_id, progress, completed_at, claimed_at
+           FROM player_quests
+          WHERE player_id = $1 AND period = $2",
+        player,
+        period
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(live
+        .into_iter()
+        .map(|quest| {
+            let row = rows.iter().find(|r| r.quest_id == quest.id);
+            QuestProgress {
+                period,
+                progress: row.map_or(0, |r| r.progress),
+                completed_at: row.and_then(|r| r.completed_at),
+                claimed_at: row.and_then(|r| r.claimed_at),
+                quest,
+            }
+        })
+        .collect())
+}
+
+/// Pay out a completed quest of `kind` in the current period, once.
+pub async fn claim(
+    db: &PgPool,
+    player: Uuid,
+    kind: QuestKind,
+    quest_id: &str,
+    day: NaiveDate,
+    daily_count: u32,
+) -> Result<QuestProgress> {
+    let quest = current(kind, day, daily_count)
+        .into_iter()
+        .find(|q| q.id == quest_id)
+        .ok_or_else(|| anyhow!("no current {} {quest_id}", kind.as_str()))?;
+    let period = period(kind, day);
+    let mut tx = db.begin().await?;
+
+    let row = sqlx::query!(
+        "SELECT progress, completed_at, claimed_at
+           FROM player_quests
+          WHERE player_id = $1 AND quest_id = $2 AND period = $3
+          FOR UPDATE",
+        player,
+        quest_id,
+        period
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .filter(|r| r.completed_at.is_some())
+    .ok_or_else(|| anyhow!("quest not completed"))?;
+    if row.claimed_at.is_some() {
+        bail!("reward already claimed");
+    }
+
+    let ref_id = format!("quest:{quest_id}:{period}");
+    ledger_repo::transfer(
+        &mut tx,
+        Account::System(SystemAccount::Rewards),
+        Account::Player(player),
+        quest.reward_credits,
+        Reason::Reward,
+        Some(&ref_id),
+    )
+    .await?;
+    if let Some(it) = &quest.reward_item {
+        trade_repo::ensure_room(&mut tx, player, it.item_id, it.qty).await?;
+        trade_repo::credit(
+            &mut tx,
+            player,
+            &Bundle {
+                credits: 0,
+                items: vec![it.clone()],
+            },
+            &ref_id,
+        )
+        .await?;
+    }
+
+    let claimed_at = sqlx::query_scalar!(
+        r#"UPDATE player_quests SET claimed_at = NOW()
+            WHERE player_id = $1 AND quest_id = $2 AND period = $3
+        RETURNING claimed_at AS "claimed_at!""#,
+        player,
+        quest_id,
+        period
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(QuestProgress {
+        quest,
+        period,
+        progress: row.progress,
+        completed_at: row.completed_at,
+        claimed_at: Some(claimed_at),
+    })
+}
@@ -0,0 +1,549 @@
+//! Two-party trade offers with escrow.
+//!
+//! Lifecycle: `open` → `accepted` | `cancelled` | `countered` | `expired`.
+//! The proposer’s side is taken into escrow on propose and either handed to
+//! the recipient on accept or refunded on every other exit.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::ledger_repo::{self, Account, Reason, SystemAccount};
+
+/// Upper bound on distinct item types per side.
+pub const MAX_BUNDLE_ITEMS: usize = 16;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BundleItem {
+    pub item_id: i32,
+    pub qty: i32,
+}
+
+/// One side of a trade: credits plus any number of item stacks.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Bundle {
+    #[serde(default)]
+    pub credits: i64,
+    #[serde(default)]
+    pub items: Vec<BundleItem>,
+}
+
+impl Bundle {
+    pub fn is_empty(&self) -> bool {
+        self.credits == 0 && self.items.is_empty()
+    }
+
+    /// Reject negative amounts and merge duplicate item ids.
+    pub fn normalized(&self) -> Result<Bundle> {
+        if self.credits < 0 {
+            bail!("credits must be >= 0");
+        }
+        let mut items: Vec<BundleItem> = Vec::with_capacity(self.items.len());
+        for it in &self.items {
+            if it.qty <= 0 {
+                bail!("qty must be > 0");
+            }
+            match items.iter_mut().find(|x| x.item_id == it.item_id) {
+                Some(x) => {
+                    x.qty = x
+                        .qty
+                        .checked_add(it.qty)
+                        .ok_or_else(|| anyhow!("qty overflow"))?
+                }
+                None => items.push(it.clone()),
+            }
+        }
+        if items.len() > MAX_BUNDLE_ITEMS {
+            bail!("at most {MAX_BUNDLE_ITEMS} item types per side");
+        }
+        items.sort_by_key(|x| x.item_id);
+        Ok(Bundle {
+            credits: self.credits,
+            items,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeStatus {
+    Open,
+    Accepted,
+    Cancelled,
+    Countered,
+    Expired,
+}
+
+impl TradeStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TradeStatus::Open => "open",
+            TradeStatus::Accepted => "accepted",
+            TradeStatus::Cancelled => "cancelled",
+            TradeStatus::Countered => "countered",
+            TradeStatus::Expired => "expired",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "open" => TradeStatus::Open,
+            "accepted" => TradeStatus::Accepted,
+            "cancelled" => TradeStatus::Cancelled,
+            "countered" => TradeStatus::Countered,
+            "expired" => TradeStatus::Expired,
+            other => bail!("unknown trade status {other}"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TradeOffer {
+    pub id: Uuid,
+    pub proposer_id: Uuid,
+    pub recipient_id: Uuid,
+    /// Escrowed from the proposer.
+    pub offer: Bundle,
+    /// Owed by the recipient on accept.
+    pub ask: Bundle,
+    pub status: TradeStatus,
+    /// Offer this one counters, if any.
+    pub parent_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+//////////////////////////////////////////////////
+// Public operations (each one transaction)
+//////////////////////////////////////////////////
+
+/// Open a new offer and move the proposer’s side into escrow.
+pub async fn propose(
+    db: &PgPool,
+    proposer: Uuid,
+    recipient: Uuid,
+    offer: &Bundle,
+    ask: &Bundle,
+    ttl: Duration,
+) -> Result<TradeOffer> {
+    let mut tx = db.begin().await?;
+    let created = insert_offer(&mut tx, proposer, recipient, offer, ask, ttl, None).await?;
+    tx.commit().await?;
+    Ok(created)
+}
+
+/// Recipient answers with new terms: the original is closed as `countered`
+/// (escrow refunded) and a reverse offer is opened with its own escrow.
+/// Returns (closed original, new offer).
+pub async fn counter(
+    db: &PgPool,
+    actor: Uuid,
+    offer_id: Uuid,
+    offer: &Bundle,
+    ask: &Bundle,
+    ttl: Duration,
+) -> Result<(TradeOffer, TradeOffer)> {
+    let mut tx = db.begin().await?;
+    let mut original = lock_open(&mut tx, offer_id).await?;
+    if original.recipient_id != actor {
+        bail!("only the recipient may counter");
+    }
+    credit(
+        &mut tx,
+        original.proposer_id,
+        &original.offer,
+        &original.id.to_string(),
+    )
+    .await?;
+    close(&mut tx, &mut original, TradeStatus::Countered).await?;
+
+    let created = insert_offer(
+        &mut tx,
+        actor,
+        original.proposer_id,
+        offer,
+        ask,
+        ttl,
+        Some(original.id),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok((original, created))
+}
+
+/// Recipient accepts: their side is debited and both sides settle at once.
+pub async fn accept(db: &PgPool, actor: Uuid, offer_id: Uuid) -> Result<TradeOffer> {
+    let mut tx = db.begin().await?;
+    let mut o = lock_open(&mut tx, offer_id).await?;
+    if o.recipient_id != actor {
+        bail!("only the recipient may accept");
+    }
+    debit(&mut tx, o.recipient_id, &o.ask, &o.id.to_string()).await?;
+    for it in &o.offer.items {
+        ensure_room(&mut tx, o.recipient_id, it.item_id, it.qty).await?;
+    }
+    for it in &o.ask.items {
+        ensure_room(&mut tx, o.proposer_id, it.item_id, it.qty).await?;
+    }
+    credit(&mut tx, o.recipient_id, &o.offer, &o.id.to_string()).await?;
+    credit(&mut tx, o.proposer_id, &o.ask, &o.id.to_string()).await?;
+    close(&mut tx, &mut o, TradeStatus::Accepted).await?;
+    tx.commit().await?;
+    Ok(o)
+}
+
+/// Proposer withdraws an open offer; escrow is refunded.
+pub async fn cancel(db: &PgPool, actor: Uuid, offer_id: Uuid) -> Result<TradeOffer> {
+    let mut tx = db.begin().await?;
+    let mut o = lock_open(&mut tx, offer_id).await?;
+    if o.proposer_id != actor {
+        bail!("only the proposer may cancel");
+    }
+    credit(&mut tx, o.proposer_id, &o.offer, &o.id.to_string()).await?;
+    close(&mut tx, &mut o, TradeStatus::Cancelled).await?;
+    tx.commit().await?;
+    Ok(o)
+}
+
+/// Close every open offer past its deadline and refund its escrow.
+pub async fn expire_due(db: &PgPool) -> Result<Vec<TradeOffer>> {
+    let mut tx = db.begin().await?;
+    let ids = sqlx::query_scalar!(
+        "SELECT id FROM trade_offers
+          WHERE status = 'open' AND expires_at <= NOW()
+          FOR UPDATE SKIP LOCKED"
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .context("selecting expired offers")?;
+
+    let mut expired = Vec::with_capacity(ids.len());
+    for id in ids {
+        let mut o = load(&mut tx, id).await?;
+        credit(&mut tx, o.proposer_id, &o.offer, &o.id.to_string()).await?;
+        close(&mut tx, &mut o, TradeStatus::Expired).await?;
+        expired.push(o);
+    }
+    tx.commit().await?;
+    Ok(expired)
+}
+
+/// Open offers the player sent or received, newest first.
+pub async fn list_open(db: &PgPool, player: Uuid) -> Result<Vec<TradeOffer>> {
+    let mut conn = db.acquire().await?;
+    let ids = sqlx::query_scalar!(
+        "SELECT id FROM trade_offers
+          WHERE status = 'open' AND expires_at > NOW()
+            AND (proposer_id = $1 OR recipient_id = $1)
+          ORDER BY created_at DESC",
+        player
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut out = Vec::with_capacity(ids.len());
+    for id in ids {
+        out.push(load(&mut conn, id).await?);
+    }
+    Ok(out)
+}
+
+//////////////////////////////////////////////////
+// Helpers (run inside the caller’s transaction)
+//////////////////////////////////////////////////
+
+async fn insert_offer(
+    conn: &mut PgConnection,
+    proposer: Uuid,
+    recipient: Uuid,
+    offer: &Bundle,
+    ask: &Bundle,
+    ttl: Duration,
+    parent: Option<Uuid>,
+) -> Result<TradeOffer> {
+    if proposer == recipient {
+        bail!("cannot trade with yourself");
+    }
+    let (offer, ask) = (offer.normalized()?, ask.normalized()?);
+    if offer.is_empty() && ask.is_empty() {
+        bail!("empty trade");
+    }
+    for it in offer.items.iter().chain(&ask.items) {
+        ensure_tradable(conn, it.item_id).await?;
+    }
+
+    let row = sqlx::query!(
+        r#"INSERT INTO trade_offers
+               (proposer_id, recipient_id, offer_credits, ask_credits, parent_id, expires_at)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           RETURNING id, created_at, expires_at"#,
+        proposer,
+        recipient,
+        offer.credits,
+        ask.credits,
+        parent,
+        Utc::now() + ttl
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .context("unknown recipient")?;
+
+    debit(conn, proposer, &offer, &row.id.to_string()).await?;
+
+    for (side, bundle) in [("offer", &offer), ("ask", &ask)] {
+        for it in &bundle.items {
+            sqlx::query!(
+                "INSERT INTO trade_offer_items (offer_id, side, item_id, qty)
+                 VALUES ($1, $2, $3, $4)",
+                row.id,
+                side,
+                it.item_id,
+                it.qty
+            )
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("unknown item {}", it.item_id))?;
+        }
+    }
+
+    Ok(TradeOffer {
+        id: row.id,
+        proposer_id: proposer,
+        recipient_id: recipient,
+        offer,
+        ask,
+        status: TradeStatus::Open,
+        parent_id: parent,
+        created_at: row.created_at,
+        expires_at: row.expires_at,
+    })
+}
+
+/// Lock an offer that is still open and not yet past its deadline.
+async fn lock_open(conn: &mut PgConnection, offer_id: Uuid) -> Result<TradeOffer> {
+    let open = sqlx::query_scalar!(
+        "SELECT id FROM trade_offers
+          WHERE id = $1 AND status = 'open' AND expires_at > NOW()
+          FOR UPDATE",
+        offer_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if open.is_none() {
+        bail!("offer not found or no longer open");
+    }
+    load(conn, offer_id).await
+}
+
+async fn load(conn: &mut PgConnection, offer_id: Uuid) -> Result<TradeOffer> {
+    let row = sqlx::query!(
+        "SELECT id, proposer_id, recipient_id, offer_credits, ask_credits,
+                status, parent_id, created_at, expires_at
+           FROM trade_offers WHERE id = $1",
+        offer_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .context("offer not found")?;
+
+    let items = sqlx::query!(
+        "SELECT side, item_id, qty FROM trade_offer_items
+          WHERE offer_id = $1 ORDER BY item_id",
+        offer_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let side = |name: &str, credits: i64| Bundle {
+        credits,
+        items: items
+            .iter()
+            .filter(|r| r.side == name)
+            .map(|r| BundleItem {
+                item_id: r.item_id,
+                qty: r.qty,
+            })
+            .collect(),
+    };
+
+    Ok(TradeOffer {
+        id: row.id,
+        proposer_id: row.proposer_id,
+        recipient_id: row.recipient_id,
+        offer: side("offer", row.offer_credits),
+        ask: side("ask", row.ask_credits),
+        status: TradeStatus::parse(&row.status)?,
+        parent_id: row.parent_id,
+        created_at: row.created_at,
+        expires_at: row.expires_at,
+    })
+}
+
+async fn close(conn: &mut PgConnection, o: &mut TradeOffer, status: TradeStatus) -> Result<()> {
+    sqlx::query!(
+        "UPDATE trade_offers SET status = $2, closed_at = NOW() WHERE id = $1",
+        o.id,
+        status.as_str()
+    )
+    .execute(&mut *conn)
+    .await?;
+    o.status = status;
+    Ok(())
+}
+
+/// Take a bundle from a player into escrow; fails without side effects on
+/// shortfall (the caller’s transaction is dropped). `ref_id` tags the
+/// ledger entries.
+pub(crate) async fn debit(
+    conn: &mut PgConnection,
+    player: Uuid,
+    b: &Bundle,
+    ref_id: &str,
+) -> Result<()> {
+    ledger_repo::transfer(
+        conn,
+        Account::Player(player),
+        Account::System(SystemAccount::Escrow),
+        b.credits,
+        Reason::Trade,
+        Some(ref_id),
+    )
+    .await?;
+    for it in &b.items {
+        take_item(conn, player, it.item_id, it.qty).await?;
+    }
+    Ok(())
+}
+
+/// Remove `qty` of an item from a player’s inventory, or fail on shortfall.
+pub(crate) async fn take_item(
+    conn: &mut PgConnection,
+    player: Uuid,
+    item_id: i32,
+    qty: i32,
+) -> Result<()> {
+    // `player_items.quantity` must stay > 0, so a full stack is removed.
+    let mut rows = sqlx::query!(
+        "DELETE FROM player_items
+          WHERE player_id = $1 AND item_id = $2 AND quantity = $3",
+        player,
+        item_id,
+        qty
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if rows == 0 {
+        rows = sqlx::query!(
+            "UPDATE player_items SET quantity = quantity - $3
+              WHERE player_id = $1 AND item_id = $2 AND quantity > $3",
+            player,
+            item_id,
+            qty
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+    }
+    if rows == 0 {
+        bail!("insufficient quantity of item {item_id}");
+    }
+    Ok(())
+}
+
+/// Fail unless the item may change hands between players (tradable and
+/// not soulbound).
+pub(crate) async fn ensure_tradable(conn: &mut PgConnection, item_id: i32) -> Result<()> {
+    let row = sqlx::query!(
+        "SELECT tradable, soulbound FROM items WHERE id = $1",
+        item_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| anyhow!("unknown item {item_id}"))?;
+    if row.soulbound {
+        bail!("item {item_id} is soulbound");
+    }
+    if !row.tradable {
+        bail!("item {item_id} is not tradable");
+    }
+    Ok(())
+}
+
+/// Fail if receiving `qty` more of an item would push the player past its
+/// `max_stack`.
+pub(crate) async fn ensure_room(
+    conn: &mut PgConnection,
+    player: Uuid,
+    item_id: i32,
+    qty: i32,
+) -> Result<()> {
+    if !has_room(conn, player, item_id, qty).await? {
+        let max = sqlx::query_scalar!("SELECT max_stack FROM items WHERE id = $1", item_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        bail!("item {item_id} stacks to at most {max}");
+    }
+    Ok(())
+}
+
+/// Whether the player can receive `qty` more of an item within its
+/// `max_stack`.
+pub(crate) async fn has_room(
+    conn: &mut PgConnection,
+    player: Uuid,
+    item_id: i32,
+    qty: i32,
+) -> Result<bool> {
+    let row = sqlx::query!(
+        r#"SELECT i.max_stack,
+                  COALESCE(pi.quantity, 0) AS "held!"
+             FROM items i
+             LEFT JOIN player_items pi
+               ON pi.item_id = i.id AND pi.player_id = $1
+            WHERE i.id = $2"#,
+        player,
+        item_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| anyhow!("unknown item {item_id}"))?;
+    Ok(row.held as i64 + qty as i64 <= row.max_stack as i64)
+}
+
+/// Release a bundle from escrow to a player. Refunds skip the stack check
+/// so escrow can always be returned; callers that hand items to a new
+/// owner run [`ensure_room`] first.
+pub(crate) async fn credit(
+    conn: &mut PgConnection,
+    player: Uuid,
+    b: &Bundle,
+    ref_id: &str,
+) -> Result<()> {
+    ledger_repo::transfer(
+        conn,
+        Account::System(SystemAccount::Escrow),
+        Account::Player(player),
+        b.credits,
+        Reason::Trade,
+        Some(ref_id),
+    )
+    .await?;
+    for it in &b.items {
+        sqlx::query!(
+            r#"INSERT INTO player_items (player_id, item_id, quantity)
+               VALUES ($1, $2, $3)
+               ON CONFLICT (player_id, item_id)
+               DO UPDATE SET quantity = player_items.quantity + EXCLUDED.quantity"#,
+            player,
+            it.item_id,
+            it.qty
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
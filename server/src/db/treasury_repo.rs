@@ -0,0 +1,256 @@
+//! Faction resource treasuries.
+//!
+//! Owned parcels pay into the treasury on each world tick (see
+//! `ecosystem::simulation`); structures, land claims and wars are paid out
+//! of it. Every movement is written to `faction_treasury_ledger` in the
+//! caller’s transaction, and a spend that would overdraw any resource fails.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::ecosystem::model::Resource;
+
+/// An amount of each treasury resource.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Resources {
+    pub energy: i64,
+    pub biomass: i64,
+    pub gene_seeds: i64,
+}
+
+impl Resources {
+    pub fn get(&self, r: Resource) -> i64 {
+        match r {
+            Resource::Energy => self.energy,
+            Resource::Biomass => self.biomass,
+            Resource::GeneSeeds => self.gene_seeds,
+        }
+    }
+
+    pub fn add(&mut self, r: Resource, amount: i64) {
+        match r {
+            Resource::Energy => self.energy += amount,
+            Resource::Biomass => self.biomass += amount,
+            Resource::GeneSeeds => self.gene_seeds += amount,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        Resource::ALL.iter().all(|&r| self.get(r) == 0)
+    }
+}
+
+/// Why resources moved (`faction_treasury_ledger.reason`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TreasuryReason {
+    Yield,
+    Structure,
+    War,
+    Admin,
+    Land,
+}
+
+impl TreasuryReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TreasuryReason::Yield => "yield",
+            TreasuryReason::Structure => "structure",
+            TreasuryReason::War => "war",
+            TreasuryReason::Admin => "admin",
+            TreasuryReason::Land => "land",
+        }
+    }
+}
+
+/// Yield paid in one world tick.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TickIncome {
+    pub tick_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub resources: Resources,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Treasury {
+    pub faction_id: Uuid,
+    #[serde(flatten)]
+    pub balance: Resources,
+    /// Most recent ticks first.
+    pub income: Vec<TickIncome>,
+}
+
+async fn log(
+    conn: &mut PgConnection,
+    faction: Uuid,
+    delta: &Resources,
+    reason: TreasuryReason,
+    ref_id: Option<&str>,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    for r in Resource::ALL {
+        let amount = delta.get(r);
+        if amount == 0 {
+            continue;
+        }
+        sqlx::query!(
+            "INSERT INTO faction_treasury_ledger
+                    (faction_id, resource, amount, reason, ref_id, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            faction,
+            r.as_str(),
+            amount,
+            reason.as_str(),
+            ref_id,
+            at
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Add `amount` to the faction’s treasury, creating it on first use.
+pub async fn deposit(
+    conn: &mut PgConnection,
+    faction: Uuid,
+    amount: &Resources,
+    reason: TreasuryReason,
+    ref_id: Option<&str>,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    if Resource::ALL.iter().any(|&r| amount.get(r) < 0) {
+        bail!("negative deposit");
+    }
+    sqlx::query!(
+        "INSERT INTO faction_treasuries (faction_id, energy, biomass, gene_seeds, updated_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (faction_id) DO UPDATE
+            SET energy     = faction_treasuries.energy + EXCLUDED.energy,
+                biomass    = faction_treasuries.biomass + EXCLUDED.biomass,
+                gene_seeds = faction_treasuries.gene_seeds + EXCLUDED.gene_seeds,
+                updated_at = EXCLUDED.updated_at",
+        faction,
+        amount.energy,
+        amount.biomass,
+        amount.gene_seeds,
+        at
+    )
+    .execute(&mut *conn)
+    .await?;
+    log(conn, faction, amount, reason, ref_id, at).await
+}
+
+/// Take `cost` out of the faction’s treasury; fails without touching it if
+/// any resource would go negative.
+pub async fn spend(
+    conn: &mut PgConnection,
+    faction: Uuid,
+    cost: &Resources,
+    reason: TreasuryReason,
+    ref_id: Option<&str>,
+) -> Result<()> {
+    if cost.is_zero() {
+        return Ok(());
+    }
+    if Resource::ALL.iter().any(|&r| cost.get(r) < 0) {
+        bail!("negative cost");
+    }
+    let rows = sqlx::query!(
+        "UPDATE faction_treasuries
+            SET energy     = energy - $2,
+                biomass    = biomass - $3,
+                gene_seeds = gene_seeds - $4,
+                updated_at = NOW()
+          WHERE faction_id = $1
+            AND energy >= $2 AND biomass >= $3 AND gene_seeds >= $4",
+        faction,
+        cost.energy,
+        cost.biomass,
+        cost.gene_seeds
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if rows == 0 {
+        bail!("insufficient treasury");
+    }
+
+    let mut delta = Resources::default();
+    for r in Resource::ALL {
+        delta.add(r, -cost.get(r));
+    }
+    log(conn, faction, &delta, reason, ref_id, Utc::now()).await
+}
+
+/// What building `structure_type` costs; `None` if it has no cost row,
+/// i.e. is not a buildable type.
+pub async fn structure_cost(
+    conn: &mut PgConnection,
+    structure_type: &str,
+) -> Result<Option<Resources>> {
+    Ok(sqlx::query_as!(
+        Resources,
+        "SELECT energy, biomass, gene_seeds FROM structure_costs WHERE structure_type = $1",
+        structure_type
+    )
+    .fetch_optional(&mut *conn)
+    .await?)
+}
+
+/// Current balance and the yield of the last `ticks` world ticks.
+pub async fn get(db: &PgPool, faction: Uuid, ticks: i64) -> Result<Treasury> {
+    let balance = sqlx::query_as!(
+        Resources,
+        "SELECT energy, biomass, gene_seeds FROM faction_treasuries WHERE faction_id = $1",
+        faction
+    )
+    .fetch_optional(db)
+    .await?
+    .unwrap_or_default();
+
+    let rows = sqlx::query!(
+        r#"SELECT created_at, resource, SUM(amount)::BIGINT AS "amount!"
+             FROM faction_treasury_ledger
+            WHERE faction_id = $1 AND reason = 'yield'
+              AND created_at IN (SELECT DISTINCT created_at
+                                   FROM faction_treasury_ledger
+                                  WHERE faction_id = $1 AND reason = 'yield'
+                                  ORDER BY created_at DESC
+                                  LIMIT $2)
+            GROUP BY created_at, resource"#,
+        faction,
+        ticks
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut by_tick: BTreeMap<DateTime<Utc>, Resources> = BTreeMap::new();
+    for r in rows {
+        let resource = Resource::ALL.into_iter().find(|x| x.as_str() == r.resource);
+        if let Some(resource) = resource {
+            by_tick
+                .entry(r.created_at)
+                .or_default()
+                .add(resource, r.amount);
+        }
+    }
+
+    Ok(Treasury {
+        faction_id: faction,
+        balance,
+        income: by_tick
+            .into_iter()
+            .rev()
+            .map(|(tick_at, resources)| TickIncome { tick_at, resources })
+            .collect(),
+    })
+}
@@ -1 +1,4 @@
+pub mod model;
 pub mod simulation;
+pub mod territory;
+pub mod worldgen;
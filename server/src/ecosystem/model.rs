@@ -0,0 +1,304 @@
+//! Per-parcel world model: biome rules, one tick of evolution, the events
+//! worth announcing and the resources an owned parcel yields.
+//!
+//! Everything here is pure; `simulation` loads parcels, structures and
+//! neighbours from the database, calls [`step`] and [`events`] and writes
+//! the result back.
+
+use serde::{Deserialize, Serialize};
+
+/// Parcel biome (`land_parcels.biome_type`, matched case-insensitively).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Biome {
+    Forest,
+    #[default]
+    Grassland,
+    Desert,
+    Wetland,
+    Tundra,
+}
+
+impl Biome {
+    /// Unknown names fall back to grassland.
+    pub fn parse(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "forest" => Biome::Forest,
+            "desert" => Biome::Desert,
+            "wetland" | "swamp" => Biome::Wetland,
+            "tundra" => Biome::Tundra,
+            _ => Biome::Grassland,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Biome::Forest => "forest",
+            Biome::Grassland => "grassland",
+            Biome::Desert => "desert",
+            Biome::Wetland => "wetland",
+            Biome::Tundra => "tundra",
+        }
+    }
+
+    pub fn rules(self) -> BiomeRules {
+        match self {
+            Biome::Forest => BiomeRules {
+                fertility_target: 0.7,
+                recovery: 0.05,
+                capacity: 100.0,
+                growth: 0.08,
+                cleanse: 0.05,
+                species_per_biomass: 0.6,
+            },
+            Biome::Grassland => BiomeRules {
+                fertility_target: 0.6,
+                recovery: 0.06,
+                capacity: 60.0,
+                growth: 0.12,
+                cleanse: 0.04,
+                species_per_biomass: 0.8,
+            },
+            Biome::Desert => BiomeRules {
+                fertility_target: 0.2,
+                recovery: 0.02,
+                capacity: 15.0,
+                growth: 0.05,
+                cleanse: 0.02,
+                species_per_biomass: 0.5,
+            },
+            Biome::Wetland => BiomeRules {
+                fertility_target: 0.8,
+                recovery: 0.08,
+                capacity: 80.0,
+                growth: 0.1,
+                cleanse: 0.08,
+                species_per_biomass: 1.0,
+            },
+            Biome::Tundra => BiomeRules {
+                fertility_target: 0.3,
+                recovery: 0.02,
+                capacity: 25.0,
+                growth: 0.04,
+                cleanse: 0.03,
+                species_per_biomass: 0.4,
+            },
+        }
+    }
+
+    /// Resource an owned parcel of this biome yields, and how much per
+    /// tick before structures and pollution.
+    pub fn base_yield(self) -> (Resource, f64) {
+        match self {
+            Biome::Forest => (Resource::Biomass, 5.0),
+            Biome::Grassland => (Resource::Biomass, 2.0),
+            Biome::Desert => (Resource::Energy, 5.0),
+            Biome::Wetland => (Resource::GeneSeeds, 3.0),
+            Biome::Tundra => (Resource::Energy, 2.0),
+        }
+    }
+}
+
+/// Faction treasury resource (`faction_treasury_ledger.resource`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    Energy,
+    Biomass,
+    GeneSeeds,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 3] = [Resource::Energy, Resource::Biomass, Resource::GeneSeeds];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Resource::Energy => "energy",
+            Resource::Biomass => "biomass",
+            Resource::GeneSeeds => "gene_seeds",
+        }
+    }
+}
+
+/// One tick of yield from a parcel: the biome’s base, times the product of
+/// the structures’ multipliers, cut by pollution; rounded to whole units.
+pub fn parcel_yield(biome: Biome, s: &ParcelState, yield_mult: f64) -> (Resource, i64) {
+    let (resource, base) = biome.base_yield();
+    let amount = base * yield_mult.max(0.0) * (1.0 - s.pollution.clamp(0.0, 1.0));
+    (resource, amount.round() as i64)
+}
+
+/// Biome-specific rates; all per tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiomeRules {
+    /// Fertility drifts toward this level.
+    pub fertility_target: f64,
+    /// Share of the gap to `fertility_target` closed each tick.
+    pub recovery: f64,
+    /// Most biomass the parcel can hold.
+    pub capacity: f64,
+    /// Logistic biomass growth rate at full fertility.
+    pub growth: f64,
+    /// Share of pollution broken down each tick.
+    pub cleanse: f64,
+    /// Species the parcel supports per unit of biomass.
+    pub species_per_biomass: f64,
+}
+
+/// Evolving state of one parcel.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ParcelState {
+    /// 0..=1
+    pub fertility: f64,
+    pub biomass: f64,
+    /// 0..=1
+    pub pollution: f64,
+    pub population: f64,
+}
+
+/// Summed effect of the structures on a tile (`structure_eco_effects`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileMods {
+    /// Pollution added per tick (negative cleans).
+    pub pollution: f64,
+    /// Fertility added per tick.
+    pub fertility: f64,
+    /// Multiplier on biomass growth.
+    pub growth_mult: f64,
+}
+
+impl Default for TileMods {
+    fn default() -> Self {
+        TileMods {
+            pollution: 0.0,
+            fertility: 0.0,
+            growth_mult: 1.0,
+        }
+    }
+}
+
+impl TileMods {
+    /// Stack another structure’s effect onto this tile.
+    pub fn add(&mut self, other: &TileMods) {
+        self.pollution += other.pollution;
+        self.fertility += other.fertility;
+        self.growth_mult *= other.growth_mult;
+    }
+}
+
+/// Share of the gap to the neighbour average that moves in one tick.
+pub const POLLUTION_SPREAD: f64 = 0.1;
+pub const MIGRATION: f64 = 0.05;
+
+/// Grazing: biomass eaten per individual per tick.
+const GRAZING: f64 = 0.02;
+/// Fertility lost per tick at full pollution.
+const POLLUTION_SOIL_DAMAGE: f64 = 0.03;
+/// Share of the population lost per tick at full pollution.
+const POLLUTION_MORTALITY: f64 = 0.2;
+/// Population growth rate below carrying capacity.
+const BREEDING: f64 = 0.1;
+
+/// One tick. `neighbours` is the average state of the adjacent parcels
+/// (`None` for an isolated parcel).
+pub fn step(
+    s: &ParcelState,
+    rules: &BiomeRules,
+    mods: &TileMods,
+    neighbours: Option<&ParcelState>,
+) -> ParcelState {
+    let fertility = (s.fertility + rules.recovery * (rules.fertility_target - s.fertility)
+        - POLLUTION_SOIL_DAMAGE * s.pollution
+        + mods.fertility)
+        .clamp(0.0, 1.0);
+
+    let rate = rules.growth * fertility * (1.0 - s.pollution) * mods.growth_mult;
+    let grown = rate * s.biomass.max(1.0) * (1.0 - s.biomass / rules.capacity);
+    let biomass = (s.biomass + grown - GRAZING * s.population).clamp(0.0, rules.capacity);
+
+    let supported = rules.species_per_biomass * biomass;
+    let breeding = if supported > 0.0 {
+        BREEDING * s.population * (1.0 - s.population / supported)
+    } else {
+        -s.population
+    };
+    let mut population = s.population + breeding - POLLUTION_MORTALITY * s.pollution * s.population;
+
+    let mut pollution = s.pollution * (1.0 - rules.cleanse) + mods.pollution;
+    if let Some(n) = neighbours {
+        pollution += POLLUTION_SPREAD * (n.pollution - s.pollution);
+        population += MIGRATION * (n.population - s.population);
+    }
+
+    ParcelState {
+        fertility,
+        biomass,
+        pollution: pollution.clamp(0.0, 1.0),
+        population: population.max(0.0),
+    }
+}
+
+/// Mean state of a set of parcels.
+pub fn average<'a>(states: impl IntoIterator<Item = &'a ParcelState>) -> Option<ParcelState> {
+    let mut n = 0.0;
+    let mut sum = ParcelState {
+        fertility: 0.0,
+        biomass: 0.0,
+        pollution: 0.0,
+        population: 0.0,
+    };
+    for s in states {
+        n += 1.0;
+        sum.fertility += s.fertility;
+        sum.biomass += s.biomass;
+        sum.pollution += s.pollution;
+        sum.population += s.population;
+    }
+    (n > 0.0).then(|| ParcelState {
+        fertility: sum.fertility / n,
+        biomass: sum.biomass / n,
+        pollution: sum.pollution / n,
+        population: sum.population / n,
+    })
+}
+
+/// What happened to a parcel this tick.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EcoEventKind {
+    /// Biomass reached most of the biome’s capacity.
+    Bloom,
+    /// Pollution crossed the danger line or the population crashed.
+    Blight,
+    /// Fertility fell below the drought line.
+    Drought,
+}
+
+/// Share of capacity that counts as a bloom.
+pub const BLOOM_AT: f64 = 0.9;
+/// Pollution level that counts as a blight.
+pub const BLIGHT_POLLUTION: f64 = 0.6;
+/// Share of the population lost in one tick that counts as a blight.
+pub const BLIGHT_CRASH: f64 = 0.25;
+/// Fertility level that counts as a drought.
+pub const DROUGHT_AT: f64 = 0.15;
+
+/// Events for the transition `before` → `after`. Only threshold crossings
+/// count, so a parcel stuck in drought is announced once.
+pub fn events(before: &ParcelState, after: &ParcelState, rules: &BiomeRules) -> Vec<EcoEventKind> {
+    let mut out = Vec::new();
+    let bloom = rules.capacity * BLOOM_AT;
+    if before.biomass < bloom && after.biomass >= bloom {
+        out.push(EcoEventKind::Bloom);
+    }
+    let polluted = before.pollution < BLIGHT_POLLUTION && after.pollution >= BLIGHT_POLLUTION;
+    let crashed =
+        before.population > 1.0 && after.population < before.population * (1.0 - BLIGHT_CRASH);
+    if polluted || crashed {
+        out.push(EcoEventKind::Blight);
+    }
+    if before.fertility >= DROUGHT_AT && after.fertility < DROUGHT_AT {
+        out.push(EcoEventKind::Drought);
+    }
+    out
+}
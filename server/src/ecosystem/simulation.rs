@@ -1,30 +1,257 @@
-//! Minute-tick heartbeat (placeholder for real biome logic).
+//! Minute-tick world simulation.
+//!
+//! Every tick evolves each land parcel under its biome’s rules (see
+//! `ecosystem::model`), modified by the structures standing on it and by
+//! its eight neighbours, and publishes blooms, blights and droughts on
+//! `world:events`. Owned parcels then pay their yield into the owning
+//! faction’s treasury (see `db::treasury_repo`).
+//!
+//! The tick reads the map without locking it and writes it back in
+//! id-ordered batches, one short transaction each, so claims and contests
+//! only ever wait on a single batch.
+
+use std::collections::{BTreeMap, HashMap};
 
 use chrono::{DateTime, Utc};
 use redis::{AsyncCommands, Client as RedisClient};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use tokio::time::{sleep, Duration};
+use uuid::Uuid;
+
+use crate::{
+    db::treasury_repo::{self, Resources, TreasuryReason},
+    ecosystem::{
+        model::{self, Biome, EcoEventKind, ParcelState, TileMods},
+        worldgen,
+    },
+};
 
-#[derive(Serialize)]
-struct WorldEvent {
-    ts: DateTime<Utc>,
-    kind: &'static str,
+/// One frame on `world:events`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorldEvent {
+    pub ts: DateTime<Utc>,
+    pub kind: EcoEventKind,
+    pub parcel_id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub biome: Biome,
+    /// Parcel state after the tick.
+    pub state: ParcelState,
 }
 
-async fn tick(db: &PgPool, redis: &RedisClient) {
-    // Cheap liveness query (replace with real biome math later)
-    let _ = sqlx::query("SELECT 1").execute(db).await;
+struct Parcel {
+    id: i32,
+    x: i32,
+    y: i32,
+    biome: Biome,
+    rich: bool,
+    state: ParcelState,
+}
+
+/// Parcels written (and paid out) per transaction.
+const TICK_BATCH: usize = 500;
+
+/// Run one tick over every parcel, pay parcel yields to their factions and
+/// return the events of the batches that were written.
+pub async fn tick_world(db: &PgPool) -> anyhow::Result<Vec<WorldEvent>> {
+    let parcels: Vec<Parcel> = sqlx::query!(
+        "SELECT id, biome_type, rich, x, y,
+                fertility, biomass, pollution, population
+           FROM land_parcels
+          ORDER BY id"
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|r| Parcel {
+        id: r.id,
+        x: r.x,
+        y: r.y,
+        biome: Biome::parse(&r.biome_type),
+        rich: r.rich,
+        state: ParcelState {
+            fertility: r.fertility,
+            biomass: r.biomass,
+            pollution: r.pollution,
+            population: r.population,
+        },
+    })
+    .collect();
+
+    let effects: HashMap<String, TileMods> = sqlx::query!(
+        "SELECT structure_type, pollution, fertility, growth_mult FROM structure_eco_effects"
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|r| {
+        let mods = TileMods {
+            pollution: r.pollution,
+            fertility: r.fertility,
+            growth_mult: r.growth_mult,
+        };
+        (r.structure_type, mods)
+    })
+    .collect();
+
+    let yields: HashMap<String, f64> =
+        sqlx::query!("SELECT structure_type, yield_mult FROM structure_yields")
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .map(|r| (r.structure_type, r.yield_mult))
+            .collect();
+
+    let mut mods: HashMap<(i32, i32), TileMods> = HashMap::new();
+    let mut yield_mults: HashMap<(i32, i32), f64> = HashMap::new();
+    for s in sqlx::query!("SELECT type AS kind, x, y FROM structures")
+        .fetch_all(db)
+        .await?
+    {
+        if let Some(effect) = effects.get(&s.kind) {
+            mods.entry((s.x, s.y)).or_default().add(effect);
+        }
+        if let Some(mult) = yields.get(&s.kind) {
+            *yield_mults.entry((s.x, s.y)).or_insert(1.0) *= mult;
+        }
+    }
+
+    // Neighbours see the state from before this tick.
+    let by_tile: HashMap<(i32, i32), ParcelState> =
+        parcels.iter().map(|p| ((p.x, p.y), p.state)).collect();
+
+    let next: Vec<ParcelState> = parcels
+        .iter()
+        .map(|p| {
+            let around = model::average(
+                (-1..=1)
+                    .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+                    .filter(|&d| d != (0, 0))
+                    .filter_map(|(dx, dy)| by_tile.get(&(p.x + dx, p.y + dy))),
+            );
+            let tile = mods.get(&(p.x, p.y)).copied().unwrap_or_default();
+            model::step(&p.state, &p.biome.rules(), &tile, around.as_ref())
+        })
+        .collect();
 
-    let evt = WorldEvent {
-        ts: Utc::now(),
-        kind: "heartbeat",
+    let now = Utc::now();
+    let ref_id = format!("tick:{}", now.timestamp());
+    let mut events = Vec::new();
+    for (batch, next) in parcels.chunks(TICK_BATCH).zip(next.chunks(TICK_BATCH)) {
+        if let Err(e) = write_batch(db, batch, next, &yield_mults, &ref_id, now).await {
+            log::error!("world tick batch from parcel {} failed: {e:?}", batch[0].id);
+            continue;
+        }
+        for (p, next) in batch.iter().zip(next) {
+            for kind in model::events(&p.state, next, &p.biome.rules()) {
+                events.push(WorldEvent {
+                    ts: now,
+                    kind,
+                    parcel_id: p.id,
+                    x: p.x,
+                    y: p.y,
+                    biome: p.biome,
+                    state: *next,
+                });
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Store one batch’s next states and pay its yields in one transaction.
+/// Rows are locked in id order, and the owner paid is the one the update
+/// saw, so a parcel claimed since the tick read the map pays its new
+/// faction.
+async fn write_batch(
+    db: &PgPool,
+    parcels: &[Parcel],
+    next: &[ParcelState],
+    yield_mults: &HashMap<(i32, i32), f64>,
+    ref_id: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let ids: Vec<i32> = parcels.iter().map(|p| p.id).collect();
+    let column = |f: fn(&ParcelState) -> f64| next.iter().map(f).collect::<Vec<_>>();
+    let (fertility, biomass, pollution, population) = (
+        column(|s| s.fertility),
+        column(|s| s.biomass),
+        column(|s| s.pollution),
+        column(|s| s.population),
+    );
+
+    let mut tx = db.begin().await?;
+    let owners: HashMap<i32, Uuid> = sqlx::query!(
+        "WITH locked AS (
+             SELECT id FROM land_parcels WHERE id = ANY($1) ORDER BY id FOR UPDATE
+         )
+         UPDATE land_parcels AS lp
+            SET fertility = u.fertility, biomass = u.biomass,
+                pollution = u.pollution, population = u.population
+           FROM UNNEST($1::INT[], $2::FLOAT8[], $3::FLOAT8[], $4::FLOAT8[], $5::FLOAT8[])
+                AS u(id, fertility, biomass, pollution, population)
+          WHERE lp.id = u.id AND lp.id IN (SELECT id FROM locked)
+      RETURNING lp.id, lp.owner_faction_id",
+        &ids,
+        &fertility,
+        &biomass,
+        &pollution,
+        &population
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .filter_map(|r| Some((r.id, r.owner_faction_id?)))
+    .collect();
+
+    // Ordered by faction, so concurrent treasury writers lock in one order.
+    let mut income: BTreeMap<Uuid, Resources> = BTreeMap::new();
+    for (p, next) in parcels.iter().zip(next) {
+        let Some(&faction) = owners.get(&p.id) else {
+            continue;
+        };
+        let mut mult = yield_mults.get(&(p.x, p.y)).copied().unwrap_or(1.0);
+        if p.rich {
+            mult *= worldgen::RICH_YIELD;
+        }
+        let (resource, amount) = model::parcel_yield(p.biome, next, mult);
+        income.entry(faction).or_default().add(resource, amount);
+    }
+    for (faction, amount) in &income {
+        treasury_repo::deposit(
+            &mut tx,
+            *faction,
+            amount,
+            TreasuryReason::Yield,
+            Some(ref_id),
+            now,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn tick(db: &PgPool, redis: &RedisClient) {
+    let events = match tick_world(db).await {
+        Ok(events) => events,
+        Err(e) => {
+            log::error!("world tick failed: {e:?}");
+            return;
+        }
     };
+    if events.is_empty() {
+        return;
+    }
     if let Ok(mut conn) = redis.get_multiplexed_async_connection().await {
-        let _: () = conn
-            .publish("world:events", serde_json::to_string(&evt).unwrap())
-            .await
-            .unwrap_or(());
+        for evt in &events {
+            let _: () = conn
+                .publish("world:events", serde_json::to_string(evt).unwrap())
+                .await
+                .unwrap_or(());
+        }
     }
 }
 
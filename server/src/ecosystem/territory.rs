@@ -0,0 +1,104 @@
+//! Territory rules: what a claim costs and how a contest is decided.
+//!
+//! A faction’s first parcel is free. Every later claim must border its
+//! territory (checked in `db::land_repo::claim`) and costs more the larger
+//! the territory already is and the more valuable the tile.
+//!
+//! Contests not settled by a champions’ duel are settled by strength: the
+//! attacker’s committed energy against the tile’s defensive structures,
+//! each side helped by the parcels it holds around the tile. Ties hold.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{db::treasury_repo::Resources, ecosystem::model::Biome};
+
+/// Credits per unit of tile value per parcel already held.
+pub const CREDITS_PER_VALUE: i64 = 25;
+/// Energy and biomass (each) per unit of tile value per parcel already held.
+pub const RESOURCES_PER_VALUE: i64 = 5;
+
+/// Orthogonal neighbours; a claim must share an edge with owned land.
+pub const BORDERS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Who pays for a claim.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PayWith {
+    /// The faction treasury, in energy and biomass.
+    #[default]
+    Treasury,
+    /// The claiming player, in credits.
+    Credits,
+}
+
+/// Price of one claim in either currency; only the chosen one is charged.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClaimCost {
+    pub credits: i64,
+    pub resources: Resources,
+}
+
+impl ClaimCost {
+    /// The part charged when paying with `pay`.
+    pub fn charged(self, pay: PayWith) -> ClaimCost {
+        match pay {
+            PayWith::Treasury => ClaimCost { credits: 0, ..self },
+            PayWith::Credits => ClaimCost {
+                resources: Resources::default(),
+                ..self
+            },
+        }
+    }
+}
+
+/// Relative worth of a tile; resource-rich tiles count double.
+pub fn tile_value(biome: Biome, rich: bool) -> i64 {
+    let base = match biome {
+        Biome::Wetland => 4,
+        Biome::Forest | Biome::Desert => 3,
+        Biome::Grassland => 2,
+        Biome::Tundra => 1,
+    };
+    if rich {
+        base * 2
+    } else {
+        base
+    }
+}
+
+/// Cost of claiming a tile for a faction that already holds `territory`
+/// parcels.
+pub fn claim_cost(biome: Biome, rich: bool, territory: i64) -> ClaimCost {
+    let units = tile_value(biome, rich) * territory.max(0);
+    ClaimCost {
+        credits: CREDITS_PER_VALUE * units,
+        resources: Resources {
+            energy: RESOURCES_PER_VALUE * units,
+            biomass: RESOURCES_PER_VALUE * units,
+            gene_seeds: 0,
+        },
+    }
+}
+
+/// Least energy a faction must commit to declare an attack.
+pub const MIN_ATTACK_ENERGY: i64 = 10;
+/// Energy per point of attack strength.
+pub const ENERGY_PER_STRENGTH: f64 = 2.0;
+/// Defense of a parcel with no structures.
+pub const BASE_DEFENSE: f64 = 10.0;
+/// Strength a side gains per parcel it holds on the tile’s borders.
+pub const BORDER_SUPPORT: f64 = 2.0;
+
+pub fn attack_strength(energy: i64, bordering: i64) -> f64 {
+    energy.max(0) as f64 / ENERGY_PER_STRENGTH + BORDER_SUPPORT * bordering.max(0) as f64
+}
+
+/// `structures` is the summed `structure_defense` of the tile.
+pub fn defense_strength(structures: f64, bordering: i64) -> f64 {
+    BASE_DEFENSE + structures.max(0.0) + BORDER_SUPPORT * bordering.max(0) as f64
+}
+
+/// The attacker must beat the defense outright.
+pub fn attacker_wins(attack: f64, defense: f64) -> bool {
+    attack > defense
+}
@@ -0,0 +1,152 @@
+//! Seeded world map: bounds, biome of every tile and rare resource-rich
+//! tiles.
+//!
+//! The map is a pure function of `(seed, x, y)`, so nothing is stored until
+//! a tile is claimed; `land_parcels` rows are materialised from [`Tile`] on
+//! first claim. Biomes come from two smooth value-noise fields, heat and
+//! moisture, so neighbouring tiles mostly share a biome.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::settings,
+    ecosystem::model::{Biome, ParcelState},
+    game::rng::CombatRng,
+};
+
+/// Tiles between noise lattice points of the coarse octave.
+const NOISE_SCALE: i32 = 16;
+/// Resource-rich tiles per thousand.
+pub const RICH_PER_MILLE: u64 = 40;
+/// Yield multiplier of a resource-rich tile.
+pub const RICH_YIELD: f64 = 2.0;
+
+const HEAT: u64 = 0x68EA_7000;
+const MOISTURE: u64 = 0x3015_7E00;
+const RICHNESS: u64 = 0x0B1C_4E55;
+
+/// One generated map tile.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub x: i32,
+    pub y: i32,
+    pub biome: Biome,
+    /// Yields [`RICH_YIELD`] times the biome’s base.
+    pub rich: bool,
+}
+
+impl Tile {
+    /// Ecology of a freshly materialised parcel: settled at the biome’s
+    /// fertility, half its biomass capacity, unpolluted.
+    pub fn initial_state(&self) -> ParcelState {
+        let rules = self.biome.rules();
+        ParcelState {
+            fertility: rules.fertility_target,
+            biomass: rules.capacity / 2.0,
+            pollution: 0.0,
+            population: rules.species_per_biomass * rules.capacity / 4.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorldGen {
+    pub seed: u64,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl WorldGen {
+    pub fn new(seed: u64, width: i32, height: i32) -> Self {
+        WorldGen {
+            seed,
+            width,
+            height,
+        }
+    }
+
+    /// The world configured by `WORLD_SEED`, `WORLD_WIDTH` and `WORLD_HEIGHT`.
+    pub fn from_settings() -> Self {
+        let s = settings();
+        Self::new(s.world_seed, s.world_width, s.world_height)
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (0..self.width).contains(&x) && (0..self.height).contains(&y)
+    }
+
+    /// The tile at `(x, y)`, or `None` off the map.
+    pub fn tile(&self, x: i32, y: i32) -> Option<Tile> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        let heat = self.noise(HEAT, x, y);
+        let moisture = self.noise(MOISTURE, x, y);
+        Some(Tile {
+            x,
+            y,
+            biome: biome_for(heat, moisture),
+            rich: self.hash(RICHNESS, x, y) % 1000 < RICH_PER_MILLE,
+        })
+    }
+
+    fn hash(&self, salt: u64, x: i32, y: i32) -> u64 {
+        let key = self.seed
+            ^ salt
+            ^ (x as u32 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (y as u32 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        CombatRng::new(key).next_u64()
+    }
+
+    fn lattice(&self, salt: u64, x: i32, y: i32) -> f64 {
+        self.hash(salt, x, y) as f64 / u64::MAX as f64
+    }
+
+    /// Smoothly interpolated value noise at one scale, in `0..=1`.
+    fn octave(&self, salt: u64, x: i32, y: i32, scale: i32) -> f64 {
+        let (cx, cy) = (x.div_euclid(scale), y.div_euclid(scale));
+        let fx = smooth(x.rem_euclid(scale) as f64 / scale as f64);
+        let fy = smooth(y.rem_euclid(scale) as f64 / scale as f64);
+        let top = lerp(
+            self.lattice(salt, cx, cy),
+            self.lattice(salt, cx + 1, cy),
+            fx,
+        );
+        let bottom = lerp(
+            self.lattice(salt, cx, cy + 1),
+            self.lattice(salt, cx + 1, cy + 1),
+            fx,
+        );
+        lerp(top, bottom, fy)
+    }
+
+    /// Two octaves: coarse regions with finer detail on top.
+    fn noise(&self, salt: u64, x: i32, y: i32) -> f64 {
+        let coarse = self.octave(salt, x, y, NOISE_SCALE);
+        let fine = self.octave(salt.rotate_left(17), x, y, NOISE_SCALE / 4);
+        (coarse * 2.0 + fine) / 3.0
+    }
+}
+
+/// Biome for a heat/moisture pair, both in `0..=1`.
+pub fn biome_for(heat: f64, moisture: f64) -> Biome {
+    if moisture > 0.65 {
+        Biome::Wetland
+    } else if heat < 0.3 {
+        Biome::Tundra
+    } else if heat > 0.55 && moisture < 0.35 {
+        Biome::Desert
+    } else if moisture > 0.45 {
+        Biome::Forest
+    } else {
+        Biome::Grassland
+    }
+}
+
+fn smooth(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
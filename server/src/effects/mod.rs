@@ -0,0 +1,415 @@
+//! Item effects.
+//!
+//! An item’s `items.effects` column is a JSON array of [`Effect`]s, and this
+//! enum is the registry of everything an item can do. Out-of-duel effects
+//! are applied by [`use_item`] in the same transaction as the inventory
+//! debit. Duel effects ([`Effect::in_duel`]) only work through
+//! `TurnAction::UseItem`: the items a player owns are moved into the
+//! game’s escrow and loaded into the battle by [`take_loadout`], resolved
+//! by `game::logic`, and what is left is handed back by
+//! [`release_duel_items`] when the game ends.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{
+    db::{
+        ledger_repo::{self, Account, Reason, SystemAccount},
+        trade_repo,
+    },
+    game::types::{ItemStack, ResourcePool, UnitType},
+};
+
+/// One thing an item does when used.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Effect {
+    /// Pay credits from the rewards account.
+    GrantCredits { amount: i64 },
+    /// Extra resources at the start of the player’s next duel.
+    DuelBoost {
+        #[serde(default)]
+        energy: u32,
+        #[serde(default)]
+        biomass: u32,
+        #[serde(default)]
+        gene_seeds: u32,
+    },
+    /// Restore hit-points (`stats.hp`, capped at `stats.max_hp`) of a
+    /// structure the player owns; the structure is the use target.
+    RepairStructure { hp: i64 },
+    /// Permanently allow deploying a unit type that `requires_unlock`.
+    UnlockUnit { unit_type: UnitType },
+    /// Duel only: add resources to the user’s pool immediately.
+    Resources {
+        #[serde(default)]
+        energy: u32,
+        #[serde(default)]
+        biomass: u32,
+        #[serde(default)]
+        gene_seeds: u32,
+    },
+    /// Duel only: restore hp to one of the user’s units, up to its max.
+    HealUnit { hp: u32 },
+}
+
+impl Effect {
+    /// True for effects that only make sense inside a running duel.
+    pub fn in_duel(&self) -> bool {
+        matches!(self, Effect::Resources { .. } | Effect::HealUnit { .. })
+    }
+}
+
+/// Parse an `items.effects` value. Duel and out-of-duel effects may not be
+/// mixed on one item.
+pub fn parse(value: serde_json::Value) -> Result<Vec<Effect>> {
+    let effects: Vec<Effect> = serde_json::from_value(value)?;
+    if effects.iter().any(Effect::in_duel) && !effects.iter().all(Effect::in_duel) {
+        bail!("duel and out-of-duel effects cannot be mixed");
+    }
+    Ok(effects)
+}
+
+/// What one effect did, as reported to the player.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Applied {
+    CreditsGranted {
+        amount: i64,
+        balance: i64,
+    },
+    /// `pending` is everything now queued for the next duel.
+    DuelBoostQueued {
+        pending: ResourcePool,
+    },
+    StructureRepaired {
+        structure_id: i32,
+        hp: i64,
+        max_hp: Option<i64>,
+    },
+    UnitUnlocked {
+        unit_type: UnitType,
+        /// False if the player already had it.
+        newly: bool,
+    },
+}
+
+/// Result of [`use_item`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UseOutcome {
+    pub item_id: i32,
+    pub used: i32,
+    pub remaining: i32,
+    pub applied: Vec<Applied>,
+}
+
+/// Consume `qty` of an item and apply its effects, all or nothing.
+/// `target` is the structure id for `RepairStructure`.
+pub async fn use_item(
+    db: &PgPool,
+    player: Uuid,
+    item_id: i32,
+    qty: i32,
+    target: Option<i32>,
+) -> Result<UseOutcome> {
+    if qty <= 0 {
+        bail!("quantity must be > 0");
+    }
+    let mut tx = db.begin().await?;
+
+    let raw = sqlx::query_scalar!("SELECT effects FROM items WHERE id = $1", item_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .context("unknown item")?;
+    let effects = parse(raw).with_context(|| format!("item {item_id} has invalid effects"))?;
+    if effects.is_empty() {
+        bail!("item has no effect");
+    }
+    if effects.iter().any(Effect::in_duel) {
+        bail!("item can only be used in a duel");
+    }
+
+    trade_repo::take_item(&mut tx, player, item_id, qty).await?;
+
+    let mut applied = Vec::with_capacity(effects.len());
+    for effect in &effects {
+        applied.push(apply(&mut tx, player, item_id, effect, qty, target).await?);
+    }
+
+    let remaining = sqlx::query_scalar!(
+        "SELECT quantity FROM player_items WHERE player_id = $1 AND item_id = $2",
+        player,
+        item_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .unwrap_or(0);
+
+    tx.commit().await?;
+    Ok(UseOutcome {
+        item_id,
+        used: qty,
+        remaining,
+        applied,
+    })
+}
+
+/// Apply one out-of-duel effect `times` times over.
+async fn apply(
+    conn: &mut PgConnection,
+    player: Uuid,
+    item_id: i32,
+    effect: &Effect,
+    times: i32,
+    target: Option<i32>,
+) -> Result<Applied> {
+    let times = times as i64;
+    match effect {
+        Effect::GrantCredits { amount } => {
+            let amount = amount.saturating_mul(times);
+            ledger_repo::transfer(
+                conn,
+                Account::System(SystemAccount::Rewards),
+                Account::Player(player),
+                amount,
+                Reason::Reward,
+                Some(&format!("item:{item_id}")),
+            )
+            .await?;
+            let balance = sqlx::query_scalar!("SELECT credits FROM players WHERE id = $1", player)
+                .fetch_one(&mut *conn)
+                .await?;
+            Ok(Applied::CreditsGranted { amount, balance })
+        }
+        Effect::DuelBoost {
+            energy,
+            biomass,
+            gene_seeds,
+        } => {
+            let scale = |v: &u32| (*v as i64 * times).min(i32::MAX as i64) as i32;
+            let r = sqlx::query!(
+                "INSERT INTO duel_boosts (player_id, energy, biomass, gene_seeds)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (player_id) DO UPDATE
+                    SET energy     = duel_boosts.energy     + EXCLUDED.energy,
+                        biomass    = duel_boosts.biomass    + EXCLUDED.biomass,
+                        gene_seeds = duel_boosts.gene_seeds + EXCLUDED.gene_seeds
+                 RETURNING energy, biomass, gene_seeds",
+                player,
+                scale(energy),
+                scale(biomass),
+                scale(gene_seeds)
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+            Ok(Applied::DuelBoostQueued {
+                pending: ResourcePool {
+                    energy: r.energy as u32,
+                    biomass: r.biomass as u32,
+                    gene_seeds: r.gene_seeds as u32,
+                },
+            })
+        }
+        Effect::RepairStructure { hp } => {
+            let structure_id = target.context("target structure required")?;
+            let stats = sqlx::query_scalar!(
+                "SELECT stats FROM structures
+                  WHERE id = $1 AND owner_player_id = $2
+                  FOR UPDATE",
+                structure_id,
+                player
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+            .context("not your structure")?;
+            let current = stats["hp"]
+                .as_i64()
+                .context("structure has no hit points")?;
+            let max_hp = stats["max_hp"].as_i64();
+            if max_hp.is_some_and(|max| current >= max) {
+                bail!("structure already at full hp");
+            }
+            let healed = current.saturating_add(hp.saturating_mul(times));
+            let healed = max_hp.map_or(healed, |max| healed.min(max));
+            sqlx::query!(
+                "UPDATE structures SET stats = jsonb_set(stats, '{hp}', to_jsonb($2::BIGINT))
+                  WHERE id = $1",
+                structure_id,
+                healed
+            )
+            .execute(&mut *conn)
+            .await?;
+            Ok(Applied::StructureRepaired {
+                structure_id,
+                hp: healed,
+                max_hp,
+            })
+        }
+        Effect::UnlockUnit { unit_type } => {
+            let known = sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM unit_defs WHERE id = $1) AS "known!""#,
+                unit_type.id()
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+            if !known {
+                bail!("unknown unit type {}", unit_type.id());
+            }
+            let newly = sqlx::query!(
+                "INSERT INTO player_unit_unlocks (player_id, unit_type)
+                 VALUES ($1, $2)
+                 ON CONFLICT DO NOTHING",
+                player,
+                unit_type.id()
+            )
+            .execute(&mut *conn)
+            .await?
+            .rows_affected()
+                == 1;
+            Ok(Applied::UnitUnlocked {
+                unit_type: unit_type.clone(),
+                newly,
+            })
+        }
+        Effect::Resources { .. } | Effect::HealUnit { .. } => {
+            bail!("item can only be used in a duel")
+        }
+    }
+}
+
+/// What a player brings into a duel.
+#[derive(Debug, Clone, Default)]
+pub struct Loadout {
+    /// Queued [`Effect::DuelBoost`] resources (consumed by [`take_loadout`]).
+    pub boost: ResourcePool,
+    pub unlocked: Vec<UnitType>,
+    /// Owned items with duel effects, usable via `TurnAction::UseItem`;
+    /// held in escrow for the game.
+    pub items: Vec<ItemStack>,
+}
+
+/// Load a player’s duel loadout for `game`, consuming any queued boost and
+/// moving their duel items into the game’s escrow, so they cannot also be
+/// sold or traded while the game runs.
+pub async fn take_loadout(db: &PgPool, game: Uuid, player: Uuid) -> Result<Loadout> {
+    let mut tx = db.begin().await?;
+    let boost = sqlx::query!(
+        "DELETE FROM duel_boosts WHERE player_id = $1
+         RETURNING energy, biomass, gene_seeds",
+        player
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .map(|r| ResourcePool {
+        energy: r.energy as u32,
+        biomass: r.biomass as u32,
+        gene_seeds: r.gene_seeds as u32,
+    })
+    .unwrap_or_default();
+
+    let unlocked = sqlx::query_scalar!(
+        "SELECT unit_type FROM player_unit_unlocks WHERE player_id = $1 ORDER BY unit_type",
+        player
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(UnitType::new)
+    .collect();
+
+    let rows = sqlx::query!(
+        "SELECT pi.item_id, pi.quantity, i.effects
+           FROM player_items pi
+           JOIN items i ON i.id = pi.item_id
+          WHERE pi.player_id = $1 AND jsonb_array_length(i.effects) > 0
+          ORDER BY pi.item_id",
+        player
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let items: Vec<ItemStack> = rows
+        .into_iter()
+        .filter_map(|r| {
+            let effects = parse(r.effects).ok()?;
+            if !effects.iter().all(Effect::in_duel) {
+                return None;
+            }
+            Some(ItemStack {
+                item_id: r.item_id,
+                qty: r.quantity as u32,
+                effects,
+            })
+        })
+        .collect();
+
+    for it in &items {
+        trade_repo::take_item(&mut tx, player, it.item_id, it.qty as i32).await?;
+        sqlx::query!(
+            "INSERT INTO duel_item_escrow (game_id, player_id, item_id, quantity)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (game_id, player_id, item_id)
+             DO UPDATE SET quantity = duel_item_escrow.quantity + EXCLUDED.quantity",
+            game,
+            player,
+            it.item_id,
+            it.qty as i32
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(Loadout {
+        boost,
+        unlocked,
+        items,
+    })
+}
+
+/// Spend one escrowed duel item a player used during a turn.
+pub async fn consume_duel_item(db: &PgPool, game: Uuid, player: Uuid, item_id: i32) -> Result<()> {
+    let rows = sqlx::query!(
+        "UPDATE duel_item_escrow SET quantity = quantity - 1
+          WHERE game_id = $1 AND player_id = $2 AND item_id = $3 AND quantity > 0",
+        game,
+        player,
+        item_id
+    )
+    .execute(db)
+    .await?
+    .rows_affected();
+    if rows == 0 {
+        bail!("item {item_id} not in escrow");
+    }
+    Ok(())
+}
+
+/// Hand the unused escrowed duel items of `game` back to their owners.
+/// Like other escrow refunds this skips the stack check.
+pub async fn release_duel_items(db: &PgPool, game: Uuid) -> Result<()> {
+    let mut tx = db.begin().await?;
+    let rows = sqlx::query!(
+        "DELETE FROM duel_item_escrow WHERE game_id = $1
+         RETURNING player_id, item_id, quantity",
+        game
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    for r in rows.into_iter().filter(|r| r.quantity > 0) {
+        sqlx::query!(
+            "INSERT INTO player_items (player_id, item_id, quantity)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (player_id, item_id)
+             DO UPDATE SET quantity = player_items.quantity + EXCLUDED.quantity",
+            r.player_id,
+            r.item_id,
+            r.quantity
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
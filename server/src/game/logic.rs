@@ -1,177 +1,670 @@
+use crate::cache::{self, UnitCost, UnitDef};
+use crate::effects::Effect;
 use crate::game::types::*;
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use uuid::Uuid;
 
-/// Resource cost of a unit.
-#[derive(Debug, Clone, Copy)]
-struct ResourceCost {
-    energy: u32,
-    biomass: u32,
-    gene_seeds: u32,
-}
-
-/// Combat stats of a unit.
-#[derive(Debug, Clone, Copy)]
-struct UnitStats {
-    atk: u32,
-    hp: u32,
-}
-
 impl UnitType {
-    fn cost(self) -> ResourceCost {
-        match self {
-            UnitType::Light => ResourceCost {
-                energy: 1,
-                biomass: 0,
-                gene_seeds: 0,
-            },
-            UnitType::Ranged => ResourceCost {
-                energy: 2,
-                biomass: 1,
-                gene_seeds: 0,
-            },
-            UnitType::Heavy => ResourceCost {
-                energy: 0,
-                biomass: 3,
-                gene_seeds: 0,
-            },
-            UnitType::Seeder => ResourceCost {
-                energy: 1,
-                biomass: 0,
-                gene_seeds: 1,
-            },
-        }
-    }
-    fn stats(self) -> UnitStats {
-        match self {
-            UnitType::Light => UnitStats { atk: 1, hp: 1 },
-            UnitType::Ranged => UnitStats { atk: 2, hp: 1 },
-            UnitType::Heavy => UnitStats { atk: 3, hp: 3 },
-            UnitType::Seeder => UnitStats { atk: 0, hp: 2 },
-        }
+    /// Catalogue row for this type; `None` if it is not (or no longer) listed.
+    fn def(&self) -> Option<UnitDef> {
+        cache::get_unit_def(self)
     }
 }
 
 impl ResourcePool {
-    fn can_pay(&self, c: ResourceCost) -> bool {
+    fn can_pay(&self, c: &UnitCost) -> bool {
         self.energy >= c.energy && self.biomass >= c.biomass && self.gene_seeds >= c.gene_seeds
     }
-    fn pay(&mut self, c: ResourceCost) {
+    fn pay(&mut self, c: &UnitCost) {
         self.energy -= c.energy;
         self.biomass -= c.biomass;
         self.gene_seeds -= c.gene_seeds;
     }
 }
 
-/// Per-turn outcome sent to clients.
+impl BattleState {
+    fn units(&self, side: Side) -> &Vec<Unit> {
+        match side {
+            Side::P1 => &self.units_p1,
+            Side::P2 => &self.units_p2,
+        }
+    }
+    fn units_mut(&mut self, side: Side) -> &mut Vec<Unit> {
+        match side {
+            Side::P1 => &mut self.units_p1,
+            Side::P2 => &mut self.units_p2,
+        }
+    }
+    fn pool_mut(&mut self, side: Side) -> &mut ResourcePool {
+        match side {
+            Side::P1 => &mut self.pool_p1,
+            Side::P2 => &mut self.pool_p2,
+        }
+    }
+    /// Locate a living unit: (side, index into that side’s vec).
+    fn find(&self, id: Uuid) -> Option<(Side, usize)> {
+        [Side::P1, Side::P2].into_iter().find_map(|s| {
+            self.units(s)
+                .iter()
+                .position(|u| u.id == id)
+                .map(|i| (s, i))
+        })
+    }
+    fn items_mut(&mut self, side: Side) -> &mut Vec<ItemStack> {
+        match side {
+            Side::P1 => &mut self.items_p1,
+            Side::P2 => &mut self.items_p2,
+        }
+    }
+    fn unlocked(&self, side: Side) -> &Vec<UnitType> {
+        match side {
+            Side::P1 => &self.unlocked_p1,
+            Side::P2 => &self.unlocked_p2,
+        }
+    }
+    fn owner(&self, side: Side) -> Uuid {
+        match side {
+            Side::P1 => self.p1,
+            Side::P2 => self.p2,
+        }
+    }
+    fn core_hp_mut(&mut self, side: Side) -> &mut u32 {
+        match side {
+            Side::P1 => &mut self.core_hp_p1,
+            Side::P2 => &mut self.core_hp_p2,
+        }
+    }
+    fn points_mut(&mut self, side: Side) -> &mut u32 {
+        match side {
+            Side::P1 => &mut self.points_p1,
+            Side::P2 => &mut self.points_p2,
+        }
+    }
+    fn occupied(&self, pos: Position) -> bool {
+        self.units_p1
+            .iter()
+            .chain(&self.units_p2)
+            .any(|u| u.pos == pos)
+    }
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::P1 => Side::P2,
+            Side::P2 => Side::P1,
+        }
+    }
+}
+
+/// Something visible that happened while resolving an action.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind")]
+pub enum CombatEvent {
+    Spawned {
+        unit_id: Uuid,
+        pos: Position,
+    },
+    Moved {
+        unit_id: Uuid,
+        from: Position,
+        to: Position,
+    },
+    Damage {
+        source_id: Uuid,
+        target_id: Uuid,
+        amount: u32,
+        remaining_hp: u32,
+    },
+    Healed {
+        source_id: Uuid,
+        target_id: Uuid,
+        amount: u32,
+    },
+    Destroyed {
+        unit_id: Uuid,
+    },
+    /// A duel item was consumed; `remaining` is what the side has left.
+    ItemUsed {
+        item_id: i32,
+        remaining: u32,
+    },
+    ResourcesGained {
+        side: Side,
+        gained: ResourcePool,
+    },
+    /// Hit-points restored by an item.
+    Restored {
+        target_id: Uuid,
+        amount: u32,
+    },
+    /// A unit standing on the enemy back row hit that side’s core.
+    CoreDamage {
+        source_id: Uuid,
+        core: Side,
+        amount: u32,
+        remaining_hp: u32,
+    },
+}
+
+/// Why an action was refused. Serialised as a stable snake_case code.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    /// No catalogue entry for the requested unit type.
+    UnknownUnitType,
+    /// Spawn cell off-board or outside the submitter’s deployment half.
+    OutsideDeployZone,
+    /// Target cell off the board.
+    OffBoard,
+    /// Target cell already holds a unit.
+    Occupied,
+    InsufficientResources,
+    /// Acting unit does not exist (never spawned).
+    UnknownUnit,
+    /// Acting unit belongs to the other seat.
+    NotYourUnit,
+    /// Acting unit died earlier this turn.
+    UnitDestroyed,
+    /// The unit already moved, attacked or used an ability this turn.
+    AlreadyActed,
+    /// Move distance exceeds the unit’s speed.
+    TooFar,
+    /// Target further than the unit’s range.
+    OutOfRange,
+    /// Target missing or on the wrong side.
+    InvalidTarget,
+    /// The unit type does not have that ability.
+    AbilityUnavailable,
+    /// Heal target already at full hp.
+    NoEffect,
+    /// The unit type must be unlocked before it can be deployed.
+    UnitLocked,
+    /// The side has none of that item left (or never brought it).
+    NoSuchItem,
+}
+
+/// One validated action and the events it caused.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ActionOutcome {
+    pub side: Side,
+    pub action: TurnAction,
+    pub events: Vec<CombatEvent>,
+}
+
+/// One refused action and why.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RejectedAction {
+    pub side: Side,
+    pub action: TurnAction,
+    pub reason: RejectReason,
+}
+
+/// Per-turn outcome sent to clients.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct CombatResult {
-    pub applied: Vec<TurnAction>, // all validated actions
-    pub spawned: Vec<Unit>,       // units entering play
-    pub destroyed: Vec<Uuid>,     // units killed this turn
+    pub applied: Vec<TurnAction>,      // all validated actions
+    pub spawned: Vec<Unit>,            // units entering play
+    pub destroyed: Vec<Uuid>,          // units killed this turn
+    pub outcomes: Vec<ActionOutcome>,  // resolution order, for animation
+    pub rejected: Vec<RejectedAction>, // refused actions with reasons
+    pub income_p1: ResourcePool,       // added to p1’s pool at end of turn
+    pub income_p2: ResourcePool,       // added to p2’s pool at end of turn
+    pub breaches: Vec<CombatEvent>,    // end-of-turn core damage
 }
 
+impl CombatResult {
+    fn record(
+        &mut self,
+        side: Side,
+        action: TurnAction,
+        outcome: Result<Vec<CombatEvent>, RejectReason>,
+    ) {
+        match outcome {
+            Ok(events) => {
+                self.destroyed.extend(events.iter().filter_map(|e| match e {
+                    CombatEvent::Destroyed { unit_id } => Some(*unit_id),
+                    _ => None,
+                }));
+                self.applied.push(action.clone());
+                self.outcomes.push(ActionOutcome {
+                    side,
+                    action,
+                    events,
+                });
+            }
+            Err(reason) => self.rejected.push(RejectedAction {
+                side,
+                action,
+                reason,
+            }),
+        }
+    }
+}
+
+/// Resolve one simultaneous turn.
+///
+/// `actions_p1` / `actions_p2` are whatever each seat submitted; the seat is
+/// the only authority on ownership. Spawned units get a server-minted id
+/// (drawn from `state.rng`) and the seat’s player as owner, and a seat may
+/// only move, attack or cast with its own units.
+///
+/// Order: spawns (side picked by coin flip goes first), then item uses in
+/// the same side order, then every move / attack / ability sorted by the acting unit’s speed, with ties
+/// broken by `state.rng`. Each unit acts at most once per turn and a unit
+/// destroyed before its initiative comes up loses its action. Every kill
+/// scores a point for the acting side. Units that end the turn on the enemy
+/// back row then hit the enemy core for their attack value. Income is paid
+/// last, so only units alive at the end of the turn yield.
 pub fn resolve_turn(
+    state: &mut BattleState,
     actions_p1: Vec<TurnAction>,
     actions_p2: Vec<TurnAction>,
-    pool_p1: &mut ResourcePool,
-    pool_p2: &mut ResourcePool,
-    units_p1: &mut Vec<Unit>,
-    units_p2: &mut Vec<Unit>,
 ) -> CombatResult {
-    let mut applied = Vec::new();
-    let mut spawned = Vec::new();
-    let mut destroyed = Vec::new();
+    let mut res = CombatResult::default();
 
     // 1️⃣  Spawn units (pay cost now).
-    let mut play_units =
-        |actions: &Vec<TurnAction>, pool: &mut ResourcePool, field: &mut Vec<Unit>| {
-            for a in actions {
-                if let TurnAction::PlayUnit { unit } = a {
-                    let cost = unit.unit_type.cost();
-                    if pool.can_pay(cost) {
-                        pool.pay(cost);
-                        let mut u = unit.clone();
-                        u.hp = u.unit_type.stats().hp;
-                        field.push(u.clone());
-                        spawned.push(u);
-                        applied.push(a.clone());
-                    }
-                }
+    let first = if state.rng.below(2) == 0 {
+        Side::P1
+    } else {
+        Side::P2
+    };
+    for side in [first, first.other()] {
+        let actions = match side {
+            Side::P1 => &actions_p1,
+            Side::P2 => &actions_p2,
+        };
+        for a in actions {
+            if let TurnAction::PlayUnit { unit_type, pos } = a {
+                let outcome = spawn(state, side, unit_type, *pos).map(|u| {
+                    let events = vec![CombatEvent::Spawned {
+                        unit_id: u.id,
+                        pos: u.pos,
+                    }];
+                    res.spawned.push(u);
+                    events
+                });
+                res.record(side, a.clone(), outcome);
             }
+        }
+    }
+
+    // 2️⃣  Items take effect immediately, same side order as spawns.
+    for side in [first, first.other()] {
+        let actions = match side {
+            Side::P1 => &actions_p1,
+            Side::P2 => &actions_p2,
         };
-    play_units(&actions_p1, pool_p1, units_p1);
-    play_units(&actions_p2, pool_p2, units_p2);
+        for a in actions {
+            if let TurnAction::UseItem { item_id, target_id } = a {
+                let outcome = apply_item(state, side, *item_id, *target_id);
+                res.record(side, a.clone(), outcome);
+            }
+        }
+    }
 
-    // 2️⃣  Collect & sort attacks for deterministic order.
-    let mut all_attacks: Vec<TurnAction> = actions_p1
-        .iter()
-        .chain(actions_p2.iter())
-        .filter(|a| matches!(a, TurnAction::Attack { .. }))
-        .cloned()
-        .collect();
-    all_attacks.sort_by_key(|a| match a {
-        TurnAction::Attack { attacker_id, .. } => *attacker_id,
-        _ => Uuid::nil(),
-    });
-
-    // 3️⃣  Apply attacks.
-    for action in &all_attacks {
-        if let TurnAction::Attack {
-            attacker_id,
-            defender_id,
-        } = *action
-        {
-            let attacker_in_p1 = units_p1.iter().any(|u| u.id == attacker_id);
-
-            if attacker_in_p1 {
-                // attacker is in p1, defender must be in p2
-                if let Some(attacker) = units_p1.iter().find(|u| u.id == attacker_id) {
-                    let power = attacker.unit_type.stats().atk;
-                    if let Some(def_pos) = units_p2.iter().position(|u| u.id == defender_id) {
-                        let defender = &mut units_p2[def_pos];
-                        if power >= defender.hp {
-                            destroyed.push(defender.id);
-                            units_p2.remove(def_pos);
-                        } else {
-                            defender.hp -= power;
-                        }
-                        applied.push(action.clone());
-                    }
+    // 3️⃣  Initiative queue: fastest first, seeded roll breaks ties.
+    let mut queue = Vec::new();
+    for (side, actions) in [(Side::P1, &actions_p1), (Side::P2, &actions_p2)] {
+        for a in actions {
+            let Some(actor) = actor_of(a) else { continue };
+            let speed = state
+                .units(side)
+                .iter()
+                .find(|u| u.id == actor)
+                .and_then(|u| u.unit_type.def())
+                .map_or(0, |d| d.speed);
+            queue.push((Reverse(speed), state.rng.next_u64(), side, a.clone()));
+        }
+    }
+    queue.sort_by_key(|(speed, roll, ..)| (*speed, *roll));
+
+    // 4️⃣  Apply in initiative order.
+    let mut acted: Vec<Uuid> = Vec::new();
+    for (_, _, side, action) in queue {
+        let actor = actor_of(&action).unwrap_or_default();
+        let outcome = own_unit(state, side, actor, &res.destroyed)
+            .and_then(|idx| {
+                if acted.contains(&actor) {
+                    Err(RejectReason::AlreadyActed)
+                } else {
+                    Ok(idx)
                 }
-            } else {
-                // attacker is in p2, defender must be in p1
-                if let Some(attacker) = units_p2.iter().find(|u| u.id == attacker_id) {
-                    let power = attacker.unit_type.stats().atk;
-                    if let Some(def_pos) = units_p1.iter().position(|u| u.id == defender_id) {
-                        let defender = &mut units_p1[def_pos];
-                        if power >= defender.hp {
-                            destroyed.push(defender.id);
-                            units_p1.remove(def_pos);
-                        } else {
-                            defender.hp -= power;
-                        }
-                        applied.push(action.clone());
-                    }
+            })
+            .and_then(|idx| match action {
+                TurnAction::Move { to, .. } => apply_move(state, side, idx, to),
+                TurnAction::Attack { defender_id, .. } => {
+                    apply_attack(state, side, idx, defender_id)
                 }
-            }
+                TurnAction::UseAbility {
+                    ability, target_id, ..
+                } => apply_ability(state, side, idx, ability, target_id),
+                TurnAction::PlayUnit { .. } | TurnAction::UseItem { .. } | TurnAction::Pass => {
+                    unreachable!()
+                }
+            });
+        if let Ok(events) = &outcome {
+            acted.push(actor);
+            let kills = events
+                .iter()
+                .filter(|e| matches!(e, CombatEvent::Destroyed { .. }))
+                .count() as u32;
+            *state.points_mut(side) += kills;
         }
+        res.record(side, action, outcome);
     }
 
-    // 4️⃣  Pass actions are always valid.
-    let mut record_passes = |actions: Vec<TurnAction>| {
-        for a in actions {
-            if matches!(a, TurnAction::Pass) {
-                applied.push(a);
+    // 5️⃣  Pass actions are always valid.
+    for a in actions_p1.into_iter().chain(actions_p2) {
+        if matches!(a, TurnAction::Pass) {
+            res.applied.push(a);
+        }
+    }
+
+    // 6️⃣  Breaches: survivors on the enemy back row damage its core.
+    for side in [Side::P1, Side::P2] {
+        res.breaches.extend(breach(state, side));
+    }
+
+    // 7️⃣  Income: base trickle plus the yield of every surviving unit.
+    res.income_p1 = collect_income(state, Side::P1);
+    res.income_p2 = collect_income(state, Side::P2);
+
+    res
+}
+
+fn breach(state: &mut BattleState, side: Side) -> Vec<CombatEvent> {
+    let back_row = match side {
+        Side::P1 => state.board.rows - 1,
+        Side::P2 => 0,
+    };
+    let hits: Vec<(Uuid, u32)> = state
+        .units(side)
+        .iter()
+        .filter(|u| u.pos.row == back_row)
+        .filter_map(|u| u.unit_type.def().map(|d| (u.id, d.atk)))
+        .filter(|(_, atk)| *atk > 0)
+        .collect();
+    let core = side.other();
+    hits.into_iter()
+        .map(|(source_id, atk)| {
+            let hp = state.core_hp_mut(core);
+            let amount = atk.min(*hp);
+            *hp -= amount;
+            CombatEvent::CoreDamage {
+                source_id,
+                core,
+                amount,
+                remaining_hp: *hp,
+            }
+        })
+        .collect()
+}
+
+fn collect_income(state: &mut BattleState, side: Side) -> ResourcePool {
+    let mut income = state.base_income.clone();
+    for def in state.units(side).iter().filter_map(|u| u.unit_type.def()) {
+        income.add(&def.income);
+    }
+    state.pool_mut(side).add(&income);
+    income
+}
+
+/// Unit performing a board action (`None` for spawns, items and passes).
+fn actor_of(a: &TurnAction) -> Option<Uuid> {
+    match a {
+        TurnAction::Move { unit_id, .. } | TurnAction::UseAbility { unit_id, .. } => Some(*unit_id),
+        TurnAction::Attack { attacker_id, .. } => Some(*attacker_id),
+        TurnAction::PlayUnit { .. } | TurnAction::UseItem { .. } | TurnAction::Pass => None,
+    }
+}
+
+/// Index of `id` among `side`’s living units, or why it cannot act.
+fn own_unit(
+    state: &BattleState,
+    side: Side,
+    id: Uuid,
+    destroyed: &[Uuid],
+) -> Result<usize, RejectReason> {
+    if let Some(idx) = state.units(side).iter().position(|u| u.id == id) {
+        return Ok(idx);
+    }
+    Err(if state.units(side.other()).iter().any(|u| u.id == id) {
+        RejectReason::NotYourUnit
+    } else if destroyed.contains(&id) {
+        RejectReason::UnitDestroyed
+    } else {
+        RejectReason::UnknownUnit
+    })
+}
+
+/// Server-side spawn: id and owner are never taken from the client.
+fn spawn(
+    state: &mut BattleState,
+    side: Side,
+    unit_type: &UnitType,
+    pos: Position,
+) -> Result<Unit, RejectReason> {
+    let def = unit_type.def().ok_or(RejectReason::UnknownUnitType)?;
+    if def.requires_unlock && !state.unlocked(side).contains(unit_type) {
+        return Err(RejectReason::UnitLocked);
+    }
+    if !state.board.in_deploy_zone(side, pos) {
+        return Err(RejectReason::OutsideDeployZone);
+    }
+    if state.occupied(pos) {
+        return Err(RejectReason::Occupied);
+    }
+    let pool = state.pool_mut(side);
+    if !pool.can_pay(&def.cost) {
+        return Err(RejectReason::InsufficientResources);
+    }
+    pool.pay(&def.cost);
+
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&state.rng.next_u64().to_le_bytes());
+    bytes[8..].copy_from_slice(&state.rng.next_u64().to_le_bytes());
+    let u = Unit {
+        id: uuid::Builder::from_random_bytes(bytes).into_uuid(),
+        unit_type: unit_type.clone(),
+        owner_id: state.owner(side),
+        hp: def.hp,
+        pos,
+    };
+    state.units_mut(side).push(u.clone());
+    Ok(u)
+}
+
+fn apply_move(
+    state: &mut BattleState,
+    side: Side,
+    idx: usize,
+    to: Position,
+) -> Result<Vec<CombatEvent>, RejectReason> {
+    let unit = &state.units(side)[idx];
+    let (unit_id, from) = (unit.id, unit.pos);
+    let speed = unit
+        .unit_type
+        .def()
+        .ok_or(RejectReason::UnknownUnitType)?
+        .speed;
+    if !state.board.contains(to) {
+        return Err(RejectReason::OffBoard);
+    }
+    if state.occupied(to) {
+        return Err(RejectReason::Occupied);
+    }
+    if from.distance(to) > speed {
+        return Err(RejectReason::TooFar);
+    }
+    state.units_mut(side)[idx].pos = to;
+    Ok(vec![CombatEvent::Moved { unit_id, from, to }])
+}
+
+fn apply_attack(
+    state: &mut BattleState,
+    side: Side,
+    idx: usize,
+    defender_id: Uuid,
+) -> Result<Vec<CombatEvent>, RejectReason> {
+    let attacker = &state.units(side)[idx];
+    let (attacker_id, from) = (attacker.id, attacker.pos);
+    let stats = attacker
+        .unit_type
+        .def()
+        .ok_or(RejectReason::UnknownUnitType)?;
+    let defender = state
+        .units(side.other())
+        .iter()
+        .find(|u| u.id == defender_id)
+        .ok_or(RejectReason::InvalidTarget)?;
+    if from.distance(defender.pos) > stats.range {
+        return Err(RejectReason::OutOfRange);
+    }
+    Ok(hit(state, attacker_id, defender_id, stats.atk))
+}
+
+fn apply_ability(
+    state: &mut BattleState,
+    side: Side,
+    idx: usize,
+    ability: Ability,
+    target_id: Option<Uuid>,
+) -> Result<Vec<CombatEvent>, RejectReason> {
+    let caster = state.units(side)[idx].clone();
+    let stats = caster
+        .unit_type
+        .def()
+        .ok_or(RejectReason::UnknownUnitType)?;
+    if !stats.abilities.contains(&ability) {
+        return Err(RejectReason::AbilityUnavailable);
+    }
+    match ability {
+        Ability::Volley => {
+            let targets: Vec<Uuid> = state
+                .units(side.other())
+                .iter()
+                .filter(|u| {
+                    u.pos.lane == caster.pos.lane && caster.pos.distance(u.pos) <= stats.range
+                })
+                .map(|u| u.id)
+                .collect();
+            Ok(targets
+                .into_iter()
+                .flat_map(|t| hit(state, caster.id, t, 1))
+                .collect())
+        }
+        Ability::Regrow => {
+            let target_id = target_id.ok_or(RejectReason::InvalidTarget)?;
+            let target = state
+                .units_mut(side)
+                .iter_mut()
+                .find(|u| u.id == target_id)
+                .ok_or(RejectReason::InvalidTarget)?;
+            if caster.pos.distance(target.pos) > 1 {
+                return Err(RejectReason::OutOfRange);
+            }
+            let max_hp = target.unit_type.def().map_or(0, |d| d.hp);
+            if target.hp >= max_hp {
+                return Err(RejectReason::NoEffect);
             }
+            target.hp += 1;
+            Ok(vec![CombatEvent::Healed {
+                source_id: caster.id,
+                target_id,
+                amount: 1,
+            }])
+        }
+    }
+}
+
+/// Consume one of `side`’s duel items and apply its effects. Targets are
+/// checked before anything changes.
+fn apply_item(
+    state: &mut BattleState,
+    side: Side,
+    item_id: i32,
+    target_id: Option<Uuid>,
+) -> Result<Vec<CombatEvent>, RejectReason> {
+    let stack = state
+        .items_mut(side)
+        .iter()
+        .position(|s| s.item_id == item_id && s.qty > 0)
+        .ok_or(RejectReason::NoSuchItem)?;
+    let effects = state.items_mut(side)[stack].effects.clone();
+
+    let heal_target = if effects.iter().any(|e| matches!(e, Effect::HealUnit { .. })) {
+        let id = target_id.ok_or(RejectReason::InvalidTarget)?;
+        let idx = state
+            .units(side)
+            .iter()
+            .position(|u| u.id == id)
+            .ok_or(RejectReason::InvalidTarget)?;
+        let unit = &state.units(side)[idx];
+        if unit.hp >= unit.unit_type.def().map_or(0, |d| d.hp) {
+            return Err(RejectReason::NoEffect);
         }
+        Some(idx)
+    } else {
+        None
     };
-    record_passes(actions_p1);
-    record_passes(actions_p2);
 
-    CombatResult {
-        applied,
-        spawned,
-        destroyed,
+    let stack = &mut state.items_mut(side)[stack];
+    stack.qty -= 1;
+    let mut events = vec![CombatEvent::ItemUsed {
+        item_id,
+        remaining: stack.qty,
+    }];
+    for effect in effects {
+        match effect {
+            Effect::Resources {
+                energy,
+                biomass,
+                gene_seeds,
+            } => {
+                let gained = ResourcePool {
+                    energy,
+                    biomass,
+                    gene_seeds,
+                };
+                state.pool_mut(side).add(&gained);
+                events.push(CombatEvent::ResourcesGained { side, gained });
+            }
+            Effect::HealUnit { hp } => {
+                let Some(idx) = heal_target else { continue };
+                let unit = &mut state.units_mut(side)[idx];
+                let max_hp = unit.unit_type.def().map_or(0, |d| d.hp);
+                let amount = hp.min(max_hp.saturating_sub(unit.hp));
+                unit.hp += amount;
+                events.push(CombatEvent::Restored {
+                    target_id: unit.id,
+                    amount,
+                });
+            }
+            // Out-of-duel effects never make it into a battle loadout.
+            _ => {}
+        }
+    }
+    Ok(events)
+}
+
+/// Deal damage, removing the target if it drops to 0 hp.
+fn hit(state: &mut BattleState, source_id: Uuid, target_id: Uuid, amount: u32) -> Vec<CombatEvent> {
+    let Some((side, idx)) = state.find(target_id) else {
+        return Vec::new();
+    };
+    let target = &mut state.units_mut(side)[idx];
+    target.hp = target.hp.saturating_sub(amount);
+    let mut events = vec![CombatEvent::Damage {
+        source_id,
+        target_id,
+        amount,
+        remaining_hp: target.hp,
+    }];
+    if target.hp == 0 {
+        state.units_mut(side).remove(idx);
+        events.push(CombatEvent::Destroyed { unit_id: target_id });
     }
+    events
 }
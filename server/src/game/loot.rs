@@ -0,0 +1,138 @@
+//! Loot tables for match rewards.
+//!
+//! Rolling is pure and seeded: the same table and [`loot_seed`] always give
+//! the same [`Roll`], so a recorded reward can be audited by replaying it
+//! from its stored seed. Seeds are keyed with `LOOT_SECRET`, so they cannot
+//! be worked out from the public game and player ids in advance.
+//! Loading tables and paying out live in `db::loot_repo`.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{db::trade_repo::BundleItem, game::rng::CombatRng};
+
+/// How a seat finished, from its own point of view.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Win => "win",
+            Outcome::Draw => "draw",
+            Outcome::Loss => "loss",
+        }
+    }
+
+    /// Outcome for `player` given the game’s winner (`None` = draw).
+    pub fn for_player(player: Uuid, winner: Option<Uuid>) -> Self {
+        match winner {
+            None => Outcome::Draw,
+            Some(w) if w == player => Outcome::Win,
+            Some(_) => Outcome::Loss,
+        }
+    }
+}
+
+/// One weighted drop; `item_id: None` is an empty slot.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LootEntry {
+    pub item_id: Option<i32>,
+    pub weight: u32,
+    pub qty_min: i32,
+    pub qty_max: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LootTable {
+    pub id: i32,
+    pub credits_min: i64,
+    pub credits_max: i64,
+    /// Drops rolled from `entries`.
+    pub rolls: u32,
+    pub entries: Vec<LootEntry>,
+}
+
+/// Result of rolling a table once.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct Roll {
+    pub credits: i64,
+    /// Merged by item id, ascending.
+    pub items: Vec<BundleItem>,
+}
+
+/// What one seat received for a finished game.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MatchReward {
+    pub player_id: Uuid,
+    pub outcome: Outcome,
+    /// Table the roll came from.
+    pub table_id: i32,
+    /// Seed the roll used (see [`loot_seed`]).
+    pub seed: u64,
+    pub credits: i64,
+    /// Items actually granted: drops past an item’s `max_stack` are lost,
+    /// so this can be less than the roll.
+    pub items: Vec<BundleItem>,
+}
+
+/// Per-seat seed: an HMAC of the game and player ids under the server’s
+/// `secret`, so each seat rolls its own sequence and nobody without the
+/// secret can predict it.
+pub fn loot_seed(secret: &[u8], game_id: Uuid, player: Uuid) -> u64 {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(game_id.as_bytes());
+    mac.update(player.as_bytes());
+    let digest = mac.finalize().into_bytes();
+    u64::from_le_bytes(digest[..8].try_into().expect("SHA-256 digest is 32 bytes"))
+}
+
+/// Uniform value in `lo..=hi` (`hi < lo` is treated as `lo`).
+fn between(rng: &mut CombatRng, lo: i64, hi: i64) -> i64 {
+    if hi <= lo {
+        return lo;
+    }
+    lo + rng.below((hi - lo) as u64 + 1) as i64
+}
+
+impl LootTable {
+    /// Roll credits, then `rolls` weighted drops.
+    pub fn roll(&self, rng: &mut CombatRng) -> Roll {
+        let credits = between(rng, self.credits_min, self.credits_max);
+        let total: u64 = self.entries.iter().map(|e| e.weight as u64).sum();
+
+        let mut items: Vec<BundleItem> = Vec::new();
+        if total > 0 {
+            for _ in 0..self.rolls {
+                let mut pick = rng.below(total);
+                let Some(entry) = self.entries.iter().find(|e| {
+                    if pick < e.weight as u64 {
+                        true
+                    } else {
+                        pick -= e.weight as u64;
+                        false
+                    }
+                }) else {
+                    continue;
+                };
+                let Some(item_id) = entry.item_id else {
+                    continue;
+                };
+                let qty = between(rng, entry.qty_min as i64, entry.qty_max as i64) as i32;
+                match items.iter_mut().find(|x| x.item_id == item_id) {
+                    Some(x) => x.qty = x.qty.saturating_add(qty),
+                    None => items.push(BundleItem { item_id, qty }),
+                }
+            }
+        }
+        items.sort_by_key(|x| x.item_id);
+        Roll { credits, items }
+    }
+}
@@ -1,5 +1,8 @@
 pub mod logic;
+pub mod loot;
+pub mod rng;
 pub mod scoring;
 pub mod session;
 pub mod snapshot;
 pub mod types;
+pub mod victory;
@@ -0,0 +1,39 @@
+//! Tiny deterministic RNG (SplitMix64) for combat resolution.
+//!
+//! Its whole state is one `u64`, so it round-trips through the Redis
+//! snapshot and a restored session keeps producing the same sequence.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CombatRng {
+    /// Seed the duel started from (kept for replays / audits).
+    pub seed: u64,
+    state: u64,
+}
+
+impl CombatRng {
+    pub fn new(seed: u64) -> Self {
+        CombatRng { seed, state: seed }
+    }
+
+    /// Derive the seed from the game id so every duel is reproducible.
+    pub fn from_game_id(game_id: Uuid) -> Self {
+        let (hi, lo) = game_id.as_u64_pair();
+        Self::new(hi ^ lo)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..n` (`n` must be > 0).
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
@@ -2,16 +2,29 @@
 //! ✔ resume after disconnect            (NEW: loads snapshot if found)
 //! ✔ grace-period auto-forfeit
 //! ✔ persistent per-turn snapshot in Redis
+//! ✔ seeded combat RNG (same game id + inputs ⇒ same replay)
+//! ✔ per-mode victory rules with score breakdown
+//! ✔ turn clock: absent side auto-passes, repeated timeouts forfeit
+//! ✔ turn-number validation, idempotent resubmission
+//! ✔ item loadouts: queued boosts, unlocks and duel items loaded at start
+//! ✔ seeded loot rolls paid out with the result and announced in GameOver
+//! ✔ turn, match and win events fed to quest progress
 
 use crate::{
     config::settings,
-    db::elo_repo,
+    db::{elo_repo, loot_repo},
+    effects,
     game::{
-        logic, scoring,
+        logic,
+        loot::{MatchReward, Outcome},
+        rng::CombatRng,
+        scoring,
         snapshot::Snapshot,
-        types::{ResourcePool, TurnAction, Unit},
+        types::{BattleState, Side, TurnAction},
+        victory::{self, GameMode, RuleScore, Verdict},
     },
-    protocol::{ClientMsg, ServerMsg},
+    progression::{self, EventKind},
+    protocol::{ClientMsg, ErrorCode, ServerMsg, TurnDeadline},
 };
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
@@ -21,31 +34,107 @@ use std::sync::Arc;
 use tokio::{
     sync::mpsc,
     task::JoinHandle,
-    time::{sleep, Duration, Instant},
+    time::{sleep, sleep_until, Duration, Instant},
 };
 use uuid::Uuid;
 
 /// In-memory map of active sessions: game_id → sender
 static SESSIONS: Lazy<DashMap<Uuid, mpsc::Sender<ClientMsg>>> = Lazy::new(DashMap::new);
 
+/// Seats of games with both players, so only a session’s first frames hit
+/// the DB. Dropped with the session.
+static SEATS: Lazy<DashMap<Uuid, (Uuid, Uuid)>> = Lazy::new(DashMap::new);
+
 #[derive(Debug)]
 pub enum DispatchErr {
     ChannelClosed,
+    /// Message `player_id` differs from the socket’s authenticated player.
+    NotAuthenticatedPlayer,
+    UnknownGame,
+    NotYourGame,
+    Db(sqlx::Error),
 }
 
-pub async fn dispatch(db: PgPool, redis: RedisClient, msg: ClientMsg) -> Result<(), DispatchErr> {
-    let game_id = match &msg {
-        ClientMsg::Ready { game_id, .. }
-        | ClientMsg::Turn { game_id, .. }
-        | ClientMsg::Resume { game_id, .. }
-        | ClientMsg::Disconnected { game_id, .. } => *game_id,
-    };
+impl DispatchErr {
+    /// Wire code reported to the client.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            DispatchErr::NotAuthenticatedPlayer => ErrorCode::PlayerMismatch,
+            DispatchErr::UnknownGame => ErrorCode::UnknownGame,
+            DispatchErr::NotYourGame => ErrorCode::NotYourGame,
+            DispatchErr::ChannelClosed | DispatchErr::Db(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+impl std::fmt::Display for DispatchErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DispatchErr::ChannelClosed => f.write_str("game session closed"),
+            DispatchErr::NotAuthenticatedPlayer => {
+                f.write_str("player_id does not match the authenticated player")
+            }
+            DispatchErr::UnknownGame => f.write_str("unknown game"),
+            DispatchErr::NotYourGame => f.write_str("not seated in this game"),
+            DispatchErr::Db(e) => write!(f, "database error: {e}"),
+        }
+    }
+}
+
+/// `(player1, player2)` of `game_id`; cached once both seats are filled.
+async fn seats(db: &PgPool, game_id: Uuid) -> Result<(Uuid, Option<Uuid>), DispatchErr> {
+    if let Some(seats) = SEATS.get(&game_id) {
+        let (p1, p2) = *seats;
+        return Ok((p1, Some(p2)));
+    }
+    let row = sqlx::query!(
+        "SELECT player1_id, player2_id FROM games WHERE id = $1",
+        game_id
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(DispatchErr::Db)?
+    .ok_or(DispatchErr::UnknownGame)?;
+    if let Some(p2) = row.player2_id {
+        SEATS.insert(game_id, (row.player1_id, p2));
+    }
+    Ok((row.player1_id, row.player2_id))
+}
+
+/// Route `msg` to its game’s session task, spawning it on first contact.
+/// `auth_player` is the identity proven at the WS handshake.
+pub async fn dispatch(
+    db: PgPool,
+    redis: RedisClient,
+    auth_player: Uuid,
+    msg: ClientMsg,
+) -> Result<(), DispatchErr> {
+    let game_id = msg.game_id();
+    if msg.player_id() != auth_player {
+        return Err(DispatchErr::NotAuthenticatedPlayer);
+    }
+
+    // Only the two seated players may talk to a game (the WS layer’s own
+    // Disconnected notices are trusted).
+    if !matches!(msg, ClientMsg::Disconnected { .. }) {
+        let (player1, player2) = seats(&db, game_id).await?;
+        let pid = msg.player_id();
+        if player1 != pid && player2 != Some(pid) {
+            return Err(DispatchErr::NotYourGame);
+        }
+    }
 
     // Fast path - already running
     if let Some(tx) = SESSIONS.get(&game_id) {
         return tx.send(msg).await.map_err(|_| DispatchErr::ChannelClosed);
     }
 
+    // A disconnect from a game with no live session (e.g. the socket closing
+    // after GameOver) has nobody to tell; it must not revive the game
+    if matches!(msg, ClientMsg::Disconnected { .. }) {
+        return Ok(());
+    }
+
     // Spawn new actor
     let (tx, mut rx) = mpsc::channel::<ClientMsg>(64);
     tx.send(msg).await.map_err(|_| DispatchErr::ChannelClosed)?;
@@ -68,18 +157,16 @@ pub async fn dispatch(db: PgPool, redis: RedisClient, msg: ClientMsg) -> Result<
         let mut dc_since_p1 = None::<Instant>;
         let mut dc_since_p2 = None::<Instant>;
 
-        let mut pool_p1 = ResourcePool {
-            energy: 5,
-            biomass: 5,
-            gene_seeds: 2,
-        };
-        let mut pool_p2 = pool_p1.clone();
-        let mut units_p1: Vec<Unit> = Vec::new();
-        let mut units_p2: Vec<Unit> = Vec::new();
+        let mut battle = BattleState::new(CombatRng::from_game_id(game_id));
 
         let mut pending_p1 = None::<(u32, Vec<TurnAction>)>;
         let mut pending_p2 = None::<(u32, Vec<TurnAction>)>;
         let mut last_turn_result = None::<ServerMsg>;
+        let mut turn_deadline = None::<Instant>;
+        let mut deadline_msg = None::<TurnDeadline>;
+        let mut timeouts_p1 = 0_u32;
+        let mut timeouts_p2 = 0_u32;
+        let mut loadouts_loaded = false;
 
         // ---- NEW: snapshot restore ---------------------------------------
         if let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await {
@@ -90,27 +177,37 @@ pub async fn dispatch(db: PgPool, redis: RedisClient, msg: ClientMsg) -> Result<
                     p2 = snap.p2;
                     ready_p1 = snap.ready_p1;
                     ready_p2 = snap.ready_p2;
-                    pool_p1 = snap.pool_p1;
-                    pool_p2 = snap.pool_p2;
-                    units_p1 = snap.units_p1;
-                    units_p2 = snap.units_p2;
+                    battle = snap.battle;
                     pending_p1 = snap.pending_p1;
                     pending_p2 = snap.pending_p2;
                     last_turn_result = snap.last_turn_result;
+                    timeouts_p1 = snap.timeouts_p1;
+                    timeouts_p2 = snap.timeouts_p2;
+                    loadouts_loaded = snap.loadouts_loaded;
                     log::info!("Session {game_id} restored from snapshot (turn {turn})");
                 }
             }
         }
         //--------------------------------------------------------------------
 
-        // Mark row InProgress (idempotent)
+        // Mark row InProgress (idempotent; a finished game stays finished)
         let _ = sqlx::query!(
-            "UPDATE games SET state = 'InProgress' WHERE id = $1",
+            "UPDATE games SET state = 'InProgress' WHERE id = $1 AND state <> 'Finished'",
             game_id
         )
         .execute(&db_pool)
         .await;
 
+        // Victory rules come from the row’s mode (default: duel)
+        let mode = sqlx::query_scalar!("SELECT mode FROM games WHERE id = $1", game_id)
+            .fetch_optional(&db_pool)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|m| GameMode::parse(&m))
+            .unwrap_or_default();
+        let rules = mode.rules();
+
         // helper: publish via Redis
         let redis_pub = redis_client.clone();
         let publish = move |pid: Uuid, msg: ServerMsg| -> JoinHandle<()> {
@@ -140,6 +237,8 @@ pub async fn dispatch(db: PgPool, redis: RedisClient, msg: ClientMsg) -> Result<
                         | ClientMsg::Resume{ player_id, .. } => {
                             if p1.is_none()                           { p1 = Some(player_id); }
                             else if p2.is_none() && p1 != Some(player_id) { p2 = Some(player_id); }
+                            battle.p1 = p1.unwrap_or_default();
+                            battle.p2 = p2.unwrap_or_default();
 
                             if Some(player_id) == p1 { ready_p1 = true; dc_since_p1 = None; }
                             if Some(player_id) == p2 { ready_p2 = true; dc_since_p2 = None; }
@@ -151,9 +250,29 @@ pub async fn dispatch(db: PgPool, redis: RedisClient, msg: ClientMsg) -> Result<
                                 }
                             }
 
-                            // If both ready, (re)announce GameStart
+                            // If both ready, (re)announce GameStart; the turn clock is
+                            // armed once per turn, so resending Ready cannot extend it
                             if ready_p1 && ready_p2 {
-                                let gs = ServerMsg::GameStart { game_id, turn };
+                                if turn == 0 && !loadouts_loaded {
+                                    load_loadouts(&db_pool, game_id, &mut battle).await;
+                                    loadouts_loaded = true;
+                                    // Boosts are spent and items escrowed now: persist that
+                                    // before a restart could load them a second time
+                                    save_snapshot(&redis_client, &snap_key, &Snapshot {
+                                        turn, p1, p2, ready_p1, ready_p2,
+                                        battle: battle.clone(),
+                                        pending_p1: pending_p1.clone(), pending_p2: pending_p2.clone(),
+                                        last_turn_result: last_turn_result.clone(),
+                                        timeouts_p1, timeouts_p2,
+                                        loadouts_loaded,
+                                    }).await;
+                                }
+                                if turn_deadline.is_none() {
+                                    let (at, deadline) = arm_deadline(turn);
+                                    turn_deadline = Some(at);
+                                    deadline_msg = Some(deadline);
+                                }
+                                let gs = ServerMsg::GameStart { game_id, turn, deadline: deadline_msg.clone() };
                                 publish(p1.unwrap(), gs.clone()).await.ok();
                                 publish(p2.unwrap(), gs).await.ok();
                                 if let Some(tr) = &last_turn_result {
@@ -170,51 +289,57 @@ pub async fn dispatch(db: PgPool, redis: RedisClient, msg: ClientMsg) -> Result<
                         }
 
                         // ------- Player turn -------------------------------
-                        ClientMsg::Turn{ player_id, turn: t, actions, .. } => {
-                            if Some(player_id) == p1 { pending_p1 = Some((t, actions.clone())); }
-                            if Some(player_id) == p2 { pending_p2 = Some((t, actions.clone())); }
-
-                            if let (Some((ta,a1)), Some((tb,a2))) = (&pending_p1,&pending_p2) {
-                                if ta == tb {
-                                    let result = logic::resolve_turn(
-                                        a1.clone(), a2.clone(),
-                                        &mut pool_p1, &mut pool_p2,
-                                        &mut units_p1,&mut units_p2,
-                                    );
-                                    let tr = ServerMsg::TurnResult{ game_id, turn:*ta, result };
-                                    last_turn_result = Some(tr.clone());
-                                    publish(p1.unwrap(), tr.clone()).await.ok();
-                                    publish(p2.unwrap(), tr.clone()).await.ok();
-
-                                    pending_p1 = None;
-                                    pending_p2 = None;
-                                    turn += 1;
-
-                                    // save snapshot
-                                    if let Ok(mut conn) = redis_client.get_multiplexed_async_connection().await {
-                                        let snap = Snapshot {
-                                            turn, p1, p2, ready_p1, ready_p2,
-                                            pool_p1: pool_p1.clone(), pool_p2: pool_p2.clone(),
-                                            units_p1: units_p1.clone(), units_p2: units_p2.clone(),
-                                            pending_p1: pending_p1.clone(), pending_p2: pending_p2.clone(),
-                                            last_turn_result: last_turn_result.clone(),
-                                        };
-                                        let _: () = conn
-                                            .set_ex(&snap_key, serde_json::to_string(&snap).unwrap(), settings().disconnect_grace)
-                                            .await
-                                            .unwrap_or(());
-                                    }
-
-                                    if turn >= settings().max_turns {
-                                        finish_game(&db_pool,&publish,game_id,&units_p1,&units_p2,p1,p2,&snap_key).await;
-                                        break;
+                        ClientMsg::Turn{ player_id, turn: t, actions, request_id, .. } => {
+                            let (pending, timeouts) = if Some(player_id) == p1 {
+                                (&mut pending_p1, &mut timeouts_p1)
+                            } else if Some(player_id) == p2 {
+                                (&mut pending_p2, &mut timeouts_p2)
+                            } else {
+                                continue;
+                            };
+                            match submit_turn(pending, turn, t, actions) {
+                                Ok(Submission::Accepted) => *timeouts = 0,
+                                Ok(Submission::Resolved) => {
+                                    if let Some(tr) = &last_turn_result {
+                                        publish(player_id, tr.clone()).await.ok();
                                     }
                                 }
+                                Err((code, message)) => {
+                                    publish(player_id, ServerMsg::Error { code, message, request_id }).await.ok();
+                                }
                             }
                         }
                     }
                 }
 
+                // ------- Turn clock: absent side passes ---------------
+                _ = sleep_until(turn_deadline.unwrap_or_else(Instant::now)),
+                    if ready_p1 && ready_p2 && turn_deadline.is_some() => {
+                    turn_deadline = None;
+                    if pending_p1.is_none() { pending_p1 = Some((turn, vec![TurnAction::Pass])); timeouts_p1 += 1; }
+                    if pending_p2.is_none() { pending_p2 = Some((turn, vec![TurnAction::Pass])); timeouts_p2 += 1; }
+                    log::info!("Session {game_id} turn {turn} timed out ({timeouts_p1}/{timeouts_p2})");
+
+                    // Consecutive timeouts by one side forfeit the duel; by
+                    // both, nobody is playing and it ends as a draw
+                    match timeout_end(timeouts_p1, timeouts_p2, settings().max_timeouts) {
+                        Some(TimeoutEnd::Forfeit(Side::P1)) => {
+                            finish_forfeit(&db_pool,&publish,game_id,p2.unwrap(),p1.unwrap(),&snap_key).await;
+                            break;
+                        }
+                        Some(TimeoutEnd::Forfeit(Side::P2)) => {
+                            finish_forfeit(&db_pool,&publish,game_id,p1.unwrap(),p2.unwrap(),&snap_key).await;
+                            break;
+                        }
+                        Some(TimeoutEnd::Abandoned) => {
+                            let verdict = Verdict { winner: None, rule: Some(ABANDONED), scores: Vec::new() };
+                            finish_game(&db_pool,&publish,game_id,verdict,p1,p2,&snap_key).await;
+                            break;
+                        }
+                        None => {}
+                    }
+                }
+
                 // ------- Grace-period watch -------------------------------
                 _ = sleep(Duration::from_secs(5)) => {
                     let grace = Duration::from_secs(settings().disconnect_grace);
@@ -228,56 +353,233 @@ pub async fn dispatch(db: PgPool, redis: RedisClient, msg: ClientMsg) -> Result<
                     }
                 }
             }
+
+            // ------- Resolve once both sides are in (submitted or timed out)
+            if let (Some((ta,a1)), Some((tb,a2))) = (&pending_p1,&pending_p2) {
+                if ta == tb {
+                    let resolved = *ta;
+                    let result = logic::resolve_turn(
+                        &mut battle, a1.clone(), a2.clone(),
+                    );
+                    pending_p1 = None;
+                    pending_p2 = None;
+                    turn += 1;
+                    consume_used_items(&db_pool, game_id, &battle, &result).await;
+                    progression::emit(battle.p1, EventKind::TurnPlayed);
+                    progression::emit(battle.p2, EventKind::TurnPlayed);
+
+                    let final_turn = turn >= settings().max_turns;
+                    let verdict = victory::judge(&rules, &battle, final_turn);
+                    let game_over = final_turn || verdict.winner.is_some();
+
+                    // Next turn’s clock starts now (none once the game is decided)
+                    let deadline = if game_over {
+                        turn_deadline = None;
+                        None
+                    } else {
+                        let (at, deadline) = arm_deadline(turn);
+                        turn_deadline = Some(at);
+                        Some(deadline)
+                    };
+                    deadline_msg = deadline.clone();
+
+                    let tr = ServerMsg::TurnResult{ game_id, turn: resolved, result, deadline };
+                    last_turn_result = Some(tr.clone());
+                    publish(p1.unwrap(), tr.clone()).await.ok();
+                    publish(p2.unwrap(), tr.clone()).await.ok();
+
+                    // save snapshot
+                    save_snapshot(&redis_client, &snap_key, &Snapshot {
+                        turn, p1, p2, ready_p1, ready_p2,
+                        battle: battle.clone(),
+                        pending_p1: pending_p1.clone(), pending_p2: pending_p2.clone(),
+                        last_turn_result: last_turn_result.clone(),
+                        timeouts_p1, timeouts_p2,
+                        loadouts_loaded,
+                    }).await;
+
+                    if game_over {
+                        finish_game(&db_pool,&publish,game_id,verdict,p1,p2,&snap_key).await;
+                        break;
+                    }
+                }
+            }
         }
 
         // final cleanup
+        if let Err(e) = effects::release_duel_items(&db_pool, game_id).await {
+            log::error!("returning duel items of {game_id} failed: {e:?}");
+        }
         SESSIONS.remove(&game_id);
+        SEATS.remove(&game_id);
     });
 
     Ok(())
 }
 
-/// Decide winner: returns Some(player_id) if exactly one side has units remaining.
-fn decide_winner(u1: &[Unit], u2: &[Unit], p1: Option<Uuid>, p2: Option<Uuid>) -> Option<Uuid> {
-    match (u1.is_empty(), u2.is_empty()) {
-        (false, true) => p1,
-        (true, false) => p2,
-        _ => None,
+/// What became of an accepted [`submit_turn`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Submission {
+    /// Stored (or an identical resend of what is stored) for the current turn.
+    Accepted,
+    /// A retry of the turn that just resolved; resend its result.
+    Resolved,
+}
+
+/// Accept a seat’s submission for the `current` turn.
+///
+/// Only the current turn number is accepted, and a seat submits once per
+/// turn: an identical resend (e.g. after a reconnect) is a no-op, a
+/// different one is refused rather than overwriting the first. A retry of
+/// the previous turn, whose result the client may have missed, is answered
+/// rather than refused.
+pub fn submit_turn(
+    pending: &mut Option<(u32, Vec<TurnAction>)>,
+    current: u32,
+    turn: u32,
+    actions: Vec<TurnAction>,
+) -> Result<Submission, (ErrorCode, String)> {
+    if turn.checked_add(1) == Some(current) {
+        return Ok(Submission::Resolved);
+    }
+    if turn != current {
+        return Err((
+            ErrorCode::StaleTurn,
+            format!("expected turn {current}, got {turn}"),
+        ));
     }
+    match pending {
+        Some((_, prev)) if *prev == actions => Ok(Submission::Accepted),
+        Some(_) => Err((
+            ErrorCode::TurnAlreadySubmitted,
+            format!("turn {turn} already submitted"),
+        )),
+        None => {
+            *pending = Some((turn, actions));
+            Ok(Submission::Accepted)
+        }
+    }
+}
+
+/// Victory rule recorded for a game both seats stopped playing. It closes
+/// the game with no winner and moves no rating.
+pub const ABANDONED: &str = "abandoned";
+
+/// How a turn-clock expiry ends the game, if it does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutEnd {
+    /// This seat reached the timeout limit alone and forfeits.
+    Forfeit(Side),
+    /// Both seats reached it: the game is abandoned as a draw.
+    Abandoned,
+}
+
+/// Check the consecutive-timeout counts against `limit`.
+pub fn timeout_end(timeouts_p1: u32, timeouts_p2: u32, limit: u32) -> Option<TimeoutEnd> {
+    match (timeouts_p1 >= limit, timeouts_p2 >= limit) {
+        (true, true) => Some(TimeoutEnd::Abandoned),
+        (true, false) => Some(TimeoutEnd::Forfeit(Side::P1)),
+        (false, true) => Some(TimeoutEnd::Forfeit(Side::P2)),
+        (false, false) => None,
+    }
+}
+
+/// Give each seat its item loadout: queued boosts join the starting pool,
+/// unlocked unit types and owned duel items become usable.
+async fn load_loadouts(db: &PgPool, game_id: Uuid, battle: &mut BattleState) {
+    for side in [Side::P1, Side::P2] {
+        let player = match side {
+            Side::P1 => battle.p1,
+            Side::P2 => battle.p2,
+        };
+        let loadout = match effects::take_loadout(db, game_id, player).await {
+            Ok(l) => l,
+            Err(e) => {
+                log::error!("loadout for {player} failed: {e:?}");
+                continue;
+            }
+        };
+        let (pool, unlocked, items) = match side {
+            Side::P1 => (&mut battle.pool_p1, &mut battle.unlocked_p1, &mut battle.items_p1),
+            Side::P2 => (&mut battle.pool_p2, &mut battle.unlocked_p2, &mut battle.items_p2),
+        };
+        pool.add(&loadout.boost);
+        *unlocked = loadout.unlocked;
+        *items = loadout.items;
+    }
+}
+
+/// Take the duel items used this turn out of the game’s escrow.
+async fn consume_used_items(
+    db: &PgPool,
+    game_id: Uuid,
+    battle: &BattleState,
+    result: &logic::CombatResult,
+) {
+    for o in &result.outcomes {
+        let TurnAction::UseItem { item_id, .. } = o.action else {
+            continue;
+        };
+        let player = match o.side {
+            Side::P1 => battle.p1,
+            Side::P2 => battle.p2,
+        };
+        if let Err(e) = effects::consume_duel_item(db, game_id, player, item_id).await {
+            log::warn!("duel item {item_id} used by {player} not debited: {e:?}");
+        }
+    }
+}
+
+/// Store `snap` under `key` for as long as a disconnected seat may return.
+async fn save_snapshot(redis: &RedisClient, key: &str, snap: &Snapshot) {
+    if let Ok(mut conn) = redis.get_multiplexed_async_connection().await {
+        let _: () = conn
+            .set_ex(
+                key,
+                serde_json::to_string(snap).unwrap(),
+                settings().disconnect_grace,
+            )
+            .await
+            .unwrap_or(());
+    }
+}
+
+/// Start the clock for `turn`: (local expiry, wire announcement).
+fn arm_deadline(turn: u32) -> (Instant, TurnDeadline) {
+    let seconds = settings().turn_timeout;
+    let deadline = TurnDeadline {
+        turn,
+        seconds,
+        expires_at: chrono::Utc::now() + chrono::Duration::seconds(seconds as i64),
+    };
+    (Instant::now() + Duration::from_secs(seconds), deadline)
 }
 
-#[allow(clippy::too_many_arguments)]
 async fn finish_game(
     db: &PgPool,
     publish: &impl Fn(Uuid, ServerMsg) -> JoinHandle<()>,
     gid: Uuid,
-    u1: &[Unit],
-    u2: &[Unit],
+    verdict: Verdict,
     p1: Option<Uuid>,
     p2: Option<Uuid>,
     snap_key: &str,
 ) {
-    let winner = decide_winner(u1, u2, p1, p2);
-    apply_elo_and_persist(db, gid, winner, p1, p2).await;
+    let winner = verdict.winner.and_then(|side| match side {
+        Side::P1 => p1,
+        Side::P2 => p2,
+    });
+    let rewards =
+        apply_elo_and_persist(db, gid, winner, p1, p2, verdict.rule, &verdict.scores).await;
     if let (Some(a), Some(b)) = (p1, p2) {
-        publish(
-            a,
-            ServerMsg::GameOver {
-                game_id: gid,
-                winner,
-            },
-        )
-        .await
-        .ok();
-        publish(
-            b,
-            ServerMsg::GameOver {
-                game_id: gid,
-                winner,
-            },
-        )
-        .await
-        .ok();
+        let over = ServerMsg::GameOver {
+            game_id: gid,
+            winner,
+            rule: verdict.rule.map(str::to_string),
+            scores: verdict.scores,
+            rewards,
+        };
+        publish(a, over.clone()).await.ok();
+        publish(b, over).await.ok();
     }
     let _: () = redis_cleanup(db, snap_key).await;
 }
@@ -290,25 +592,25 @@ async fn finish_forfeit(
     loser: Uuid,
     snap_key: &str,
 ) {
-    apply_elo_and_persist(db, gid, Some(winner), Some(winner), Some(loser)).await;
-    publish(
-        winner,
-        ServerMsg::GameOver {
-            game_id: gid,
-            winner: Some(winner),
-        },
-    )
-    .await
-    .ok();
-    publish(
-        loser,
-        ServerMsg::GameOver {
-            game_id: gid,
-            winner: Some(winner),
-        },
+    let rewards = apply_elo_and_persist(
+        db,
+        gid,
+        Some(winner),
+        Some(winner),
+        Some(loser),
+        Some("forfeit"),
+        &[],
     )
-    .await
-    .ok();
+    .await;
+    let over = ServerMsg::GameOver {
+        game_id: gid,
+        winner: Some(winner),
+        rule: Some("forfeit".into()),
+        scores: Vec::new(),
+        rewards,
+    };
+    publish(winner, over.clone()).await.ok();
+    publish(loser, over).await.ok();
     let _: () = redis_cleanup(db, snap_key).await;
 }
 
@@ -322,37 +624,95 @@ async fn redis_cleanup(_db: &PgPool, key: &str) {
     }
 }
 
+/// Apply Elo, record the result and pay each seat’s match reward, all in
+/// one transaction. Returns the rewards to announce in `GameOver`.
 async fn apply_elo_and_persist(
     db: &PgPool,
     gid: Uuid,
     winner: Option<Uuid>,
     p1_opt: Option<Uuid>,
     p2_opt: Option<Uuid>,
-) {
-    if let (Some(p1), Some(p2)) = (p1_opt, p2_opt) {
-        let (r1, r2) = sqlx::query_as::<_, (i32, i32)>(
-            "SELECT p1.elo_rating, p2.elo_rating FROM players p1, players p2 WHERE p1.id=$1 AND p2.id=$2"
-        )
-        .bind(p1)
-        .bind(p2)
-        .fetch_one(db)
-        .await
-        .unwrap();
+    rule: Option<&str>,
+    scores: &[RuleScore],
+) -> Vec<MatchReward> {
+    let (Some(p1), Some(p2)) = (p1_opt, p2_opt) else {
+        return Vec::new();
+    };
+    match persist_result(db, gid, winner, p1, p2, rule, scores).await {
+        Ok(rewards) => rewards,
+        Err(e) => {
+            log::error!("persisting result of {gid} failed: {e:?}");
+            Vec::new()
+        }
+    }
+}
 
+/// Record the result of `gid`, finishing the game, and pay each seat’s
+/// match reward. An [`ABANDONED`] game is finished without a winner,
+/// rating change or reward.
+pub async fn persist_result(
+    db: &PgPool,
+    gid: Uuid,
+    winner: Option<Uuid>,
+    p1: Uuid,
+    p2: Uuid,
+    rule: Option<&str>,
+    scores: &[RuleScore],
+) -> anyhow::Result<Vec<MatchReward>> {
+    let mut tx = db.begin().await?;
+    let (mode, r1, r2) = sqlx::query_as::<_, (String, i32, i32)>(
+        "SELECT g.mode, p1.elo_rating, p2.elo_rating FROM games g, players p1, players p2
+          WHERE g.id=$1 AND p1.id=$2 AND p2.id=$3"
+    )
+    .bind(gid)
+    .bind(p1)
+    .bind(p2)
+    .fetch_one(&mut *tx)
+    .await?;
+    let mode = GameMode::parse(&mode).unwrap_or_default();
+
+    let abandoned = rule == Some(ABANDONED);
+    let (d1, d2) = if abandoned {
+        (0, 0)
+    } else {
         let flag = match winner {
             Some(id) if id == p1 => 1,
             Some(id) if id == p2 => 2,
             _ => 0,
         };
         let (d1, d2) = scoring::elo_delta(r1, r2, flag, 32.0);
-        let _ = elo_repo::apply_delta(db, p1, d1).await;
-        let _ = elo_repo::apply_delta(db, p2, d2).await;
+        elo_repo::apply_delta(&mut *tx, p1, d1).await?;
+        elo_repo::apply_delta(&mut *tx, p2, d2).await?;
+        (d1, d2)
+    };
 
-        let _ = sqlx::query!(
-            "UPDATE games SET state='Finished', winner_id=$1, player1_elo_delta=$2, player2_elo_delta=$3 WHERE id=$4",
-            winner, d1, d2, gid
-        )
-        .execute(db)
-        .await;
+    sqlx::query!(
+        "UPDATE games SET state='Finished', winner_id=$1, player1_elo_delta=$2, player2_elo_delta=$3,
+                          victory_rule=$4, score_breakdown=$5 WHERE id=$6",
+        winner, d1, d2, rule, serde_json::to_value(scores).unwrap_or_default(), gid
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Loot bands use the pre-game rating; only a played-out result pays.
+    let mut rewards = Vec::with_capacity(2);
+    if !abandoned {
+        for (player, rating) in [(p1, r1), (p2, r2)] {
+            let outcome = Outcome::for_player(player, winner);
+            if let Some(r) = loot_repo::award(&mut tx, gid, player, mode, outcome, rating).await? {
+                rewards.push(r);
+            }
+        }
+    }
+
+    tx.commit().await?;
+    if abandoned {
+        return Ok(rewards);
+    }
+    progression::emit(p1, EventKind::MatchPlayed);
+    progression::emit(p2, EventKind::MatchPlayed);
+    if let Some(w) = winner {
+        progression::emit(w, EventKind::MatchWon);
     }
+    Ok(rewards)
 }
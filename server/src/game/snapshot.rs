@@ -1,7 +1,7 @@
 //! Serializable per-game snapshot stored in Redis after every turn.
 
 use crate::{
-    game::types::{ResourcePool, TurnAction, Unit},
+    game::types::{BattleState, TurnAction},
     protocol::ServerMsg,
 };
 use serde::{Deserialize, Serialize};
@@ -17,13 +17,20 @@ pub struct Snapshot {
     pub ready_p1: bool,
     pub ready_p2: bool,
 
-    pub pool_p1: ResourcePool,
-    pub pool_p2: ResourcePool,
-    pub units_p1: Vec<Unit>,
-    pub units_p2: Vec<Unit>,
+    pub battle: BattleState,
 
     pub pending_p1: Option<(u32, Vec<TurnAction>)>,
     pub pending_p2: Option<(u32, Vec<TurnAction>)>,
 
     pub last_turn_result: Option<ServerMsg>,
+
+    /// Consecutive turn-clock expiries per seat.
+    #[serde(default)]
+    pub timeouts_p1: u32,
+    #[serde(default)]
+    pub timeouts_p2: u32,
+
+    /// Item loadouts already applied to `battle` (done once per game).
+    #[serde(default)]
+    pub loadouts_loaded: bool,
 }
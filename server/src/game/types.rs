@@ -1,21 +1,104 @@
+use crate::{effects::Effect, game::rng::CombatRng};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use uuid::Uuid;
 
 /// Tracks a player’s resource pools.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
 pub struct ResourcePool {
     pub energy: u32,
     pub biomass: u32,
     pub gene_seeds: u32,
 }
 
-/// Four starting archetypes.
+impl ResourcePool {
+    pub fn add(&mut self, other: &ResourcePool) {
+        self.energy = self.energy.saturating_add(other.energy);
+        self.biomass = self.biomass.saturating_add(other.biomass);
+        self.gene_seeds = self.gene_seeds.saturating_add(other.gene_seeds);
+    }
+}
+
+/// Id of a row in the `unit_defs` catalogue (see [`crate::cache::UNIT_DEFS`]).
+/// Serialised as the bare id string, e.g. `"Light"`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct UnitType(pub Cow<'static, str>);
+
+impl UnitType {
+    // The four starting archetypes seeded by migration 0020.
+    pub const LIGHT: UnitType = UnitType(Cow::Borrowed("Light"));
+    pub const RANGED: UnitType = UnitType(Cow::Borrowed("Ranged"));
+    pub const HEAVY: UnitType = UnitType(Cow::Borrowed("Heavy"));
+    pub const SEEDER: UnitType = UnitType(Cow::Borrowed("Seeder"));
+
+    pub fn new(id: impl Into<String>) -> Self {
+        UnitType(Cow::Owned(id.into()))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Special moves a unit may trigger instead of a plain attack.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Ability {
+    /// 1 damage to every enemy in the caster’s lane within range.
+    Volley,
+    /// Restore 1 hp to an adjacent friendly unit.
+    Regrow,
+}
+
+/// A cell on the board. `lane` runs across, `row` runs from p1’s edge (0)
+/// towards p2’s edge (`rows - 1`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub lane: u8,
+    pub row: u8,
+}
+
+impl Position {
+    /// Manhattan distance between two cells.
+    pub fn distance(self, other: Position) -> u32 {
+        (self.lane.abs_diff(other.lane) + self.row.abs_diff(other.row)) as u32
+    }
+}
+
+/// Seat of a player in a duel.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    P1,
+    P2,
+}
+
+/// Lane/grid battlefield dimensions. Each side deploys into its own half.
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
-pub enum UnitType {
-    Light,
-    Ranged,
-    Heavy,
-    Seeder,
+pub struct Board {
+    pub lanes: u8,
+    pub rows: u8,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board { lanes: 5, rows: 6 }
+    }
+}
+
+impl Board {
+    pub fn contains(&self, pos: Position) -> bool {
+        pos.lane < self.lanes && pos.row < self.rows
+    }
+
+    /// True if `pos` lies in the deployment half of `side`.
+    pub fn in_deploy_zone(&self, side: Side, pos: Position) -> bool {
+        let half = self.rows / 2;
+        self.contains(pos)
+            && match side {
+                Side::P1 => pos.row < half,
+                Side::P2 => pos.row >= self.rows - half,
+            }
+    }
 }
 
 /// One unit on the battlefield.
@@ -25,18 +108,131 @@ pub struct Unit {
     pub unit_type: UnitType,
     pub owner_id: Uuid,
     pub hp: u32, // current hit-points
+    pub pos: Position,
 }
 
-/// Player intent each turn.
+/// Duel-usable items a side brought into the game (see `effects`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item_id: i32,
+    pub qty: u32,
+    pub effects: Vec<Effect>,
+}
+
+/// Everything `resolve_turn` reads and mutates for one duel.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BattleState {
+    /// Player seated as p1 / p2; stamped as `owner_id` on spawned units.
+    /// Older snapshots lack them; the session re-stamps them on `Ready`.
+    #[serde(default)]
+    pub p1: Uuid,
+    #[serde(default)]
+    pub p2: Uuid,
+    pub board: Board,
+    pub rng: CombatRng,
+    pub pool_p1: ResourcePool,
+    pub pool_p2: ResourcePool,
+    pub units_p1: Vec<Unit>,
+    pub units_p2: Vec<Unit>,
+    /// Granted to each side at the end of every turn, before unit income.
+    #[serde(default = "default_base_income")]
+    pub base_income: ResourcePool,
+    /// Base/core hit-points; units ending a turn on the enemy back row hit it.
+    #[serde(default = "starting_core_hp")]
+    pub core_hp_p1: u32,
+    #[serde(default = "starting_core_hp")]
+    pub core_hp_p2: u32,
+    /// One point per enemy unit destroyed.
+    #[serde(default)]
+    pub points_p1: u32,
+    #[serde(default)]
+    pub points_p2: u32,
+    /// Items each side may use via [`TurnAction::UseItem`].
+    #[serde(default)]
+    pub items_p1: Vec<ItemStack>,
+    #[serde(default)]
+    pub items_p2: Vec<ItemStack>,
+    /// Unit types each side has unlocked (only checked for types that
+    /// `requires_unlock`).
+    #[serde(default)]
+    pub unlocked_p1: Vec<UnitType>,
+    #[serde(default)]
+    pub unlocked_p2: Vec<UnitType>,
+}
+
+pub const STARTING_CORE_HP: u32 = 10;
+
+fn starting_core_hp() -> u32 {
+    STARTING_CORE_HP
+}
+
+fn default_base_income() -> ResourcePool {
+    ResourcePool {
+        energy: 2,
+        biomass: 1,
+        gene_seeds: 0,
+    }
+}
+
+impl BattleState {
+    /// Fresh duel on the default board with starter pools.
+    pub fn new(rng: CombatRng) -> Self {
+        let pool = ResourcePool {
+            energy: 5,
+            biomass: 5,
+            gene_seeds: 2,
+        };
+        BattleState {
+            p1: Uuid::nil(),
+            p2: Uuid::nil(),
+            board: Board::default(),
+            rng,
+            base_income: default_base_income(),
+            pool_p1: pool.clone(),
+            pool_p2: pool,
+            units_p1: Vec::new(),
+            units_p2: Vec::new(),
+            core_hp_p1: STARTING_CORE_HP,
+            core_hp_p2: STARTING_CORE_HP,
+            points_p1: 0,
+            points_p2: 0,
+            items_p1: Vec::new(),
+            items_p2: Vec::new(),
+            unlocked_p1: Vec::new(),
+            unlocked_p2: Vec::new(),
+        }
+    }
+}
+
+/// Player intent each turn.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum TurnAction {
+    /// Deploy a new unit; the server mints its id and sets the owner.
     PlayUnit {
-        unit: Unit,
+        unit_type: UnitType,
+        pos: Position,
+    },
+    Move {
+        unit_id: Uuid,
+        to: Position,
     },
     Attack {
         attacker_id: Uuid,
         defender_id: Uuid,
     },
+    UseAbility {
+        unit_id: Uuid,
+        ability: Ability,
+        #[serde(default)]
+        target_id: Option<Uuid>,
+    },
+    /// Use one of the side’s duel items; `target_id` is a friendly unit for
+    /// effects that need one.
+    UseItem {
+        item_id: i32,
+        #[serde(default)]
+        target_id: Option<Uuid>,
+    },
     Pass,
 }
 
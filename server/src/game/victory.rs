@@ -0,0 +1,202 @@
+//! Victory conditions and tiebreakers.
+//!
+//! Every game mode owns an ordered rule set: the first rule that names a
+//! winner decides the game, later rules act as tiebreakers. Each rule also
+//! reports a (p1, p2) score so clients can show why the duel ended.
+
+use crate::game::types::{BattleState, Side};
+use serde::{Deserialize, Serialize};
+
+/// Game modes selectable per `games` row (`games.mode`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    /// Wipe the enemy; remaining HP breaks ties.
+    #[default]
+    Duel,
+    /// Destroy the enemy core.
+    Siege,
+    /// First to N kills.
+    Skirmish,
+}
+
+impl GameMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "duel" => Some(GameMode::Duel),
+            "siege" => Some(GameMode::Siege),
+            "skirmish" => Some(GameMode::Skirmish),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GameMode::Duel => "duel",
+            GameMode::Siege => "siege",
+            GameMode::Skirmish => "skirmish",
+        }
+    }
+
+    /// Ordered rule set: decisive rule first, tiebreakers after.
+    pub fn rules(self) -> Vec<Box<dyn VictoryRule>> {
+        match self {
+            GameMode::Duel => vec![Box::new(Elimination), Box::new(TotalHp)],
+            GameMode::Siege => vec![Box::new(CoreHp), Box::new(Elimination), Box::new(TotalHp)],
+            GameMode::Skirmish => vec![
+                Box::new(Points { target: 5 }),
+                Box::new(Elimination),
+                Box::new(TotalHp),
+            ],
+        }
+    }
+}
+
+pub trait VictoryRule: Send + Sync {
+    /// Stable identifier stored in `games.victory_rule`.
+    fn name(&self) -> &'static str;
+
+    /// (p1, p2) standing under this rule.
+    fn score(&self, state: &BattleState) -> (i64, i64);
+
+    /// Winner under this rule. While the duel is running (`final_turn` is
+    /// false) only sudden-death results should be returned.
+    fn winner(&self, state: &BattleState, final_turn: bool) -> Option<Side>;
+}
+
+/// Side with the strictly higher score, if any.
+fn higher((p1, p2): (i64, i64)) -> Option<Side> {
+    match p1.cmp(&p2) {
+        std::cmp::Ordering::Greater => Some(Side::P1),
+        std::cmp::Ordering::Less => Some(Side::P2),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// Exactly one side still has units at the end.
+pub struct Elimination;
+
+impl VictoryRule for Elimination {
+    fn name(&self) -> &'static str {
+        "elimination"
+    }
+    fn score(&self, state: &BattleState) -> (i64, i64) {
+        (state.units_p1.len() as i64, state.units_p2.len() as i64)
+    }
+    fn winner(&self, state: &BattleState, final_turn: bool) -> Option<Side> {
+        if !final_turn {
+            return None;
+        }
+        match (state.units_p1.is_empty(), state.units_p2.is_empty()) {
+            (false, true) => Some(Side::P1),
+            (true, false) => Some(Side::P2),
+            _ => None,
+        }
+    }
+}
+
+/// More total hit-points on the board at the end.
+pub struct TotalHp;
+
+impl VictoryRule for TotalHp {
+    fn name(&self) -> &'static str {
+        "total_hp"
+    }
+    fn score(&self, state: &BattleState) -> (i64, i64) {
+        let sum = |units: &[crate::game::types::Unit]| units.iter().map(|u| u.hp as i64).sum();
+        (sum(&state.units_p1), sum(&state.units_p2))
+    }
+    fn winner(&self, state: &BattleState, final_turn: bool) -> Option<Side> {
+        final_turn.then(|| higher(self.score(state))).flatten()
+    }
+}
+
+/// Reduce the enemy core to 0 hp (sudden death); otherwise the healthier
+/// core wins at the end.
+pub struct CoreHp;
+
+impl VictoryRule for CoreHp {
+    fn name(&self) -> &'static str {
+        "core_hp"
+    }
+    fn score(&self, state: &BattleState) -> (i64, i64) {
+        (state.core_hp_p1 as i64, state.core_hp_p2 as i64)
+    }
+    fn winner(&self, state: &BattleState, final_turn: bool) -> Option<Side> {
+        match (state.core_hp_p1, state.core_hp_p2) {
+            (0, 0) => None,
+            (_, 0) => Some(Side::P1),
+            (0, _) => Some(Side::P2),
+            _ if final_turn => higher(self.score(state)),
+            _ => None,
+        }
+    }
+}
+
+/// First side to reach `target` points (one per kill); otherwise the
+/// higher score at the end.
+pub struct Points {
+    pub target: u32,
+}
+
+impl VictoryRule for Points {
+    fn name(&self) -> &'static str {
+        "points"
+    }
+    fn score(&self, state: &BattleState) -> (i64, i64) {
+        (state.points_p1 as i64, state.points_p2 as i64)
+    }
+    fn winner(&self, state: &BattleState, final_turn: bool) -> Option<Side> {
+        let reached = (
+            state.points_p1 >= self.target,
+            state.points_p2 >= self.target,
+        );
+        match reached {
+            (true, false) => Some(Side::P1),
+            (false, true) => Some(Side::P2),
+            (true, true) => higher(self.score(state)),
+            (false, false) if final_turn => higher(self.score(state)),
+            _ => None,
+        }
+    }
+}
+
+/// One line of the score breakdown sent with `GameOver`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RuleScore {
+    pub rule: String,
+    pub p1: i64,
+    pub p2: i64,
+}
+
+/// Outcome of applying a rule set to the board.
+#[derive(Debug, Clone)]
+pub struct Verdict {
+    pub winner: Option<Side>,
+    /// Rule that decided the game (`None` on a draw).
+    pub rule: Option<&'static str>,
+    pub scores: Vec<RuleScore>,
+}
+
+/// Apply `rules` in order; the first to name a winner decides.
+pub fn judge(rules: &[Box<dyn VictoryRule>], state: &BattleState, final_turn: bool) -> Verdict {
+    let scores = rules
+        .iter()
+        .map(|r| {
+            let (p1, p2) = r.score(state);
+            RuleScore {
+                rule: r.name().to_string(),
+                p1,
+                p2,
+            }
+        })
+        .collect();
+    let decided = rules
+        .iter()
+        .find_map(|r| r.winner(state, final_turn).map(|w| (w, r.name())));
+    Verdict {
+        winner: decided.map(|(w, _)| w),
+        rule: decided.map(|(_, n)| n),
+        scores,
+    }
+}
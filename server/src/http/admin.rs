@@ -0,0 +1,82 @@
+//! Operator-only endpoints, guarded by the `ADMIN_TOKEN` shared secret.
+
+use actix_web::{
+    dev::Payload, error, error::ErrorUnauthorized, post, put, web, FromRequest, HttpRequest,
+    HttpResponse,
+};
+use futures_util::future::{ready, Ready};
+use sqlx::PgPool;
+use std::env;
+
+use crate::{cache, pricing};
+
+/// Passes only if `X-Admin-Token` matches the `ADMIN_TOKEN` env var.
+#[derive(Debug, Clone)]
+pub struct AdminAuth;
+
+impl FromRequest for AdminAuth {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _pl: &mut Payload) -> Self::Future {
+        let expected = env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+        let given = req
+            .headers()
+            .get("X-Admin-Token")
+            .and_then(|v| v.to_str().ok());
+
+        ready(match (expected, given) {
+            (Some(e), Some(g)) if e == g => Ok(AdminAuth),
+            (None, _) => Err(ErrorUnauthorized("admin API disabled")),
+            _ => Err(ErrorUnauthorized("bad admin token")),
+        })
+    }
+}
+
+/// POST /api/admin/unit_defs/reload
+#[post("/admin/unit_defs/reload")]
+pub async fn reload_unit_defs(
+    _admin: AdminAuth,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let loaded = cache::warm_unit_defs(&db)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    log::info!("unit catalogue reloaded: {loaded} defs");
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "loaded": loaded })))
+}
+
+/// POST /api/admin/quests/reload
+#[post("/admin/quests/reload")]
+pub async fn reload_quests(
+    _admin: AdminAuth,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let loaded = cache::warm_quests(&db)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    log::info!("quest catalogue reloaded: {loaded} quests");
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "loaded": loaded })))
+}
+
+/// PUT /api/admin/items/{id}/price_curve
+#[put("/admin/items/{id}/price_curve")]
+pub async fn set_price_curve(
+    _admin: AdminAuth,
+    path: web::Path<i32>,
+    curve: web::Json<pricing::Curve>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let item_id = path.into_inner();
+    pricing::set_curve(&db, item_id, &curve)
+        .await
+        .map_err(error::ErrorBadRequest)?;
+    log::info!("price curve for item {item_id} set to {curve:?}");
+    Ok(HttpResponse::Ok().json(*curve))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(reload_unit_defs)
+        .service(reload_quests)
+        .service(set_price_curve);
+}
@@ -1,7 +1,10 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{middleware::from_fn, post, web, HttpResponse, Responder};
 use serde::Deserialize;
 
-use crate::chain::relay_tx;
+use crate::{
+    chain::relay_tx,
+    http::{auth::JwtAuth, idempotency::idempotency},
+};
 
 #[derive(Deserialize)]
 struct SponsoredReq {
@@ -9,9 +12,13 @@ struct SponsoredReq {
 }
 
 /// POST /api/tx/sponsored  { payload_hex }
-#[post("/tx/sponsored")]
-async fn sponsored(web::Json(req): web::Json<SponsoredReq>) -> impl Responder {
-    match relay_tx(crate::chain::relay::RawPayload { payload_hex: req.payload_hex }).await {
+#[post("/tx/sponsored", wrap = "from_fn(idempotency)")]
+async fn sponsored(_auth: JwtAuth, web::Json(req): web::Json<SponsoredReq>) -> impl Responder {
+    match relay_tx(crate::chain::relay::RawPayload {
+        payload_hex: req.payload_hex,
+    })
+    .await
+    {
         Ok(hash) => HttpResponse::Ok().json(serde_json::json!({"hash": hash})),
         Err(e) => {
             log::warn!("sponsored‑tx error: {e:?}");
@@ -22,4 +29,4 @@ async fn sponsored(web::Json(req): web::Json<SponsoredReq>) -> impl Responder {
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(sponsored);
-}
\ No newline at end of file
+}
@@ -65,6 +65,54 @@ pub mod extractor {
         pub player_id: Uuid,
     }
 
+    impl JwtAuth {
+        /// Validate a raw JWT and read the user / player ids from its claims.
+        pub fn from_token(token: &str) -> ActixResult<Self> {
+            let secret =
+                env::var("JWT_SECRET").map_err(|_| ErrorUnauthorized("server mis-config"))?;
+            let data = decode::<Claims>(
+                token,
+                &DecodingKey::from_secret(secret.as_bytes()),
+                &Validation::default(),
+            )
+            .map_err(|_| ErrorUnauthorized("invalid / expired token"))?;
+
+            let user_id =
+                Uuid::parse_str(&data.claims.sub).map_err(|_| ErrorUnauthorized("bad sub"))?;
+            let player_id =
+                Uuid::parse_str(&data.claims.pid).map_err(|_| ErrorUnauthorized("bad pid"))?;
+
+            Ok(JwtAuth { user_id, player_id })
+        }
+
+        /// WebSocket handshake: browsers cannot set `Authorization`, so the
+        /// token comes as `?token=<JWT>` or as the second entry of
+        /// `Sec-WebSocket-Protocol: bearer, <JWT>`.
+        pub fn from_ws_request(req: &HttpRequest) -> ActixResult<Self> {
+            let from_query = req
+                .query_string()
+                .split('&')
+                .find_map(|kv| kv.strip_prefix("token="));
+            let from_protocol = req
+                .headers()
+                .get("Sec-WebSocket-Protocol")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| {
+                    let mut parts = v.split(',').map(str::trim);
+                    (parts.next() == Some(WS_SUBPROTOCOL))
+                        .then(|| parts.next())
+                        .flatten()
+                });
+            let token = from_query
+                .or(from_protocol)
+                .ok_or_else(|| ErrorUnauthorized("missing token"))?;
+            Self::from_token(token)
+        }
+    }
+
+    /// Subprotocol name a client offers alongside its token; echoed back.
+    pub const WS_SUBPROTOCOL: &str = "bearer";
+
     impl FromRequest for JwtAuth {
         type Error = actix_web::Error;
         type Future = Ready<ActixResult<Self, Self::Error>>;
@@ -82,28 +130,14 @@ pub mod extractor {
                     .strip_prefix("Bearer ")
                     .ok_or_else(|| ErrorUnauthorized("malformed Authorization header"))?;
 
-                let secret =
-                    env::var("JWT_SECRET").map_err(|_| ErrorUnauthorized("server mis-config"))?;
-                let data = decode::<Claims>(
-                    token,
-                    &DecodingKey::from_secret(secret.as_bytes()),
-                    &Validation::default(),
-                )
-                .map_err(|_| ErrorUnauthorized("invalid / expired token"))?;
-
-                let user_id =
-                    Uuid::parse_str(&data.claims.sub).map_err(|_| ErrorUnauthorized("bad sub"))?;
-                let player_id =
-                    Uuid::parse_str(&data.claims.pid).map_err(|_| ErrorUnauthorized("bad pid"))?;
-
-                Ok(JwtAuth { user_id, player_id })
+                Self::from_token(token)
             })();
 
             ready(res)
         }
     }
 }
-pub use extractor::JwtAuth; // <-- makes path crate::http::auth::JwtAuth work
+pub use extractor::{JwtAuth, WS_SUBPROTOCOL}; // <-- makes path crate::http::auth::JwtAuth work
 
 //////////////////////////////////////////////////
 // POST /api/magic_link
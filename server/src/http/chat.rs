@@ -7,16 +7,21 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::protocol::ServerMsg;
+use crate::{
+    http::auth::JwtAuth,
+    progression::{self, EventKind},
+    protocol::ServerMsg,
+};
 
 //////////////////////////////////////////////////
 // DTOs
 //////////////////////////////////////////////////
 
+/// Sender is the authenticated player; a body `sender_id` is rejected.
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SendReq {
     pub faction_id: Uuid,
-    pub sender_id: Uuid,
     pub content: String,
 }
 
@@ -32,6 +37,7 @@ pub struct ChatMsgRow {
 //////////////////////////////////////////////////
 #[post("/chat/faction/send")]
 pub async fn send(
+    auth: JwtAuth,
     info: web::Json<SendReq>,
     db: web::Data<PgPool>,
     redis: web::Data<RedisClient>,
@@ -46,7 +52,7 @@ pub async fn send(
              SELECT 1 FROM faction_members
               WHERE faction_id = $1 AND player_id = $2)",
         info.faction_id,
-        info.sender_id
+        auth.player_id
     )
     .fetch_one(&**db)
     .await
@@ -57,21 +63,24 @@ pub async fn send(
     }
 
     // Persist message
-    let _ = sqlx::query!(
+    let saved = sqlx::query!(
         "INSERT INTO chat_messages (faction_id, sender_id, content)
               VALUES ($1,$2,$3)",
         info.faction_id,
-        info.sender_id,
+        auth.player_id,
         info.content
     )
     .execute(&**db)
     .await;
+    if saved.is_ok() {
+        progression::emit(auth.player_id, EventKind::ChatSent);
+    }
 
     // Publish via Redis
     if let Ok(mut conn) = redis.get_multiplexed_async_connection().await {
         let evt = ServerMsg::FactionChat {
             faction_id: info.faction_id,
-            sender_id: info.sender_id,
+            sender_id: auth.player_id,
             content: info.content.clone(),
             ts: Utc::now(),
         };
@@ -0,0 +1,149 @@
+//! Territory contests: declare an attack, respond as defenders, inspect.
+//!
+//! Rules and resolution live in `db::contest_repo` and
+//! `ecosystem::territory`; the `contests` worker settles what falls due.
+//! Both factions are told about each step over their players’ event
+//! channels.
+
+use actix_web::{get, middleware::from_fn, post, web, HttpResponse, Responder};
+use redis::Client as RedisClient;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    contests,
+    db::{
+        contest_repo::{self, ContestStatus, Declaration},
+        faction_repo,
+    },
+    http::{auth::JwtAuth, idempotency::idempotency},
+    protocol::ServerMsg,
+    trading,
+};
+
+/// The defenders’ answer; without a champion the contest is settled by
+/// strength at once.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RespondReq {
+    #[serde(default)]
+    pub champion_id: Option<Uuid>,
+}
+
+/// 500 for database failures, 400 for requests the contest rules refuse.
+fn rejected(e: anyhow::Error) -> HttpResponse {
+    if e.downcast_ref::<sqlx::Error>().is_some() {
+        log::error!("contest request failed: {e:?}");
+        HttpResponse::InternalServerError().finish()
+    } else {
+        HttpResponse::BadRequest().body(e.to_string())
+    }
+}
+
+/// POST /api/contests/declare
+#[post("/contests/declare", wrap = "from_fn(idempotency)")]
+pub async fn declare(
+    auth: JwtAuth,
+    info: web::Json<Declaration>,
+    db: web::Data<PgPool>,
+    redis: web::Data<RedisClient>,
+) -> impl Responder {
+    let contest = match contest_repo::declare(
+        &db,
+        auth.player_id,
+        &info,
+        contests::response_window(),
+    )
+    .await
+    {
+        Ok(c) => c,
+        Err(e) => return rejected(e),
+    };
+    let msg = ServerMsg::ContestDeclared {
+        contest: contest.clone(),
+    };
+    contests::notify_faction(&db, &redis, contest.defender_faction_id, &msg).await;
+    HttpResponse::Ok().json(contest)
+}
+
+/// POST /api/contests/{id}/respond
+#[post("/contests/{id}/respond", wrap = "from_fn(idempotency)")]
+pub async fn respond(
+    auth: JwtAuth,
+    path: web::Path<Uuid>,
+    info: web::Json<RespondReq>,
+    db: web::Data<PgPool>,
+    redis: web::Data<RedisClient>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let contest = match contest_repo::respond(&db, id, auth.player_id, info.champion_id).await {
+        Ok(c) => c,
+        Err(e) => return rejected(e),
+    };
+    match (contest.status, contest.game_id, contest.defender_champion) {
+        (ContestStatus::Dueling, Some(game_id), Some(defender)) => {
+            let attacker = contest.attacker_champion;
+            for (player, opponent_id) in [(attacker, defender), (defender, attacker)] {
+                let msg = ServerMsg::ContestDuel {
+                    contest_id: contest.id,
+                    game_id,
+                    opponent_id,
+                };
+                trading::notify(&redis, player, &msg).await;
+            }
+        }
+        (ContestStatus::Resolved, ..) => contests::notify_resolved(&db, &redis, &contest).await,
+        _ => {}
+    }
+    HttpResponse::Ok().json(contest)
+}
+
+/// GET /api/contests/{id} — members of either side only.
+#[get("/contests/{id}")]
+pub async fn get_contest(
+    auth: JwtAuth,
+    path: web::Path<Uuid>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    let c = match contest_repo::get(&db, path.into_inner()).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    for faction in [c.attacker_faction_id, c.defender_faction_id] {
+        match faction_repo::is_faction_member(&db, faction, auth.player_id).await {
+            Ok(true) => return HttpResponse::Ok().json(c),
+            Ok(false) => {}
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        }
+    }
+    HttpResponse::Forbidden().body("not in either faction")
+}
+
+/// GET /api/contests/faction/{faction_id} — unresolved contests the
+/// faction attacks or defends; members only.
+#[get("/contests/faction/{faction_id}")]
+pub async fn for_faction(
+    auth: JwtAuth,
+    path: web::Path<Uuid>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    let faction = path.into_inner();
+    match faction_repo::is_faction_member(&db, faction, auth.player_id).await {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::Forbidden().body("not in faction"),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    }
+    match contest_repo::live_for(&db, faction).await {
+        Ok(list) => HttpResponse::Ok().json(list),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(declare)
+        .service(respond)
+        .service(for_faction)
+        .service(get_contest);
+}
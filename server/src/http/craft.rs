@@ -0,0 +1,86 @@
+//! Crafting: recipe list, start a job, job status and claim.
+//!
+//! Inputs are consumed on start and outputs handed over on claim, each in
+//! one transaction (see `db::craft_repo`). Long jobs are finished by the
+//! `crafting` worker.
+
+use actix_web::{get, middleware::from_fn, post, web, HttpResponse, Responder};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    db::craft_repo,
+    http::{auth::JwtAuth, idempotency::idempotency},
+    progression::{self, EventKind},
+};
+
+/// The crafter is the authenticated player; a body `player_id` is rejected.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StartReq {
+    pub recipe_id: i32,
+    /// Structure to craft at, for recipes that need one.
+    #[serde(default)]
+    pub structure_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClaimReq {
+    pub job_id: Uuid,
+}
+
+/// GET /api/craft/recipes
+#[get("/craft/recipes")]
+pub async fn recipes(db: web::Data<PgPool>) -> impl Responder {
+    match craft_repo::list_recipes(&db).await {
+        Ok(list) => HttpResponse::Ok().json(list),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// POST /api/craft/start
+#[post("/craft/start", wrap = "from_fn(idempotency)")]
+pub async fn start(
+    auth: JwtAuth,
+    info: web::Json<StartReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    match craft_repo::start(&db, auth.player_id, info.recipe_id, info.structure_id).await {
+        Ok(job) => HttpResponse::Ok().json(job),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+/// GET /api/craft/status — the caller’s unclaimed jobs.
+#[get("/craft/status")]
+pub async fn status(auth: JwtAuth, db: web::Data<PgPool>) -> impl Responder {
+    match craft_repo::status(&db, auth.player_id).await {
+        Ok(jobs) => HttpResponse::Ok().json(jobs),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// POST /api/craft/claim
+#[post("/craft/claim", wrap = "from_fn(idempotency)")]
+pub async fn claim(
+    auth: JwtAuth,
+    info: web::Json<ClaimReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    match craft_repo::claim(&db, auth.player_id, info.job_id).await {
+        Ok(claimed) => {
+            progression::emit(auth.player_id, EventKind::ItemCrafted);
+            HttpResponse::Ok().json(claimed)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(recipes)
+        .service(start)
+        .service(status)
+        .service(claim);
+}
@@ -1,11 +1,15 @@
-//! Faction management (create / join / leave / list / promote / demote / info)
+//! Faction management (create / join / leave / list / promote / demote / info /
+//! treasury)
 
 use actix_web::{get, post, web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::db::faction_repo;
+use crate::{
+    db::{faction_repo, treasury_repo},
+    http::auth::JwtAuth,
+};
 
 //////////////////////////////////////////////////
 // Data transfer objects
@@ -39,50 +43,61 @@ pub struct FactionInfo {
 }
 
 //////////////////////////////////////////////////
-// Requests
+// Requests — the acting player always comes from the JWT; bodies that
+// still carry an actor id are rejected (`deny_unknown_fields`).
 //////////////////////////////////////////////////
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateReq {
     pub name: String,
     #[serde(default)]
     pub description: String,
-    pub founder_id: Uuid,
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct JoinReq {
     pub faction_id: Uuid,
-    pub player_id: Uuid,
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct LeaveReq {
     pub faction_id: Uuid,
-    pub player_id: Uuid,
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct PromoteReq {
     pub faction_id: Uuid,
-    pub actor_id: Uuid,
     pub target_id: Uuid,
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DemoteReq {
     pub faction_id: Uuid,
-    pub actor_id: Uuid,
     pub target_id: Uuid,
 }
 
+#[derive(Deserialize)]
+pub struct TreasuryParams {
+    /// World ticks of income to return (default 20, max 100).
+    pub ticks: Option<i64>,
+}
+
 //////////////////////////////////////////////////
 // Handlers
 //////////////////////////////////////////////////
 
 /// POST /api/factions/create
 #[post("/factions/create")]
-pub async fn create(info: web::Json<CreateReq>, db: web::Data<PgPool>) -> impl Responder {
+pub async fn create(
+    auth: JwtAuth,
+    info: web::Json<CreateReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
     let mut tx = match db.begin().await {
         Ok(t) => t,
         Err(_) => return HttpResponse::InternalServerError().finish(),
@@ -109,7 +124,7 @@ pub async fn create(info: web::Json<CreateReq>, db: web::Data<PgPool>) -> impl R
         "INSERT INTO faction_members (faction_id, player_id, role)
          VALUES ($1, $2, 'leader')",
         fid,
-        info.founder_id,
+        auth.player_id,
     )
     .execute(&mut *tx)
     .await;
@@ -205,13 +220,17 @@ pub async fn faction_of(path: web::Path<Uuid>, db: web::Data<PgPool>) -> impl Re
 
 /// POST /api/factions/join
 #[post("/factions/join")]
-pub async fn join(info: web::Json<JoinReq>, db: web::Data<PgPool>) -> impl Responder {
+pub async fn join(
+    auth: JwtAuth,
+    info: web::Json<JoinReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
     match sqlx::query!(
         r#"INSERT INTO faction_members (faction_id, player_id, role)
             VALUES ($1,$2,'member')
             ON CONFLICT DO NOTHING"#,
         info.faction_id,
-        info.player_id
+        auth.player_id
     )
     .execute(&**db)
     .await
@@ -223,12 +242,16 @@ pub async fn join(info: web::Json<JoinReq>, db: web::Data<PgPool>) -> impl Respo
 
 /// POST /api/factions/leave
 #[post("/factions/leave")]
-pub async fn leave(info: web::Json<LeaveReq>, db: web::Data<PgPool>) -> impl Responder {
+pub async fn leave(
+    auth: JwtAuth,
+    info: web::Json<LeaveReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
     let rows = sqlx::query!(
         "DELETE FROM faction_members
          WHERE faction_id = $1 AND player_id = $2",
         info.faction_id,
-        info.player_id
+        auth.player_id
     )
     .execute(&**db)
     .await
@@ -244,9 +267,18 @@ pub async fn leave(info: web::Json<LeaveReq>, db: web::Data<PgPool>) -> impl Res
 
 /// POST /api/factions/promote
 #[post("/factions/promote")]
-pub async fn promote(info: web::Json<PromoteReq>, db: web::Data<PgPool>) -> impl Responder {
-    match faction_repo::promote_member(db.get_ref(), info.faction_id, info.actor_id, info.target_id)
-        .await
+pub async fn promote(
+    auth: JwtAuth,
+    info: web::Json<PromoteReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    match faction_repo::promote_member(
+        db.get_ref(),
+        info.faction_id,
+        auth.player_id,
+        info.target_id,
+    )
+    .await
     {
         Ok(_) => HttpResponse::Ok().body("promoted"),
         Err(e) => {
@@ -258,9 +290,18 @@ pub async fn promote(info: web::Json<PromoteReq>, db: web::Data<PgPool>) -> impl
 
 /// POST /api/factions/demote
 #[post("/factions/demote")]
-pub async fn demote(info: web::Json<DemoteReq>, db: web::Data<PgPool>) -> impl Responder {
-    match faction_repo::demote_member(db.get_ref(), info.faction_id, info.actor_id, info.target_id)
-        .await
+pub async fn demote(
+    auth: JwtAuth,
+    info: web::Json<DemoteReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    match faction_repo::demote_member(
+        db.get_ref(),
+        info.faction_id,
+        auth.player_id,
+        info.target_id,
+    )
+    .await
     {
         Ok(_) => HttpResponse::Ok().body("demoted"),
         Err(e) => {
@@ -270,6 +311,27 @@ pub async fn demote(info: web::Json<DemoteReq>, db: web::Data<PgPool>) -> impl R
     }
 }
 
+/// GET /api/factions/{id}/treasury — members only.
+#[get("/factions/{id}/treasury")]
+pub async fn treasury(
+    auth: JwtAuth,
+    path: web::Path<Uuid>,
+    web::Query(params): web::Query<TreasuryParams>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    let faction = path.into_inner();
+    match faction_repo::is_faction_member(&db, faction, auth.player_id).await {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::Forbidden().body("not in faction"),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    }
+    let ticks = params.ticks.unwrap_or(20).clamp(1, 100);
+    match treasury_repo::get(&db, faction, ticks).await {
+        Ok(t) => HttpResponse::Ok().json(t),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
 //////////////////////////////////////////////////
 // Mount
 //////////////////////////////////////////////////
@@ -281,6 +343,7 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
         .service(promote)
         .service(demote)
         .service(faction_of)
+        .service(treasury)
         .service(invite)
         .service(accept)
         .service(kick);
@@ -288,32 +351,36 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
 
 // ---------- Requests ----------
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct InviteReq {
     pub faction_id: Uuid,
-    pub inviter_id: Uuid,
     pub target_player_id: Uuid,
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct AcceptReq {
     pub invite_id: Uuid,
-    pub player_id: Uuid,
 }
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct KickReq {
     pub faction_id: Uuid,
-    pub actor_id: Uuid,
     pub target_id: Uuid,
 }
 
 // ---------- Invite ----------
 #[post("/factions/invite")]
-pub async fn invite(info: web::Json<InviteReq>, db: web::Data<PgPool>) -> impl Responder {
+pub async fn invite(
+    auth: JwtAuth,
+    info: web::Json<InviteReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
     match faction_repo::create_invite(
         db.get_ref(),
         info.faction_id,
-        info.inviter_id,
+        auth.player_id,
         info.target_player_id,
     )
     .await
@@ -325,8 +392,12 @@ pub async fn invite(info: web::Json<InviteReq>, db: web::Data<PgPool>) -> impl R
 
 // ---------- Accept ----------
 #[post("/factions/invites/accept")]
-pub async fn accept(info: web::Json<AcceptReq>, db: web::Data<PgPool>) -> impl Responder {
-    match faction_repo::accept_invite(db.get_ref(), info.invite_id, info.player_id).await {
+pub async fn accept(
+    auth: JwtAuth,
+    info: web::Json<AcceptReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    match faction_repo::accept_invite(db.get_ref(), info.invite_id, auth.player_id).await {
         Ok(_) => HttpResponse::Ok().body("joined"),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
@@ -334,9 +405,18 @@ pub async fn accept(info: web::Json<AcceptReq>, db: web::Data<PgPool>) -> impl R
 
 // ---------- Kick ----------
 #[post("/factions/kick")]
-pub async fn kick(info: web::Json<KickReq>, db: web::Data<PgPool>) -> impl Responder {
-    match faction_repo::kick_member(db.get_ref(), info.faction_id, info.actor_id, info.target_id)
-        .await
+pub async fn kick(
+    auth: JwtAuth,
+    info: web::Json<KickReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    match faction_repo::kick_member(
+        db.get_ref(),
+        info.faction_id,
+        auth.player_id,
+        info.target_id,
+    )
+    .await
     {
         Ok(_) => HttpResponse::Ok().body("kicked"),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
@@ -12,6 +12,9 @@ pub struct GameSummary {
     pub winner_id: Option<Uuid>,
     pub player_elo_delta: i32,
     pub opponent_elo_delta: i32,
+    pub mode: String,
+    /// Rule that decided the game; `None` for draws and unfinished rows.
+    pub victory_rule: Option<String>,
     pub finished_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -40,6 +43,8 @@ pub async fn history(path: web::Path<Uuid>, db: web::Data<PgPool>) -> impl Respo
                 WHEN g.player1_id = $1 THEN g.player2_elo_delta
                 ELSE g.player1_elo_delta
             END                                     AS "opponent_elo_delta!",
+            g.mode                                  AS "mode",
+            g.victory_rule                          AS "victory_rule",
             g.updated_at                            AS "finished_at!"
         FROM games g
         WHERE g.player1_id = $1 OR g.player2_id = $1
@@ -0,0 +1,222 @@
+//! `Idempotency-Key` support for endpoints that must not run twice
+//! (purchases, trades, market orders, crafting, quest rewards, structure
+//! builds, land claims, territory contests, sponsored transactions).
+//!
+//! Wrap a route with `wrap = "from_fn(idempotency)"`. The
+//! first request with a given key (per player) runs normally and its status
+//! and body are kept for [`TTL_HOURS`]; a retry with the same key and body
+//! gets the stored response back with `Idempotent-Replayed: true`. Reusing a
+//! key for a different request is a 422, and a retry that arrives while the
+//! first attempt is still running is a 409. Requests without the header, or
+//! without a valid token, pass straight through.
+
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::{ErrorBadRequest, ErrorInternalServerError},
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::Next,
+    web, Error, HttpResponse,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::http::auth::JwtAuth;
+
+pub const HEADER: &str = "Idempotency-Key";
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// How long a stored response can be replayed.
+pub const TTL_HOURS: i32 = 24;
+
+const MAX_KEY_LEN: usize = 255;
+
+enum Claim {
+    /// First time this key is seen: run the handler.
+    Fresh,
+    /// Key already used for a different request.
+    Mismatch,
+    /// First request with this key has not finished yet.
+    InFlight,
+    /// Finished earlier: replay it.
+    Done {
+        status: i16,
+        content_type: Option<String>,
+        body: Vec<u8>,
+    },
+}
+
+pub async fn idempotency(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(key) = req
+        .headers()
+        .get(HEADER)
+        .map(|v| v.to_str().map(str::to_owned))
+    else {
+        return next.call(req).await.map(|r| r.map_into_boxed_body());
+    };
+    let key = key.map_err(|_| ErrorBadRequest("invalid Idempotency-Key"))?;
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(ErrorBadRequest("Idempotency-Key must be 1-255 characters"));
+    }
+    let (Some(db), Ok(auth)) = (
+        req.app_data::<web::Data<PgPool>>().cloned(),
+        req.extract::<JwtAuth>().await,
+    ) else {
+        // Unauthenticated: let the handler reject it.
+        return next.call(req).await.map(|r| r.map_into_boxed_body());
+    };
+
+    // Buffer the body so it can be hashed, then hand it back to the handler.
+    let bytes = req.extract::<web::Bytes>().await?;
+    let mut fingerprint = format!("{} {}\n", req.method(), req.path()).into_bytes();
+    fingerprint.extend_from_slice(&bytes);
+    req.set_payload(Payload::from(bytes));
+
+    let player = auth.player_id;
+    match claim(&db, player, &key, &fingerprint)
+        .await
+        .map_err(ErrorInternalServerError)?
+    {
+        Claim::Fresh => {}
+        Claim::Mismatch => {
+            return Ok(req.into_response(
+                HttpResponse::UnprocessableEntity()
+                    .body("Idempotency-Key was already used for a different request"),
+            ))
+        }
+        Claim::InFlight => {
+            return Ok(req.into_response(
+                HttpResponse::Conflict().body("a request with this Idempotency-Key is in progress"),
+            ))
+        }
+        Claim::Done {
+            status,
+            content_type,
+            body,
+        } => {
+            let status = StatusCode::from_u16(status as u16).map_err(ErrorInternalServerError)?;
+            let mut res = HttpResponse::build(status);
+            res.insert_header((REPLAYED_HEADER, "true"));
+            if let Some(ct) = content_type {
+                res.insert_header((CONTENT_TYPE, ct));
+            }
+            return Ok(req.into_response(res.body(body)));
+        }
+    }
+
+    let res = match next.call(req).await {
+        Ok(res) => res,
+        Err(e) => {
+            release(&db, player, &key).await;
+            return Err(e);
+        }
+    };
+    // Server errors are not final: free the key so the client can retry.
+    if res.status().is_server_error() {
+        release(&db, player, &key).await;
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let status = res.status();
+    let content_type = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    let (res, res_body) = res.into_parts();
+    let bytes = match body::to_bytes(res_body).await {
+        Ok(b) => b,
+        Err(e) => {
+            release(&db, player, &key).await;
+            return Err(ErrorInternalServerError(e.into().to_string()));
+        }
+    };
+
+    sqlx::query!(
+        "UPDATE idempotency_keys
+            SET status = $3, content_type = $4, response_body = $5
+          WHERE player_id = $1 AND key = $2",
+        player,
+        key,
+        status.as_u16() as i16,
+        content_type,
+        bytes.as_ref()
+    )
+    .execute(db.get_ref())
+    .await
+    .map_err(ErrorInternalServerError)?;
+
+    Ok(ServiceResponse::new(
+        req,
+        res.set_body(bytes).map_into_boxed_body(),
+    ))
+}
+
+/// Reserve `key` for this request, or report what it was used for.
+async fn claim(db: &PgPool, player: Uuid, key: &str, fingerprint: &[u8]) -> sqlx::Result<Claim> {
+    sqlx::query!(
+        "DELETE FROM idempotency_keys WHERE player_id = $1 AND expires_at <= NOW()",
+        player
+    )
+    .execute(db)
+    .await?;
+
+    let inserted = sqlx::query_scalar!(
+        "INSERT INTO idempotency_keys (player_id, key, request_hash, expires_at)
+         VALUES ($1, $2, encode(sha256($3), 'hex'), NOW() + make_interval(hours => $4))
+         ON CONFLICT DO NOTHING
+         RETURNING key",
+        player,
+        key,
+        fingerprint,
+        TTL_HOURS
+    )
+    .fetch_optional(db)
+    .await?;
+    if inserted.is_some() {
+        return Ok(Claim::Fresh);
+    }
+
+    let row = sqlx::query!(
+        r#"SELECT request_hash = encode(sha256($3), 'hex') AS "same!",
+                  status, content_type, response_body
+             FROM idempotency_keys
+            WHERE player_id = $1 AND key = $2"#,
+        player,
+        key,
+        fingerprint
+    )
+    .fetch_optional(db)
+    .await?;
+    Ok(match row {
+        // Released between our insert and select: treat as in flight.
+        None => Claim::InFlight,
+        Some(r) if !r.same => Claim::Mismatch,
+        Some(r) => match r.status {
+            None => Claim::InFlight,
+            Some(status) => Claim::Done {
+                status,
+                content_type: r.content_type,
+                body: r.response_body.unwrap_or_default(),
+            },
+        },
+    })
+}
+
+/// Drop an unfinished reservation so the key can be retried.
+async fn release(db: &PgPool, player: Uuid, key: &str) {
+    if let Err(e) = sqlx::query!(
+        "DELETE FROM idempotency_keys WHERE player_id = $1 AND key = $2 AND status IS NULL",
+        player,
+        key
+    )
+    .execute(db)
+    .await
+    {
+        log::error!("failed to release idempotency key: {e:?}");
+    }
+}
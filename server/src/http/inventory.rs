@@ -1,18 +1,46 @@
 //! Inventory endpoints (+ dev-only starter-loot helper).
 
-use actix_web::{error, get, post, web, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::http::auth::JwtAuth;
+use crate::{
+    cache::ItemMeta,
+    effects,
+    http::{
+        auth::JwtAuth,
+        items::{ItemQuery, Listing},
+    },
+};
 
 #[derive(Serialize)]
 pub struct InventoryEntry {
     item_id: i32,
     name: String,
     quantity: i32,
+    #[serde(skip)]
+    base_price: i32,
+    #[serde(flatten)]
+    meta: ItemMeta,
+}
+
+impl Listing for InventoryEntry {
+    fn item_id(&self) -> i32 {
+        self.item_id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn meta(&self) -> &ItemMeta {
+        &self.meta
+    }
+    fn price(&self) -> i64 {
+        self.base_price as i64
+    }
+    fn quantity(&self) -> i32 {
+        self.quantity
+    }
 }
 
 #[derive(Deserialize)]
@@ -20,11 +48,18 @@ pub struct UseReq {
     pub item_id: i32,
     #[serde(default)]
     pub quantity: i32,
+    /// Structure id for effects that need a target.
+    #[serde(default)]
+    pub target_id: Option<i32>,
 }
 
-/// GET /api/inventory/{player_id}
+/// GET /api/inventory/{player_id} — filter / sort with [`ItemQuery`].
 #[get("/inventory/{player_id}")]
-pub async fn get_inventory(path: web::Path<Uuid>, db: web::Data<PgPool>) -> impl Responder {
+pub async fn get_inventory(
+    path: web::Path<Uuid>,
+    web::Query(query): web::Query<ItemQuery>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
     let pid = path.into_inner();
 
     let rows = sqlx::query!(
@@ -32,6 +67,8 @@ pub async fn get_inventory(path: web::Path<Uuid>, db: web::Data<PgPool>) -> impl
         SELECT
             i.id   AS item_id,
             i.name AS name,
+            i.base_price, i.category, i.rarity, i.max_stack,
+            i.tradable, i.soulbound, i.icon_key,
             COALESCE(pi.quantity, 0) AS quantity
         FROM items i
         LEFT JOIN player_items pi
@@ -51,56 +88,44 @@ pub async fn get_inventory(path: web::Path<Uuid>, db: web::Data<PgPool>) -> impl
         item_id:  r.item_id,
         name:     r.name,
         quantity: r.quantity.unwrap_or(0),
+        base_price: r.base_price,
+        meta: ItemMeta::from_row(
+            &r.category,
+            &r.rarity,
+            r.max_stack,
+            r.tradable,
+            r.soulbound,
+            r.icon_key,
+        ),
     })
     .collect();
 
-    HttpResponse::Ok().json(out)
+    HttpResponse::Ok().json(query.apply(out))
 }
 
-/// POST /api/inventory/use
+/// POST /api/inventory/use — consume items and apply their effects.
+/// Duel-only items are refused here; they are used via `TurnAction::UseItem`.
 #[post("/inventory/use")]
 pub async fn use_item(
     auth: JwtAuth,
     info: web::Json<UseReq>,
     db: web::Data<PgPool>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let pid = auth.player_id;
-    let iid = info.item_id;
+) -> impl Responder {
     let qty = info.quantity.max(1);
-
-    // try to decrement; fail if not enough
-    let res = sqlx::query!(
-        r#"UPDATE player_items
-              SET quantity = quantity - $3
-            WHERE player_id = $1 AND item_id = $2 AND quantity >= $3
-        RETURNING quantity"#,
-        pid,
-        iid,
-        qty
-    )
-    .fetch_optional(&**db)
-    .await
-    .map_err(error::ErrorInternalServerError)?;
-
-    let resp = match res {
-        Some(r) => HttpResponse::Ok().json(json!({ "remaining": r.quantity })),
-        None => HttpResponse::BadRequest().body("not enough items"),
-    };
-
-    Ok(resp)
+    match effects::use_item(&db, auth.player_id, info.item_id, qty, info.target_id).await {
+        Ok(outcome) => HttpResponse::Ok().json(outcome),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
 }
 
 /// POST /api/inventory/grant_starter   (debug builds only)
 #[cfg(debug_assertions)]
 #[post("/inventory/grant_starter")]
-pub async fn grant_starter(
-    info: web::Json<Uuid>, // player_id
-    db: web::Data<PgPool>,
-) -> impl Responder {
+pub async fn grant_starter(auth: JwtAuth, db: web::Data<PgPool>) -> impl Responder {
     use rand::rng;
     use rand::seq::SliceRandom;
 
-    let pid = *info;
+    let pid = auth.player_id;
 
     // Grab every item ID from the catalogue
     let mut item_ids: Vec<i32> = sqlx::query_scalar!("SELECT id FROM items")
@@ -1,9 +1,91 @@
-//! Static item catalogue.
+//! Static item catalogue, plus the filter / sort query shared by every
+//! item listing (`/items`, `/shop/items`, `/inventory/{player_id}`).
 
-use crate::cache::{ItemDef, ITEMS};
+use crate::{
+    cache::{ItemCategory, ItemDef, ItemMeta, Rarity, ITEMS},
+    effects::{self, Effect},
+};
 use actix_web::{get, web, HttpResponse, Responder};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::cmp::Ordering;
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    #[default]
+    Id,
+    Name,
+    Price,
+    Rarity,
+    Category,
+    /// Inventory only; other listings treat every quantity as 0.
+    Quantity,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// `?category=&rarity=&tradable=&soulbound=&sort=&order=`
+#[derive(Debug, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ItemQuery {
+    pub category: Option<ItemCategory>,
+    pub rarity: Option<Rarity>,
+    pub tradable: Option<bool>,
+    pub soulbound: Option<bool>,
+    #[serde(default)]
+    pub sort: SortKey,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// A row in an item listing that [`ItemQuery`] can filter and sort.
+pub trait Listing {
+    fn item_id(&self) -> i32;
+    fn name(&self) -> &str;
+    fn meta(&self) -> &ItemMeta;
+    /// Price shown in this listing (base price, or current shop price).
+    fn price(&self) -> i64;
+    fn quantity(&self) -> i32 {
+        0
+    }
+}
+
+impl ItemQuery {
+    pub fn matches(&self, m: &ItemMeta) -> bool {
+        self.category.is_none_or(|c| c == m.category)
+            && self.rarity.is_none_or(|r| r == m.rarity)
+            && self.tradable.is_none_or(|t| t == m.tradable)
+            && self.soulbound.is_none_or(|s| s == m.soulbound)
+    }
+
+    /// Drop rows that do not match, then sort; ties fall back to item id.
+    pub fn apply<T: Listing>(&self, mut rows: Vec<T>) -> Vec<T> {
+        rows.retain(|r| self.matches(r.meta()));
+        rows.sort_by(|a, b| {
+            let by_key = match self.sort {
+                SortKey::Id => Ordering::Equal,
+                SortKey::Name => a.name().cmp(b.name()),
+                SortKey::Price => a.price().cmp(&b.price()),
+                SortKey::Rarity => a.meta().rarity.cmp(&b.meta().rarity),
+                SortKey::Category => a.meta().category.cmp(&b.meta().category),
+                SortKey::Quantity => a.quantity().cmp(&b.quantity()),
+            }
+            .then_with(|| a.item_id().cmp(&b.item_id()));
+            match self.order {
+                SortOrder::Asc => by_key,
+                SortOrder::Desc => by_key.reverse(),
+            }
+        });
+        rows
+    }
+}
 
 #[derive(Serialize)]
 struct Item {
@@ -11,20 +93,45 @@ struct Item {
     name: String,
     description: Option<String>,
     base_price: i32,
+    effects: Vec<Effect>,
+    #[serde(flatten)]
+    meta: ItemMeta,
+}
+
+impl Listing for ItemDef {
+    fn item_id(&self) -> i32 {
+        self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn meta(&self) -> &ItemMeta {
+        &self.meta
+    }
+    fn price(&self) -> i64 {
+        self.base_price as i64
+    }
 }
 
+/// GET /api/items
 #[get("/items")]
-pub async fn list_items(db: web::Data<PgPool>) -> impl Responder {
+pub async fn list_items(
+    web::Query(query): web::Query<ItemQuery>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
     // Use in-memory cache if warmed; otherwise fall back to DB
     let defs: Vec<ItemDef> = if !ITEMS.is_empty() {
         ITEMS.iter().map(|e| e.value().clone()).collect()
     } else {
         // Rare fallback path before warm-up completes
-        let rows =
-            sqlx::query!(r#"SELECT id, name, description, base_price FROM items ORDER BY id"#)
-                .fetch_all(&**db)
-                .await
-                .unwrap_or_default();
+        let rows = sqlx::query!(
+            r#"SELECT id, name, description, base_price, effects,
+                      category, rarity, max_stack, tradable, soulbound, icon_key
+                 FROM items ORDER BY id"#
+        )
+        .fetch_all(&**db)
+        .await
+        .unwrap_or_default();
 
         rows.into_iter()
             .map(|r| ItemDef {
@@ -32,18 +139,30 @@ pub async fn list_items(db: web::Data<PgPool>) -> impl Responder {
                 name: r.name,
                 description: r.description,
                 base_price: r.base_price,
+                effects: effects::parse(r.effects).unwrap_or_default(),
+                meta: ItemMeta::from_row(
+                    &r.category,
+                    &r.rarity,
+                    r.max_stack,
+                    r.tradable,
+                    r.soulbound,
+                    r.icon_key,
+                ),
             })
             .collect()
     };
 
     // Map to the HTTP DTO
-    let out: Vec<Item> = defs
+    let out: Vec<Item> = query
+        .apply(defs)
         .into_iter()
         .map(|it| Item {
             id: it.id,
             name: it.name,
             description: it.description,
             base_price: it.base_price,
+            effects: it.effects,
+            meta: it.meta,
         })
         .collect();
 
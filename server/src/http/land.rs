@@ -1,11 +1,37 @@
-//! Land‐parcel endpoints: claim, inspect, and list owned parcels.
+//! Land‐parcel endpoints: claim, inspect, list owned parcels and view a map
+//! region.
+//!
+//! The map is generated from `WORLD_SEED` (see `ecosystem::worldgen`):
+//! claims off the map are refused and the biome always comes from the
+//! generator. Expansion rules and claim costs live in `ecosystem::territory`;
+//! parcels taken in contests (see `http::contests`) show up in a parcel’s
+//! history.
 
-use actix_web::{get, post, web, Error, HttpResponse};
+use std::collections::HashMap;
+
+use actix_web::{get, middleware::from_fn, post, web, Error, HttpResponse};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::{
+    db::{
+        faction_repo,
+        land_repo::{self, ClaimOutcome},
+    },
+    ecosystem::{
+        model::{Biome, ParcelState},
+        territory::PayWith,
+        worldgen::WorldGen,
+    },
+    http::{auth::JwtAuth, idempotency::idempotency},
+    progression::{self, EventKind},
+};
+
+/// Most tiles one region request may cover.
+const MAX_REGION_TILES: i64 = 64 * 64;
+
 #[derive(Serialize)]
 pub struct LandParcel {
     pub id: i32,
@@ -24,53 +50,81 @@ struct OwnedParcel {
     pub owner_faction_id: Option<Uuid>,
 }
 
+/// One tile of a region: the generated terrain plus the parcel, if the
+/// tile has been claimed.
+#[derive(Serialize)]
+pub struct RegionTile {
+    pub x: i32,
+    pub y: i32,
+    pub biome: Biome,
+    pub rich: bool,
+    pub parcel_id: Option<i32>,
+    pub owner_faction_id: Option<Uuid>,
+    pub state: Option<ParcelState>,
+}
+
+/// Claims on behalf of `faction_id`; the authenticated player must be one of
+/// its officers or its leader.
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ClaimReq {
     pub faction_id: Uuid,
     pub x: i32,
     pub y: i32,
-    pub biome_type: String,
+    /// Ignored; the world generator decides the biome.
+    #[serde(default)]
+    pub biome_type: Option<serde::de::IgnoredAny>,
+    /// Who pays for claims after the first (default: the treasury).
+    #[serde(default)]
+    pub pay_with: PayWith,
 }
 
-#[post("/land/claim")]
+/// Inclusive rectangle; corners may be given in either order.
+#[derive(Deserialize)]
+pub struct RegionParams {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32,
+}
+
+#[post("/land/claim", wrap = "from_fn(idempotency)")]
 pub async fn claim(
+    auth: JwtAuth,
     info: web::Json<ClaimReq>,
     db: web::Data<PgPool>,
 ) -> Result<HttpResponse, Error> {
-    // 1) Check for existing parcel at (x,y)
-    let existing = sqlx::query_as::<_, (i32, Option<Uuid>)>(
-        "SELECT id, owner_faction_id FROM land_parcels WHERE x = $1 AND y = $2",
-    )
-    .bind(info.x)
-    .bind(info.y)
-    .fetch_optional(&**db)
-    .await
-    .map_err(actix_web::error::ErrorInternalServerError)?;
-
-    if let Some((id, owner)) = existing {
-        if owner == Some(info.faction_id) {
-            // Already owned by this faction
-            return Ok(HttpResponse::Ok().json(serde_json::json!({ "parcel_id": id })));
-        } else {
-            return Ok(HttpResponse::Conflict().body("parcel already owned"));
-        }
+    // 0) Only officers and leaders may claim for their faction
+    let role = faction_repo::member_role(db.get_ref(), info.faction_id, auth.player_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if !matches!(role.as_deref(), Some("leader" | "officer")) {
+        return Err(actix_web::error::ErrorForbidden(
+            "only officers and leaders may claim",
+        ));
     }
 
-    // 2) Insert new parcel
-    let id: i32 = sqlx::query_scalar!(
-        r#"INSERT INTO land_parcels (biome_type, owner_faction_id, x, y)
-           VALUES ($1, $2, $3, $4)
-           RETURNING id"#,
-        info.biome_type,
-        info.faction_id,
-        info.x,
-        info.y
-    )
-    .fetch_one(&**db)
-    .await
-    .map_err(actix_web::error::ErrorInternalServerError)?;
+    // 1) Only tiles on the generated map exist
+    let Some(tile) = WorldGen::from_settings().tile(info.x, info.y) else {
+        return Ok(HttpResponse::BadRequest().body("off the map"));
+    };
+
+    // 2) Border, cost and payment checks happen in one transaction
+    let outcome =
+        match land_repo::claim(&db, &tile, info.faction_id, auth.player_id, info.pay_with).await {
+            Ok(o) => o,
+            Err(e) => return Ok(HttpResponse::BadRequest().body(e.to_string())),
+        };
+    let (id, paid) = match outcome {
+        ClaimOutcome::Claimed { parcel_id, paid } => (parcel_id, paid),
+        ClaimOutcome::AlreadyOwned { parcel_id } => {
+            return Ok(HttpResponse::Ok().json(serde_json::json!({ "parcel_id": parcel_id })));
+        }
+        ClaimOutcome::Taken => return Ok(HttpResponse::Conflict().body("parcel already owned")),
+    };
+    progression::emit(auth.player_id, EventKind::LandClaimed);
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({ "parcel_id": id })))
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "parcel_id": id, "paid": paid })))
 }
 
 #[get("/land/at/{x}/{y}")]
@@ -97,6 +151,20 @@ pub async fn parcel_at(
     }
 }
 
+/// GET /api/land/at/{x}/{y}/history — claims and contest transfers of
+/// the parcel, oldest first.
+#[get("/land/at/{x}/{y}/history")]
+pub async fn history(
+    path: web::Path<(i32, i32)>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, Error> {
+    let (x, y) = path.into_inner();
+    let changes = land_repo::history(&db, x, y)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(changes))
+}
+
 #[get("/land/owned/{player_id}")]
 pub async fn owned(path: web::Path<Uuid>, db: web::Data<PgPool>) -> Result<HttpResponse, Error> {
     let pid = path.into_inner();
@@ -125,6 +193,70 @@ pub async fn owned(path: web::Path<Uuid>, db: web::Data<PgPool>) -> Result<HttpR
     Ok(HttpResponse::Ok().json(rows))
 }
 
+/// GET /api/land/region?x0&y0&x1&y1 — every map tile in the rectangle,
+/// clipped to the map, at most 64×64 tiles.
+#[get("/land/region")]
+pub async fn region(
+    web::Query(q): web::Query<RegionParams>,
+    db: web::Data<PgPool>,
+) -> Result<HttpResponse, Error> {
+    let world = WorldGen::from_settings();
+    let (x0, x1) = (q.x0.min(q.x1).max(0), q.x0.max(q.x1).min(world.width - 1));
+    let (y0, y1) = (q.y0.min(q.y1).max(0), q.y0.max(q.y1).min(world.height - 1));
+    if x0 > x1 || y0 > y1 {
+        return Ok(HttpResponse::Ok().json(Vec::<RegionTile>::new()));
+    }
+    let area = (x1 - x0 + 1) as i64 * (y1 - y0 + 1) as i64;
+    if area > MAX_REGION_TILES {
+        return Ok(HttpResponse::BadRequest().body(format!(
+            "region too large ({area} tiles, max {MAX_REGION_TILES})"
+        )));
+    }
+
+    let mut parcels: HashMap<(i32, i32), land_repo::RegionParcel> =
+        land_repo::parcels_in(&db, (x0, y0), (x1, y1))
+            .await
+            .map_err(actix_web::error::ErrorInternalServerError)?
+            .into_iter()
+            .map(|p| ((p.x, p.y), p))
+            .collect();
+
+    let mut tiles = Vec::with_capacity(area as usize);
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let Some(tile) = world.tile(x, y) else {
+                continue;
+            };
+            tiles.push(match parcels.remove(&(x, y)) {
+                Some(p) => RegionTile {
+                    x,
+                    y,
+                    biome: Biome::parse(&p.biome_type),
+                    rich: p.rich,
+                    parcel_id: Some(p.id),
+                    owner_faction_id: p.owner_faction_id,
+                    state: Some(p.state),
+                },
+                None => RegionTile {
+                    x,
+                    y,
+                    biome: tile.biome,
+                    rich: tile.rich,
+                    parcel_id: None,
+                    owner_faction_id: None,
+                    state: None,
+                },
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(tiles))
+}
+
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(claim).service(parcel_at).service(owned);
+    cfg.service(claim)
+        .service(parcel_at)
+        .service(history)
+        .service(owned)
+        .service(region);
 }
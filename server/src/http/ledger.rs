@@ -0,0 +1,39 @@
+//! Read-only view of a player’s credit ledger.
+
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{db::ledger_repo, http::auth::JwtAuth};
+
+#[derive(Deserialize)]
+pub struct LedgerParams {
+    /// Entries per page (default 50, max 200).
+    pub limit: Option<i64>,
+    /// Return entries with an id below this one (next page).
+    pub before: Option<i64>,
+}
+
+/// GET /api/players/{id}/ledger — own ledger only.
+#[get("/players/{id}/ledger")]
+pub async fn ledger(
+    auth: JwtAuth,
+    path: web::Path<Uuid>,
+    web::Query(params): web::Query<LedgerParams>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    let player = path.into_inner();
+    if player != auth.player_id {
+        return HttpResponse::Forbidden().body("not your ledger");
+    }
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+    match ledger_repo::entries(&db, player, params.before, limit).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(ledger);
+}
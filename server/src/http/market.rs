@@ -0,0 +1,112 @@
+//! Player marketplace: limit orders, cancellation and book depth.
+
+use actix_web::{get, middleware::from_fn, post, web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    config::settings,
+    db::market_repo::{self, OrderSide},
+    http::{auth::JwtAuth, idempotency::idempotency},
+    progression::{self, EventKind},
+};
+
+/// The order owner is the authenticated player; a body `player_id` is rejected.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrderReq {
+    pub item_id: i32,
+    pub side: OrderSide,
+    /// Limit price per unit.
+    pub price: i64,
+    pub qty: i32,
+}
+
+#[derive(Deserialize)]
+pub struct DepthParams {
+    /// Price levels per side (default 10, max 50).
+    pub levels: Option<i64>,
+}
+
+/// POST /api/market/orders
+#[post("/market/orders", wrap = "from_fn(idempotency)")]
+pub async fn place(
+    auth: JwtAuth,
+    info: web::Json<OrderReq>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    match market_repo::place(
+        &db,
+        auth.player_id,
+        info.item_id,
+        info.side,
+        info.price,
+        info.qty,
+        settings().market_fee_bps,
+    )
+    .await
+    {
+        Ok(placed) => {
+            for f in &placed.fills {
+                progression::emit(f.buyer_id, EventKind::TradeCompleted);
+                progression::emit(f.seller_id, EventKind::TradeCompleted);
+            }
+            HttpResponse::Ok().json(placed)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+/// POST /api/market/orders/{id}/cancel
+#[post("/market/orders/{id}/cancel", wrap = "from_fn(idempotency)")]
+pub async fn cancel(auth: JwtAuth, path: web::Path<Uuid>, db: web::Data<PgPool>) -> impl Responder {
+    match market_repo::cancel(&db, auth.player_id, path.into_inner()).await {
+        Ok(order) => HttpResponse::Ok().json(order),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+/// GET /api/market/orders — the caller’s open orders.
+#[get("/market/orders")]
+pub async fn my_orders(auth: JwtAuth, db: web::Data<PgPool>) -> impl Responder {
+    match market_repo::list_open(&db, auth.player_id).await {
+        Ok(orders) => HttpResponse::Ok().json(orders),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// GET /api/market/book/{item_id}?levels=10
+#[get("/market/book/{item_id}")]
+pub async fn book(
+    path: web::Path<i32>,
+    web::Query(params): web::Query<DepthParams>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    let levels = params.levels.unwrap_or(10).clamp(1, 50);
+    match market_repo::depth(&db, path.into_inner(), levels).await {
+        Ok(depth) => HttpResponse::Ok().json(depth),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// GET /api/market/fees — credits removed by the market fee sink.
+#[get("/market/fees")]
+pub async fn fees(db: web::Data<PgPool>) -> impl Responder {
+    match market_repo::fees_collected(&db).await {
+        Ok(total) => HttpResponse::Ok().json(json!({
+            "fee_bps": settings().market_fee_bps,
+            "collected": total,
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(place)
+        .service(cancel)
+        .service(my_orders)
+        .service(book)
+        .service(fees);
+}
@@ -2,13 +2,13 @@ use actix_web::{post, web, HttpResponse, Responder};
 use chrono::Utc;
 use redis::{AsyncCommands, Client as RedisClient};
 use serde::Deserialize;
-use uuid::Uuid;
 
-/// Body for both join & leave.
+use crate::http::auth::JwtAuth;
+
+/// Body for join; the queued player is the authenticated one.
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct QueueRequest {
-    /// Player’s UUID (client already knows this)
-    pub player_id: Uuid,
     /// Current Elo rating (used for pairing score)
     pub elo_rating: i32,
 }
@@ -16,6 +16,7 @@ pub struct QueueRequest {
 /// POST /api/matchmaking/join
 #[post("/matchmaking/join")]
 async fn join_queue(
+    auth: JwtAuth,
     info: web::Json<QueueRequest>,
     redis: web::Data<RedisClient>,
 ) -> impl Responder {
@@ -27,7 +28,7 @@ async fn join_queue(
     // Score = Elo + tiny time component, so older entrants with same Elo get priority
     let score = info.elo_rating as f64 + (Utc::now().timestamp_millis() as f64) * 1e-6;
     let _: () = conn
-        .zadd("mm:queue", info.player_id.to_string(), score)
+        .zadd("mm:queue", auth.player_id.to_string(), score)
         .await
         .unwrap_or(());
 
@@ -36,17 +37,14 @@ async fn join_queue(
 
 /// POST /api/matchmaking/leave
 #[post("/matchmaking/leave")]
-async fn leave_queue(
-    info: web::Json<QueueRequest>,
-    redis: web::Data<RedisClient>,
-) -> impl Responder {
+async fn leave_queue(auth: JwtAuth, redis: web::Data<RedisClient>) -> impl Responder {
     let mut conn = match redis.get_multiplexed_async_connection().await {
         Ok(c) => c,
         Err(_) => return HttpResponse::InternalServerError().body("Redis unavailable"),
     };
 
     let _: () = conn
-        .zrem("mm:queue", info.player_id.to_string())
+        .zrem("mm:queue", auth.player_id.to_string())
         .await
         .unwrap_or(());
 
@@ -1,17 +1,23 @@
+pub mod admin;
 pub mod auth;
 pub mod aptos;
 pub mod chat;
+pub mod contests;
+pub mod craft;
 pub mod factions;
 pub mod games;
 pub mod health;
+pub mod idempotency;
 pub mod inventory;
 pub mod items;
 pub mod land;
 pub mod leaderboard;
+pub mod ledger;
+pub mod market;
 pub mod matchmaking;
 pub mod presence;
+pub mod quests;
 pub mod routes;
 pub mod shop;
 pub mod structures;
 pub mod trades;
-pub mod tx;
\ No newline at end of file
@@ -0,0 +1,92 @@
+//! Daily quests and achievements: progress and reward claims.
+//!
+//! Progress is counted from the `progression` event bus; claims pay out
+//! through the ledger and inventory (see `db::quest_repo`).
+
+use actix_web::{get, middleware::from_fn, post, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    config::settings,
+    db::quest_repo::{self, QuestKind},
+    http::{auth::JwtAuth, idempotency::idempotency},
+    progression::{daily_period, resets_at},
+};
+
+async fn claim_reward(auth: &JwtAuth, kind: QuestKind, id: &str, db: &PgPool) -> HttpResponse {
+    let day = daily_period(Utc::now());
+    match quest_repo::claim(db, auth.player_id, kind, id, day, settings().daily_quests).await {
+        Ok(q) => HttpResponse::Ok().json(q),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+/// GET /api/quests — today’s daily quests with the caller’s progress.
+#[get("/quests")]
+pub async fn quests(auth: JwtAuth, db: web::Data<PgPool>) -> impl Responder {
+    let now = Utc::now();
+    let day = daily_period(now);
+    match quest_repo::list(
+        &db,
+        auth.player_id,
+        QuestKind::Daily,
+        day,
+        settings().daily_quests,
+    )
+    .await
+    {
+        Ok(list) => HttpResponse::Ok().json(json!({
+            "period": day,
+            "resets_at": resets_at(now),
+            "quests": list,
+        })),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// POST /api/quests/{id}/claim
+#[post("/quests/{id}/claim", wrap = "from_fn(idempotency)")]
+pub async fn claim_quest(
+    auth: JwtAuth,
+    path: web::Path<String>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    claim_reward(&auth, QuestKind::Daily, &path, &db).await
+}
+
+/// GET /api/achievements — every achievement with the caller’s progress.
+#[get("/achievements")]
+pub async fn achievements(auth: JwtAuth, db: web::Data<PgPool>) -> impl Responder {
+    let day = daily_period(Utc::now());
+    match quest_repo::list(
+        &db,
+        auth.player_id,
+        QuestKind::Achievement,
+        day,
+        settings().daily_quests,
+    )
+    .await
+    {
+        Ok(list) => HttpResponse::Ok().json(list),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// POST /api/achievements/{id}/claim
+#[post("/achievements/{id}/claim", wrap = "from_fn(idempotency)")]
+pub async fn claim_achievement(
+    auth: JwtAuth,
+    path: web::Path<String>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    claim_reward(&auth, QuestKind::Achievement, &path, &db).await
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(quests)
+        .service(claim_quest)
+        .service(achievements)
+        .service(claim_achievement);
+}
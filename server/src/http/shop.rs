@@ -1,8 +1,22 @@
-use actix_web::{error, get, post, web, HttpResponse, Responder};
+use actix_web::{error, get, middleware::from_fn, post, web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
-use uuid::Uuid;
+
+use crate::{
+    cache::ItemMeta,
+    db::{
+        ledger_repo::{self, Account, Reason, SystemAccount},
+        market_repo::OrderSide,
+        trade_repo,
+    },
+    http::{
+        auth::JwtAuth,
+        idempotency::idempotency,
+        items::{ItemQuery, Listing},
+    },
+    pricing,
+};
 
 #[derive(Serialize)]
 pub struct ShopEntry {
@@ -10,68 +24,91 @@ pub struct ShopEntry {
     pub name: String,
     pub description: Option<String>,
     pub price: i32,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+impl Listing for pricing::Quote {
+    fn item_id(&self) -> i32 {
+        self.item_id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn meta(&self) -> &ItemMeta {
+        &self.meta
+    }
+    fn price(&self) -> i64 {
+        self.price as i64
+    }
 }
 
+/// Buyer is the authenticated player; a body `player_id` is rejected.
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct BuyReq {
-    pub player_id: Uuid,
     pub item_id: i32,
     pub quantity: i32,
 }
 
+/// Seller is the authenticated player; a body `player_id` is rejected.
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SellReq {
-    pub player_id: Uuid,
     pub item_id: i32,
     pub quantity: i32,
 }
 
-/// price = base_price + current global stock
-async fn dynamic_price(db: &PgPool, item_id: i32, base: i32) -> i32 {
-    // Try to fetch SUM(quantity); treat any error or NULL as 0
-    let stock_res = sqlx::query_scalar!(
-        "SELECT SUM(quantity)::BIGINT FROM player_items WHERE item_id = $1",
-        item_id
-    )
-    .fetch_optional(db)
-    .await;
-
-    // stock_res: Result<Option<Option<i64>>, _>
-    let stock: i64 = match stock_res {
-        // Ok(Some(inner)) → inner: Option<i64>; unwrap or default to 0
-        Ok(Some(inner)) => inner.unwrap_or(0),
-        // Any error or no row → 0
-        _ => 0,
+/// GET /api/shop/items — filter / sort with [`ItemQuery`].
+#[get("/shop/items")]
+pub async fn list_items(
+    web::Query(query): web::Query<ItemQuery>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    let quotes = match pricing::catalogue(&db).await {
+        Ok(q) => q,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
     };
 
-    base + (stock as i32)
+    let out: Vec<ShopEntry> = query
+        .apply(quotes)
+        .into_iter()
+        .map(|q| ShopEntry {
+            item_id: q.item_id,
+            name: q.name,
+            description: q.description,
+            price: q.price,
+            meta: q.meta,
+        })
+        .collect();
+
+    HttpResponse::Ok().json(out)
 }
 
-/// GET /api/shop/items
-#[get("/shop/items")]
-pub async fn list_items(db: web::Data<PgPool>) -> impl Responder {
-    let rows = sqlx::query!("SELECT id, name, description, base_price FROM items ORDER BY id")
-        .fetch_all(&**db)
-        .await
-        .unwrap_or_default();
-
-    let mut out = Vec::with_capacity(rows.len());
-    for row in rows {
-        let price = dynamic_price(&db, row.id, row.base_price).await;
-        out.push(ShopEntry {
-            item_id: row.id,
-            name: row.name,
-            description: row.description,
-            price,
-        });
-    }
+#[derive(Deserialize)]
+pub struct HistoryParams {
+    /// Snapshots to return, newest first (default 100, max 1000).
+    pub limit: Option<i64>,
+}
 
-    HttpResponse::Ok().json(out)
+/// GET /api/shop/history/{item_id}
+#[get("/shop/history/{item_id}")]
+pub async fn history(
+    path: web::Path<i32>,
+    web::Query(params): web::Query<HistoryParams>,
+    db: web::Data<PgPool>,
+) -> impl Responder {
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    match pricing::history(&db, path.into_inner(), limit).await {
+        Ok(points) => HttpResponse::Ok().json(points),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
 }
 
 /// POST /api/shop/buy
-#[post("/shop/buy")]
+#[post("/shop/buy", wrap = "from_fn(idempotency)")]
 pub async fn buy(
+    auth: JwtAuth,
     info: web::Json<BuyReq>,
     db: web::Data<PgPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
@@ -81,42 +118,54 @@ pub async fn buy(
 
     let mut tx = db.begin().await.map_err(error::ErrorInternalServerError)?;
 
-    // 1) Fetch base price
-    let base_price_opt =
-        sqlx::query_scalar!("SELECT base_price FROM items WHERE id = $1", info.item_id)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(error::ErrorInternalServerError)?;
-
-    let base_price = if let Some(bp) = base_price_opt {
-        bp
-    } else {
+    // 1) Price along the curve (bad id → 400)
+    let priced = pricing::trade_total(&mut tx, info.item_id, OrderSide::Buy, info.quantity)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let Some((_, total_cost_i64)) = priced else {
         tx.rollback().await.ok();
         return Err(error::ErrorBadRequest("invalid item_id"));
     };
 
-    // 2) Compute total cost
-    let unit_price = dynamic_price(&db, info.item_id, base_price).await;
-    let total_cost = unit_price.checked_mul(info.quantity).unwrap_or_default();
-    let total_cost_i64 = total_cost as i64;
+    // 1b) Stacking limit
+    if let Err(e) =
+        trade_repo::ensure_room(&mut tx, auth.player_id, info.item_id, info.quantity).await
+    {
+        tx.rollback().await.ok();
+        return Ok(HttpResponse::BadRequest().body(e.to_string()));
+    }
 
-    // 3) Debit credits
-    let debited = sqlx::query!(
-        "UPDATE players SET credits = credits - $1 WHERE id = $2 AND credits >= $1",
-        total_cost_i64,
-        info.player_id
+    // 2) Average unit price, as logged
+    let unit_price = (total_cost_i64 / info.quantity as i64) as i32;
+
+    // 3) Log the transaction (feeds the price curve; ledger reference)
+    let sale_id = pricing::record(
+        &mut tx,
+        info.item_id,
+        auth.player_id,
+        OrderSide::Buy,
+        info.quantity,
+        unit_price,
     )
-    .execute(&mut *tx)
     .await
-    .map_err(error::ErrorInternalServerError)?
-    .rows_affected();
+    .map_err(error::ErrorInternalServerError)?;
 
-    if debited == 0 {
+    // 4) Debit credits
+    if let Err(e) = ledger_repo::transfer(
+        &mut tx,
+        Account::Player(auth.player_id),
+        Account::System(SystemAccount::Shop),
+        total_cost_i64,
+        Reason::Shop,
+        Some(&sale_id.to_string()),
+    )
+    .await
+    {
         tx.rollback().await.ok();
-        return Ok(HttpResponse::BadRequest().body("insufficient credits"));
+        return Ok(HttpResponse::BadRequest().body(e.to_string()));
     }
 
-    // 4) Upsert inventory
+    // 5) Upsert inventory
     sqlx::query!(
         r#"
         INSERT INTO player_items (player_id, item_id, quantity)
@@ -124,7 +173,7 @@ pub async fn buy(
         ON CONFLICT (player_id, item_id)
         DO UPDATE SET quantity = player_items.quantity + EXCLUDED.quantity
         "#,
-        info.player_id,
+        auth.player_id,
         info.item_id,
         info.quantity
     )
@@ -137,8 +186,9 @@ pub async fn buy(
 }
 
 /// POST /api/shop/sell
-#[post("/shop/sell")]
+#[post("/shop/sell", wrap = "from_fn(idempotency)")]
 pub async fn sell(
+    auth: JwtAuth,
     info: web::Json<SellReq>,
     db: web::Data<PgPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
@@ -148,62 +198,52 @@ pub async fn sell(
 
     let mut tx = db.begin().await.map_err(error::ErrorInternalServerError)?;
 
-    // 1) Check ownership — unwrap the Option<i64> to i64
-    let owned: i64 = sqlx::query_scalar!(
-        r#"
-        SELECT COALESCE(SUM(quantity)::BIGINT, 0)
-          FROM player_items
-         WHERE player_id = $1 AND item_id = $2
-        "#,
-        info.player_id,
-        info.item_id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(error::ErrorInternalServerError)?
-    .unwrap_or(0);
-
-    if owned < (info.quantity as i64) {
+    // 1) Price along the curve
+    let priced = pricing::trade_total(&mut tx, info.item_id, OrderSide::Sell, info.quantity)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let Some((quote, total)) = priced else {
         tx.rollback().await.ok();
-        return Ok(HttpResponse::BadRequest().body("not enough items"));
+        return Err(error::ErrorBadRequest("invalid item_id"));
+    };
+    if quote.meta.soulbound {
+        tx.rollback().await.ok();
+        return Ok(HttpResponse::BadRequest().body("soulbound items cannot be sold"));
     }
 
-    // 2) Fetch base price
-    let base_price_opt =
-        sqlx::query_scalar!("SELECT base_price FROM items WHERE id = $1", info.item_id)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(error::ErrorInternalServerError)?;
-
-    let base_price = if let Some(bp) = base_price_opt {
-        bp
-    } else {
+    // 2) Remove items (checks the stack; an emptied stack is deleted)
+    if let Err(e) =
+        trade_repo::take_item(&mut tx, auth.player_id, info.item_id, info.quantity).await
+    {
         tx.rollback().await.ok();
-        return Err(error::ErrorBadRequest("invalid item_id"));
-    };
+        return Ok(HttpResponse::BadRequest().body(e.to_string()));
+    }
 
-    // 3) Compute gain
-    let unit_price = dynamic_price(&db, info.item_id, base_price).await;
-    let total_gain = (unit_price.saturating_sub(1) as i64).saturating_mul(info.quantity as i64);
+    // 3) Compute gain (one-credit spread under each unit’s price)
+    let total_gain = total.saturating_sub(info.quantity as i64);
+    let unit_price = (total_gain / info.quantity as i64) as i32;
 
-    // 4) Remove items
-    sqlx::query!(
-        "UPDATE player_items SET quantity = quantity - $3 WHERE player_id = $1 AND item_id = $2",
-        info.player_id,
+    // 4) Log the transaction (feeds the price curve; ledger reference)
+    let sale_id = pricing::record(
+        &mut tx,
         info.item_id,
-        info.quantity
+        auth.player_id,
+        OrderSide::Sell,
+        info.quantity,
+        unit_price,
     )
-    .execute(&mut *tx)
     .await
     .map_err(error::ErrorInternalServerError)?;
 
     // 5) Credit player
-    sqlx::query!(
-        "UPDATE players SET credits = credits + $2 WHERE id = $1",
-        info.player_id,
-        total_gain
+    ledger_repo::transfer(
+        &mut tx,
+        Account::System(SystemAccount::Shop),
+        Account::Player(auth.player_id),
+        total_gain,
+        Reason::Shop,
+        Some(&sale_id.to_string()),
     )
-    .execute(&mut *tx)
     .await
     .map_err(error::ErrorInternalServerError)?;
 
@@ -212,5 +252,8 @@ pub async fn sell(
 }
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(list_items).service(buy).service(sell);
+    cfg.service(list_items)
+        .service(history)
+        .service(buy)
+        .service(sell);
 }
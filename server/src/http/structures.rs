@@ -1,5 +1,12 @@
-use crate::db::faction_repo;
-use actix_web::{get, post, web, Error, HttpResponse};
+use crate::{
+    db::{
+        faction_repo,
+        treasury_repo::{self, TreasuryReason},
+    },
+    http::{auth::JwtAuth, idempotency::idempotency},
+    progression::{self, EventKind},
+};
+use actix_web::{get, middleware::from_fn, post, web, Error, HttpResponse};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -17,9 +24,10 @@ pub struct Structure {
     pub placed_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Builder is the authenticated player; a body `player_id` is rejected.
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct BuildReq {
-    pub player_id: Uuid,
     #[serde(rename = "type")]
     pub structure_type: String,
     pub x: i32,
@@ -28,8 +36,11 @@ pub struct BuildReq {
     pub stats: serde_json::Value,
 }
 
-#[post("/structures/build")]
+/// The owning faction’s treasury pays the type’s `structure_costs`; types
+/// without a cost row are refused.
+#[post("/structures/build", wrap = "from_fn(idempotency)")]
 pub async fn build(
+    auth: JwtAuth,
     info: web::Json<BuildReq>,
     db: web::Data<PgPool>,
 ) -> Result<HttpResponse, Error> {
@@ -47,30 +58,52 @@ pub async fn build(
     .ok_or_else(|| actix_web::error::ErrorBadRequest("parcel not claimed"))?;
 
     // 2) Check membership via our DB helper
-    if !faction_repo::is_faction_member(db.get_ref(), owner_faction, info.player_id)
+    if !faction_repo::is_faction_member(db.get_ref(), owner_faction, auth.player_id)
         .await
         .map_err(actix_web::error::ErrorInternalServerError)?
     {
         return Err(actix_web::error::ErrorUnauthorized("not in owning faction"));
     }
 
-    // 3) Insert and return the new structure ID
+    // 3) Insert, pay from the faction treasury and return the new structure ID
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let cost = treasury_repo::structure_cost(&mut tx, &info.structure_type)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("unknown structure type"))?;
     let sid: i32 = sqlx::query_scalar!(
         r#"INSERT INTO structures
              (owner_player_id, owner_faction_id, type, x, y, stats)
            VALUES ($1, $2, $3, $4, $5, $6)
            RETURNING id"#,
-        info.player_id,
+        auth.player_id,
         owner_faction,
         info.structure_type,
         info.x,
         info.y,
         info.stats
     )
-    .fetch_one(db.get_ref())
+    .fetch_one(&mut *tx)
     .await
     .map_err(actix_web::error::ErrorInternalServerError)?;
 
+    treasury_repo::spend(
+        &mut tx,
+        owner_faction,
+        &cost,
+        TreasuryReason::Structure,
+        Some(&format!("structure:{sid}")),
+    )
+    .await
+    .map_err(|e| actix_web::error::ErrorBadRequest(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    progression::emit(auth.player_id, EventKind::StructureBuilt);
+
     Ok(HttpResponse::Ok().json(serde_json::json!({ "structure_id": sid })))
 }
 
@@ -1,118 +1,160 @@
-//! Player-to-player trade: moves items and credits transactionally.
+//! Player-to-player trade offers: propose, counter, accept, cancel.
+//!
+//! The proposer's bundle is escrowed on propose; the swap settles in one
+//! transaction on accept (see `db::trade_repo`). Expiry is handled by the
+//! `trading` worker.
 
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{get, middleware::from_fn, post, web, HttpResponse, Responder};
+use chrono::Duration;
+use redis::Client as RedisClient;
 use serde::Deserialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::{
+    config::settings,
+    db::trade_repo::{self, Bundle},
+    http::{auth::JwtAuth, idempotency::idempotency},
+    progression::{self, EventKind},
+    protocol::ServerMsg,
+    trading,
+};
+
+/// The proposer is the authenticated player; a body `proposer_id` is rejected.
 #[derive(Deserialize)]
-pub struct TradeReq {
-    from_player: Uuid, // seller
-    to_player: Uuid,   // buyer
-    item_id: i32,
-    qty: i32,
-    price: i64, // total credits exchanged
+#[serde(deny_unknown_fields)]
+pub struct ProposeReq {
+    pub to_player: Uuid,
+    /// What the proposer gives (escrowed now).
+    #[serde(default)]
+    pub offer: Bundle,
+    /// What the proposer wants in return.
+    #[serde(default)]
+    pub ask: Bundle,
 }
 
-/// POST /api/trades
-#[post("/trades")]
-pub async fn trade(info: web::Json<TradeReq>, db: web::Data<PgPool>) -> impl Responder {
-    if info.qty <= 0 || info.price < 0 {
-        return HttpResponse::BadRequest().body("bad qty / price");
-    }
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CounterReq {
+    #[serde(default)]
+    pub offer: Bundle,
+    #[serde(default)]
+    pub ask: Bundle,
+}
 
-    let mut tx = match db.begin().await {
-        Ok(t) => t,
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
+fn ttl() -> Duration {
+    Duration::seconds(settings().trade_offer_ttl as i64)
+}
 
-    // 1) seller stock
-    let (stock,) = sqlx::query_as::<_, (i64,)>(
-        "SELECT COALESCE(SUM(quantity),0)::BIGINT \
-         FROM player_items \
-         WHERE player_id = $1 AND item_id = $2",
+/// POST /api/trades/offers
+#[post("/trades/offers", wrap = "from_fn(idempotency)")]
+pub async fn propose(
+    auth: JwtAuth,
+    info: web::Json<ProposeReq>,
+    db: web::Data<PgPool>,
+    redis: web::Data<RedisClient>,
+) -> impl Responder {
+    match trade_repo::propose(
+        &db,
+        auth.player_id,
+        info.to_player,
+        &info.offer,
+        &info.ask,
+        ttl(),
     )
-    .bind(info.from_player)
-    .bind(info.item_id)
-    .fetch_one(&mut *tx)
     .await
-    .unwrap_or((0,));
-
-    if stock < info.qty as i64 {
-        tx.rollback().await.ok();
-        return HttpResponse::BadRequest().body("seller lacks items");
+    {
+        Ok(offer) => {
+            let msg = ServerMsg::TradeOffered {
+                offer: offer.clone(),
+            };
+            trading::notify(&redis, offer.recipient_id, &msg).await;
+            HttpResponse::Ok().json(offer)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
+}
 
-    // 2) buyer credits
-    let (credits,) = sqlx::query_as::<_, (i64,)>("SELECT credits FROM players WHERE id = $1")
-        .bind(info.to_player)
-        .fetch_one(&mut *tx)
-        .await
-        .unwrap_or((0,));
-
-    if credits < info.price {
-        tx.rollback().await.ok();
-        return HttpResponse::BadRequest().body("buyer lacks credits");
-    }
-
-    // 3) move items
-    let _ = sqlx::query!(
-        "UPDATE player_items
-         SET quantity = quantity - $1
-         WHERE player_id = $2 AND item_id = $3",
-        info.qty,
-        info.from_player,
-        info.item_id
-    )
-    .execute(&mut *tx)
-    .await;
-
-    let _ = sqlx::query!(
-        r#"INSERT INTO player_items (player_id, item_id, quantity)
-           VALUES ($1,$2,$3)
-           ON CONFLICT (player_id,item_id)
-           DO UPDATE SET quantity = player_items.quantity + EXCLUDED.quantity"#,
-        info.to_player,
-        info.item_id,
-        info.qty
-    )
-    .execute(&mut *tx)
-    .await;
-
-    // 4) move credits
-    let _ = sqlx::query!(
-        "UPDATE players SET credits = credits - $1 WHERE id = $2",
-        info.price,
-        info.to_player,
+/// POST /api/trades/offers/{id}/counter
+#[post("/trades/offers/{id}/counter", wrap = "from_fn(idempotency)")]
+pub async fn counter(
+    auth: JwtAuth,
+    path: web::Path<Uuid>,
+    info: web::Json<CounterReq>,
+    db: web::Data<PgPool>,
+    redis: web::Data<RedisClient>,
+) -> impl Responder {
+    match trade_repo::counter(
+        &db,
+        auth.player_id,
+        path.into_inner(),
+        &info.offer,
+        &info.ask,
+        ttl(),
     )
-    .execute(&mut *tx)
-    .await;
+    .await
+    {
+        Ok((original, offer)) => {
+            trading::notify_closed(&redis, &original).await;
+            let msg = ServerMsg::TradeOffered {
+                offer: offer.clone(),
+            };
+            trading::notify(&redis, offer.recipient_id, &msg).await;
+            HttpResponse::Ok().json(offer)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
 
-    let _ = sqlx::query!(
-        "UPDATE players SET credits = credits + $1 WHERE id = $2",
-        info.price,
-        info.from_player,
-    )
-    .execute(&mut *tx)
-    .await;
+/// POST /api/trades/offers/{id}/accept
+#[post("/trades/offers/{id}/accept", wrap = "from_fn(idempotency)")]
+pub async fn accept(
+    auth: JwtAuth,
+    path: web::Path<Uuid>,
+    db: web::Data<PgPool>,
+    redis: web::Data<RedisClient>,
+) -> impl Responder {
+    match trade_repo::accept(&db, auth.player_id, path.into_inner()).await {
+        Ok(offer) => {
+            progression::emit(offer.proposer_id, EventKind::TradeCompleted);
+            progression::emit(offer.recipient_id, EventKind::TradeCompleted);
+            trading::notify_closed(&redis, &offer).await;
+            HttpResponse::Ok().json(offer)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
 
-    // 5) record trade
-    let _ = sqlx::query!(
-        r#"INSERT INTO trades (from_player, to_player, item_id, qty, price)
-           VALUES ($1,$2,$3,$4,$5)"#,
-        info.from_player,
-        info.to_player,
-        info.item_id,
-        info.qty,
-        info.price as i32 // trades.price is INT
-    )
-    .execute(&mut *tx)
-    .await;
+/// POST /api/trades/offers/{id}/cancel
+#[post("/trades/offers/{id}/cancel", wrap = "from_fn(idempotency)")]
+pub async fn cancel(
+    auth: JwtAuth,
+    path: web::Path<Uuid>,
+    db: web::Data<PgPool>,
+    redis: web::Data<RedisClient>,
+) -> impl Responder {
+    match trade_repo::cancel(&db, auth.player_id, path.into_inner()).await {
+        Ok(offer) => {
+            trading::notify_closed(&redis, &offer).await;
+            HttpResponse::Ok().json(offer)
+        }
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
 
-    tx.commit().await.ok();
-    HttpResponse::Ok().body("trade executed")
+/// GET /api/trades/offers — open offers sent or received by the caller.
+#[get("/trades/offers")]
+pub async fn list(auth: JwtAuth, db: web::Data<PgPool>) -> impl Responder {
+    match trade_repo::list_open(&db, auth.player_id).await {
+        Ok(offers) => HttpResponse::Ok().json(offers),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
 }
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(trade);
+    cfg.service(propose)
+        .service(counter)
+        .service(accept)
+        .service(cancel)
+        .service(list);
 }
@@ -0,0 +1,39 @@
+//! Ledger reconciliation: periodically compares cached balances
+//! (`players.credits`, `economy_sinks.balance`) with the sum of their
+//! `credit_ledger` entries and flags any drift.
+
+use sqlx::PgPool;
+use tokio::time::{sleep, Duration};
+
+use crate::{
+    config::settings,
+    db::ledger_repo::{self, Drift},
+};
+
+/// One pass: find, log and persist every drifting balance.
+pub async fn reconcile(db: &PgPool) -> anyhow::Result<Vec<Drift>> {
+    let drift = ledger_repo::find_drift(db).await?;
+    for d in &drift {
+        log::warn!(
+            "ledger drift: player={:?} system={:?} cached={} ledger={}",
+            d.player_id,
+            d.system,
+            d.cached,
+            d.ledger
+        );
+    }
+    ledger_repo::record_drift(db, &drift).await?;
+    Ok(drift)
+}
+
+/// Spawn the loop that reconciles every `LEDGER_RECONCILE_SECS`.
+pub fn start(db: PgPool) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = reconcile(&db).await {
+                log::error!("ledger reconciliation failed: {e:?}");
+            }
+            sleep(Duration::from_secs(settings().ledger_reconcile_secs)).await;
+        }
+    });
+}
@@ -1,11 +1,18 @@
 pub mod cache;
 pub mod chain;
 pub mod config;
+pub mod contests;
+pub mod crafting;
 pub mod db;
 pub mod ecosystem;
+pub mod effects;
 pub mod game;
 pub mod http;
+pub mod ledger;
 pub mod matchmaking;
 pub mod metrics;
+pub mod pricing;
+pub mod progression;
 pub mod protocol;
+pub mod trading;
 pub mod ws;
@@ -0,0 +1,291 @@
+//! Shop price curves and the price-history snapshotter.
+//!
+//! price = clamp(base × (1 + elasticity × net), floor, ceiling)
+//!
+//! where `net` is recent shop volume (buys − sells), each transaction
+//! weighted by `exp(−decay × age_in_hours)`. With no trading the weights
+//! fade and the price drifts back to `base_price`. A shop trade of several
+//! units walks the curve one unit at a time (see [`Curve::total`]).
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use tokio::time::{sleep, Duration};
+use uuid::Uuid;
+
+use crate::{cache::ItemMeta, config::settings, db::market_repo::OrderSide};
+
+/// Transactions older than this no longer move the price.
+pub const VOLUME_WINDOW_HOURS: i32 = 72;
+
+/// Per-hour decay used for items without their own curve.
+pub const DEFAULT_DECAY: f64 = 0.1;
+
+/// Bounded supply/demand curve for one item (`item_price_curves`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Curve {
+    pub floor: i32,
+    pub ceiling: i32,
+    /// Relative price change per unit of net volume.
+    pub elasticity: f64,
+    /// Per-hour exponential decay of past volume.
+    pub decay: f64,
+}
+
+impl Curve {
+    /// Curve for items without a configured row: ½× to 4× base.
+    pub fn default_for(base: i32) -> Self {
+        Curve {
+            floor: (base / 2).max(1),
+            ceiling: base.saturating_mul(4).max(1),
+            elasticity: 0.02,
+            decay: DEFAULT_DECAY,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.floor <= 0 || self.ceiling < self.floor {
+            bail!("need 0 < floor <= ceiling");
+        }
+        if !(self.elasticity >= 0.0 && self.decay >= 0.0) {
+            bail!("elasticity and decay must be >= 0");
+        }
+        Ok(())
+    }
+
+    /// Unit price for `base` given decayed net volume `net`.
+    pub fn price(&self, base: i32, net: f64) -> i32 {
+        let raw = base as f64 * (1.0 + self.elasticity * net);
+        raw.round().clamp(self.floor as f64, self.ceiling as f64) as i32
+    }
+
+    /// Total for `qty` units traded one at a time from net volume `net`. A
+    /// bought unit costs the price before it moves the curve, a sold unit
+    /// the price after, so units bought and sold straight back never gain.
+    pub fn total(&self, base: i32, net: f64, side: OrderSide, qty: i32) -> i64 {
+        let mut total = 0_i64;
+        for k in 0..qty.max(0) {
+            let (price, bound) = match side {
+                OrderSide::Buy => (self.price(base, net + k as f64), self.ceiling),
+                OrderSide::Sell => (self.price(base, net - (k + 1) as f64), self.floor),
+            };
+            if price == bound || self.elasticity == 0.0 {
+                // The price stays put for the remaining units.
+                return total + price as i64 * (qty - k) as i64;
+            }
+            total += price as i64;
+        }
+        total
+    }
+}
+
+/// Current shop price of one catalogue item.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Quote {
+    pub item_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub base_price: i32,
+    pub price: i32,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PricePoint {
+    pub price: i32,
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A quote with the curve and decayed net volume it was priced from.
+struct Priced {
+    quote: Quote,
+    curve: Curve,
+    net: f64,
+}
+
+/// Prices for every item (or just `only`) in a single query.
+async fn priced(conn: &mut PgConnection, only: Option<i32>) -> Result<Vec<Priced>> {
+    let rows = sqlx::query!(
+        r#"SELECT i.id, i.name, i.description, i.base_price,
+                  i.category, i.rarity, i.max_stack, i.tradable, i.soulbound, i.icon_key,
+                  c.floor       AS "floor?",
+                  c.ceiling     AS "ceiling?",
+                  c.elasticity  AS "elasticity?",
+                  c.decay       AS "decay?",
+                  COALESCE(SUM(
+                      CASE WHEN s.side = 'buy' THEN s.qty ELSE -s.qty END
+                      * EXP(-COALESCE(c.decay, $1)
+                            * EXTRACT(EPOCH FROM NOW() - s.created_at)::FLOAT8 / 3600.0)
+                  ), 0)::FLOAT8 AS "net!"
+             FROM items i
+             LEFT JOIN item_price_curves c ON c.item_id = i.id
+             LEFT JOIN shop_transactions s
+                    ON s.item_id = i.id
+                   AND s.created_at > NOW() - make_interval(hours => $2)
+            WHERE ($3::INT IS NULL OR i.id = $3)
+            GROUP BY i.id, c.item_id
+            ORDER BY i.id"#,
+        DEFAULT_DECAY,
+        VOLUME_WINDOW_HOURS,
+        only
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let curve = match (r.floor, r.ceiling, r.elasticity, r.decay) {
+                (Some(floor), Some(ceiling), Some(elasticity), Some(decay)) => Curve {
+                    floor,
+                    ceiling,
+                    elasticity,
+                    decay,
+                },
+                _ => Curve::default_for(r.base_price),
+            };
+            Priced {
+                quote: Quote {
+                    item_id: r.id,
+                    name: r.name,
+                    description: r.description,
+                    base_price: r.base_price,
+                    price: curve.price(r.base_price, r.net),
+                    meta: ItemMeta::from_row(
+                        &r.category,
+                        &r.rarity,
+                        r.max_stack,
+                        r.tradable,
+                        r.soulbound,
+                        r.icon_key,
+                    ),
+                },
+                curve,
+                net: r.net,
+            }
+        })
+        .collect())
+}
+
+async fn quotes(conn: &mut PgConnection, only: Option<i32>) -> Result<Vec<Quote>> {
+    Ok(priced(conn, only)
+        .await?
+        .into_iter()
+        .map(|p| p.quote)
+        .collect())
+}
+
+/// Whole shop catalogue with current prices.
+pub async fn catalogue(db: &PgPool) -> Result<Vec<Quote>> {
+    let mut conn = db.acquire().await?;
+    quotes(&mut conn, None).await
+}
+
+/// Current price of one item; `None` if the item does not exist.
+pub async fn quote(conn: &mut PgConnection, item_id: i32) -> Result<Option<Quote>> {
+    Ok(quotes(conn, Some(item_id)).await?.pop())
+}
+
+/// Quote for one item and the total of trading `qty` of it with the shop
+/// along its curve; `None` if the item does not exist.
+pub async fn trade_total(
+    conn: &mut PgConnection,
+    item_id: i32,
+    side: OrderSide,
+    qty: i32,
+) -> Result<Option<(Quote, i64)>> {
+    Ok(priced(conn, Some(item_id)).await?.pop().map(|p| {
+        let total = p.curve.total(p.quote.base_price, p.net, side, qty);
+        (p.quote, total)
+    }))
+}
+
+/// Log a shop transaction so it feeds the curve; returns its id.
+pub async fn record(
+    conn: &mut PgConnection,
+    item_id: i32,
+    player: Uuid,
+    side: OrderSide,
+    qty: i32,
+    unit_price: i32,
+) -> Result<i64> {
+    let id = sqlx::query_scalar!(
+        "INSERT INTO shop_transactions (item_id, player_id, side, qty, unit_price)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id",
+        item_id,
+        player,
+        side.as_str(),
+        qty,
+        unit_price
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(id)
+}
+
+/// Create or replace an item’s curve.
+pub async fn set_curve(db: &PgPool, item_id: i32, c: &Curve) -> Result<()> {
+    c.validate()?;
+    sqlx::query!(
+        "INSERT INTO item_price_curves (item_id, floor, ceiling, elasticity, decay)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (item_id) DO UPDATE
+            SET floor = EXCLUDED.floor, ceiling = EXCLUDED.ceiling,
+                elasticity = EXCLUDED.elasticity, decay = EXCLUDED.decay",
+        item_id,
+        c.floor,
+        c.ceiling,
+        c.elasticity,
+        c.decay
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Write the current price of every item to `price_history`.
+pub async fn snapshot(db: &PgPool) -> Result<usize> {
+    let quotes = catalogue(db).await?;
+    let ids: Vec<i32> = quotes.iter().map(|q| q.item_id).collect();
+    let prices: Vec<i32> = quotes.iter().map(|q| q.price).collect();
+    sqlx::query!(
+        "INSERT INTO price_history (item_id, price)
+         SELECT * FROM UNNEST($1::INT[], $2::INT[])
+         ON CONFLICT DO NOTHING",
+        &ids,
+        &prices
+    )
+    .execute(db)
+    .await?;
+    Ok(ids.len())
+}
+
+/// Latest `limit` snapshots for an item, newest first.
+pub async fn history(db: &PgPool, item_id: i32, limit: i64) -> Result<Vec<PricePoint>> {
+    Ok(sqlx::query_as!(
+        PricePoint,
+        "SELECT price, recorded_at FROM price_history
+          WHERE item_id = $1
+          ORDER BY recorded_at DESC
+          LIMIT $2",
+        item_id,
+        limit
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+/// Spawn the loop that snapshots prices every `PRICE_SNAPSHOT_SECS`.
+pub fn start(db: PgPool) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = snapshot(&db).await {
+                log::error!("price snapshot failed: {e:?}");
+            }
+            sleep(Duration::from_secs(settings().price_snapshot_secs)).await;
+        }
+    });
+}
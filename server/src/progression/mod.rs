@@ -0,0 +1,130 @@
+//! Quests and achievements: the internal event bus and the daily rotation.
+//!
+//! Game and economy code paths call [`emit`] once their change is
+//! committed. The worker started by [`start`] drains the bus and bumps the
+//! matching progress counters (see `db::quest_repo`). Emitting never blocks
+//! or fails; events sent before the worker runs are buffered.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{config::settings, db::quest_repo, game::rng::CombatRng};
+
+/// Something a player did that quests can count (`quests.event`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    TurnPlayed,
+    MatchPlayed,
+    MatchWon,
+    TradeCompleted,
+    ChatSent,
+    LandClaimed,
+    StructureBuilt,
+    ItemCrafted,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::TurnPlayed => "turn_played",
+            EventKind::MatchPlayed => "match_played",
+            EventKind::MatchWon => "match_won",
+            EventKind::TradeCompleted => "trade_completed",
+            EventKind::ChatSent => "chat_sent",
+            EventKind::LandClaimed => "land_claimed",
+            EventKind::StructureBuilt => "structure_built",
+            EventKind::ItemCrafted => "item_crafted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerEvent {
+    pub player: Uuid,
+    pub kind: EventKind,
+    pub amount: i32,
+    /// When it happened; picks the daily period it counts toward.
+    pub at: DateTime<Utc>,
+}
+
+type Bus = (
+    mpsc::UnboundedSender<PlayerEvent>,
+    Mutex<Option<mpsc::UnboundedReceiver<PlayerEvent>>>,
+);
+
+static BUS: Lazy<Bus> = Lazy::new(|| {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (tx, Mutex::new(Some(rx)))
+});
+
+/// Record that `player` did `kind` once.
+pub fn emit(player: Uuid, kind: EventKind) {
+    emit_n(player, kind, 1);
+}
+
+/// Record that `player` did `kind` `amount` times.
+pub fn emit_n(player: Uuid, kind: EventKind, amount: i32) {
+    if amount > 0 {
+        let _ = BUS.0.send(PlayerEvent {
+            player,
+            kind,
+            amount,
+            at: Utc::now(),
+        });
+    }
+}
+
+/// Progress period of achievements (they never rotate).
+pub fn achievement_period() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+/// Daily-quest period containing `now` (the UTC date).
+pub fn daily_period(now: DateTime<Utc>) -> NaiveDate {
+    now.date_naive()
+}
+
+/// Next UTC midnight after `now`, when the daily rotation changes.
+pub fn resets_at(now: DateTime<Utc>) -> DateTime<Utc> {
+    (now.date_naive() + Duration::days(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+}
+
+/// The `count` daily quests offered on `day`: a seeded pick from `ids`, so
+/// every server agrees on it without storing the rotation. Sorted by id.
+pub fn daily_rotation(ids: &[String], day: NaiveDate, count: usize) -> Vec<String> {
+    let mut pool: Vec<String> = ids.to_vec();
+    pool.sort();
+    let mut rng = CombatRng::new(day.num_days_from_ce() as u64);
+    let n = count.min(pool.len());
+    for i in 0..n {
+        let j = i + rng.below((pool.len() - i) as u64) as usize;
+        pool.swap(i, j);
+    }
+    pool.truncate(n);
+    pool.sort();
+    pool
+}
+
+/// Spawn the worker that applies bus events to quest progress.
+pub fn start(db: PgPool) {
+    let Some(mut rx) = BUS.1.lock().unwrap().take() else {
+        log::warn!("progression worker already running");
+        return;
+    };
+    tokio::spawn(async move {
+        while let Some(ev) = rx.recv().await {
+            if let Err(e) = quest_repo::record(&db, &ev, settings().daily_quests).await {
+                log::error!("quest progress for {ev:?} failed: {e:?}");
+            }
+        }
+    });
+}
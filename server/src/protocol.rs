@@ -1,6 +1,12 @@
 //! Wire-protocol shared by client, WS handler and game session.
 
-use crate::game::{logic::CombatResult, types::TurnAction};
+use crate::{
+    db::{
+        contest_repo::Contest,
+        trade_repo::{TradeOffer, TradeStatus},
+    },
+    game::{logic::CombatResult, loot::MatchReward, types::TurnAction, victory::RuleScore},
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -12,41 +18,132 @@ pub enum ClientMsg {
     Ready {
         game_id: Uuid,
         player_id: Uuid,
+        /// Opaque client correlation id, echoed on any `Error` reply.
+        #[serde(default)]
+        request_id: Option<String>,
     },
     Turn {
         game_id: Uuid,
         player_id: Uuid,
         turn: u32,
         actions: Vec<TurnAction>,
+        #[serde(default)]
+        request_id: Option<String>,
     },
     /// Sent by a client that lost its socket and re-opened a new one.
     Resume {
         game_id: Uuid,
         player_id: Uuid,
+        #[serde(default)]
+        request_id: Option<String>,
     },
-    /// Emitted internally by the WS layer when a socket closes.
-    Disconnected {
-        game_id: Uuid,
-        player_id: Uuid,
-    },
+    /// Emitted internally by the WS layer when a socket closes; never
+    /// accepted from the wire.
+    #[serde(skip_deserializing)]
+    Disconnected { game_id: Uuid, player_id: Uuid },
+}
+
+impl ClientMsg {
+    pub fn game_id(&self) -> Uuid {
+        match self {
+            ClientMsg::Ready { game_id, .. }
+            | ClientMsg::Turn { game_id, .. }
+            | ClientMsg::Resume { game_id, .. }
+            | ClientMsg::Disconnected { game_id, .. } => *game_id,
+        }
+    }
+
+    pub fn player_id(&self) -> Uuid {
+        match self {
+            ClientMsg::Ready { player_id, .. }
+            | ClientMsg::Turn { player_id, .. }
+            | ClientMsg::Resume { player_id, .. }
+            | ClientMsg::Disconnected { player_id, .. } => *player_id,
+        }
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            ClientMsg::Ready { request_id, .. }
+            | ClientMsg::Turn { request_id, .. }
+            | ClientMsg::Resume { request_id, .. } => request_id.as_deref(),
+            ClientMsg::Disconnected { .. } => None,
+        }
+    }
 }
 
 // ---------- server → client ----------
+/// Stable machine-readable reason carried by `ServerMsg::Error`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Text frame is not a valid `ClientMsg`.
+    ParseError,
+    /// Message `player_id` is not the socket’s authenticated player.
+    PlayerMismatch,
+    /// No game with that id.
+    UnknownGame,
+    /// The game exists but the sender is not seated in it.
+    NotYourGame,
+    /// `turn` is neither the turn the session is waiting for nor the one
+    /// it just resolved (whose `TurnResult` is resent instead).
+    StaleTurn,
+    /// A different action list was already submitted for this turn.
+    TurnAlreadySubmitted,
+    /// Too many frames on this socket; the message was dropped.
+    RateLimited,
+    /// Server-side failure; safe to retry.
+    Internal,
+}
+
+/// When the server stops waiting for `turn`; a seat that has not submitted
+/// by then resolves as `Pass`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TurnDeadline {
+    pub turn: u32,
+    pub expires_at: DateTime<Utc>,
+    /// Length of the turn clock, for client countdowns.
+    pub seconds: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type")]
 pub enum ServerMsg {
     GameStart {
         game_id: Uuid,
         turn: u32,
+        #[serde(default)]
+        deadline: Option<TurnDeadline>,
     },
     TurnResult {
         game_id: Uuid,
         turn: u32,
         result: CombatResult,
+        /// Clock for the next turn; `None` once the game is decided.
+        #[serde(default)]
+        deadline: Option<TurnDeadline>,
     },
     GameOver {
         game_id: Uuid,
         winner: Option<Uuid>,
+        /// Rule that decided the game (`forfeit`, `elimination`, …); `None` on a draw.
+        #[serde(default)]
+        rule: Option<String>,
+        /// Every rule of the mode’s set with its (p1, p2) score.
+        #[serde(default)]
+        scores: Vec<RuleScore>,
+        /// Loot each seat received (already in inventory and ledger).
+        #[serde(default)]
+        rewards: Vec<MatchReward>,
+    },
+
+    /// A client message was refused; nothing changed server-side.
+    Error {
+        code: ErrorCode,
+        message: String,
+        /// Echo of the offending message’s `request_id`, if it had one.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
     },
 
     /// New: real-time faction chat
@@ -56,4 +153,24 @@ pub enum ServerMsg {
         content: String,
         ts: DateTime<Utc>,
     },
+
+    /// A trade offer (new or counter) is waiting for the recipient.
+    TradeOffered { offer: TradeOffer },
+    /// An offer the player is party to left the `open` state.
+    TradeClosed { offer_id: Uuid, status: TradeStatus },
+
+    /// A craft job finished and can be claimed.
+    CraftReady { job_id: Uuid, recipe_id: i32 },
+
+    /// The player’s faction is under attack and may respond until
+    /// `contest.respond_by`.
+    ContestDeclared { contest: Contest },
+    /// The player is a champion in a contest duel; join `game_id` as usual.
+    ContestDuel {
+        contest_id: Uuid,
+        game_id: Uuid,
+        opponent_id: Uuid,
+    },
+    /// A contest the player’s faction was in has been settled.
+    ContestResolved { contest: Contest },
 }
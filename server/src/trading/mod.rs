@@ -0,0 +1,56 @@
+//! Trade-offer notifications and the expiry sweeper.
+//
+//  Redis channels
+//  --------------
+//  player:<player_id>:events – `ServerMsg::TradeOffered` / `TradeClosed`
+
+use redis::{AsyncCommands, Client as RedisClient};
+use sqlx::PgPool;
+use tokio::time::{sleep, Duration};
+use uuid::Uuid;
+
+use crate::{db::trade_repo, protocol::ServerMsg};
+
+/// Best-effort push to one player's private channel.
+pub async fn notify(redis: &RedisClient, player: Uuid, msg: &ServerMsg) {
+    if let Ok(mut conn) = redis.get_multiplexed_async_connection().await {
+        let _: () = conn
+            .publish(
+                format!("player:{player}:events"),
+                serde_json::to_string(msg).unwrap(),
+            )
+            .await
+            .unwrap_or(());
+    }
+}
+
+/// Tell both parties that `offer` is no longer open.
+pub async fn notify_closed(redis: &RedisClient, offer: &trade_repo::TradeOffer) {
+    let msg = ServerMsg::TradeClosed {
+        offer_id: offer.id,
+        status: offer.status,
+    };
+    notify(redis, offer.proposer_id, &msg).await;
+    notify(redis, offer.recipient_id, &msg).await;
+}
+
+async fn tick(db: &PgPool, redis: &RedisClient) {
+    match trade_repo::expire_due(db).await {
+        Ok(expired) => {
+            for offer in &expired {
+                notify_closed(redis, offer).await;
+            }
+        }
+        Err(e) => log::error!("trade expiry sweep failed: {e:?}"),
+    }
+}
+
+/// Spawn the loop that refunds and closes expired offers.
+pub fn start(db: PgPool, redis: RedisClient) {
+    tokio::spawn(async move {
+        loop {
+            tick(&db, &redis).await;
+            sleep(Duration::from_secs(30)).await;
+        }
+    });
+}
@@ -1,15 +1,35 @@
 //! WebSocket endpoint with Redis event subscription.
 
-use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_web::{http::header, web, Error, HttpRequest, HttpResponse};
 use actix_ws::{handle, Message};
 use futures::StreamExt;
 use redis::{AsyncCommands, Client as RedisClient};
 use sqlx::PgPool;
+use tokio::time::{Duration, Instant};
 use uuid::Uuid;
 
 use crate::config::settings;
 use crate::game::session::dispatch;
-use crate::protocol::ClientMsg;
+use crate::http::auth::{JwtAuth, WS_SUBPROTOCOL};
+use crate::protocol::{ClientMsg, ErrorCode, ServerMsg};
+
+/// `request_id` of a frame that failed to parse as `ClientMsg`, if any.
+fn raw_request_id(text: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(text)
+        .ok()?
+        .get("request_id")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn error_frame(code: ErrorCode, message: impl Into<String>, request_id: Option<String>) -> String {
+    serde_json::to_string(&ServerMsg::Error {
+        code,
+        message: message.into(),
+        request_id,
+    })
+    .unwrap()
+}
 
 pub async fn ws_index(
     req: HttpRequest,
@@ -17,17 +37,22 @@ pub async fn ws_index(
     db_pool: web::Data<PgPool>,
     redis: web::Data<RedisClient>,
 ) -> Result<HttpResponse, Error> {
-    // 1 · player_id query param
-    let pid_str = req
-        .query_string()
-        .split('&')
-        .find_map(|kv| kv.strip_prefix("player_id="))
-        .ok_or_else(|| actix_web::error::ErrorBadRequest("player_id missing"))?;
-    let player_id =
-        Uuid::parse_str(pid_str).map_err(|_| actix_web::error::ErrorBadRequest("bad UUID"))?;
+    // 1 · JWT (query param or subprotocol) → player_id
+    let player_id = JwtAuth::from_ws_request(&req)?.player_id;
 
-    // 2 · handshake
-    let (response, mut session, mut ws_stream) = handle(&req, body)?;
+    // 2 · handshake (echo the subprotocol if the token came that way)
+    let (mut response, mut session, mut ws_stream) = handle(&req, body)?;
+    let offered_bearer = req
+        .headers()
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').next().map(str::trim) == Some(WS_SUBPROTOCOL));
+    if offered_bearer {
+        response.headers_mut().insert(
+            header::SEC_WEBSOCKET_PROTOCOL,
+            header::HeaderValue::from_static(WS_SUBPROTOCOL),
+        );
+    }
 
     // 3 · presence key
     {
@@ -73,21 +98,45 @@ pub async fn ws_index(
     actix::spawn(async move {
         let mut redis_stream = pubsub.on_message();
         let mut current_game: Option<Uuid> = None;
+        let mut window_start = Instant::now();
+        let mut frames_in_window = 0_u32;
 
         loop {
             tokio::select! {
                 // client → server
                 Some(frame) = ws_stream.next() => {
                     if let Ok(Message::Text(text)) = frame {
-                        if let Ok(cmsg) = serde_json::from_str::<ClientMsg>(&text) {
-                            match &cmsg {
-                                ClientMsg::Ready { game_id, .. }
-                                | ClientMsg::Resume { game_id, .. }
-                                | ClientMsg::Turn  { game_id, .. } => current_game = Some(*game_id),
-                                _ => {}
+                        // fixed one-second window per socket
+                        if window_start.elapsed() >= Duration::from_secs(1) {
+                            window_start = Instant::now();
+                            frames_in_window = 0;
+                        }
+                        frames_in_window += 1;
+                        let reply = if frames_in_window > settings().ws_rate_limit {
+                            Some(error_frame(ErrorCode::RateLimited, "too many messages", raw_request_id(&text)))
+                        } else {
+                            match serde_json::from_str::<ClientMsg>(&text) {
+                                Err(e) => Some(error_frame(ErrorCode::ParseError, e.to_string(), raw_request_id(&text))),
+                                Ok(cmsg) => {
+                                    let gid = cmsg.game_id();
+                                    let request_id = cmsg.request_id().map(str::to_string);
+                                    match dispatch(db.clone(), redis_client.clone(), player_id, cmsg).await {
+                                        Ok(()) => {
+                                            current_game = Some(gid);
+                                            None
+                                        }
+                                        Err(e) => {
+                                            log::warn!("dispatch error for {player_id}: {e}");
+                                            Some(error_frame(e.code(), e.to_string(), request_id))
+                                        }
+                                    }
+                                }
                             }
-                            if let Err(e) = dispatch(db.clone(), redis_client.clone(), cmsg).await {
-                                log::warn!("dispatch error: {e:?}");
+                        };
+                        if let Some(json) = reply {
+                            if let Err(e) = session.text(json).await {
+                                log::warn!("WS send failed for {player_id}: {e:?}");
+                                break;
                             }
                         }
                     }
@@ -113,6 +162,7 @@ pub async fn ws_index(
             let _ = dispatch(
                 db.clone(),
                 redis_client.clone(),
+                player_id,
                 ClientMsg::Disconnected {
                     game_id: gid,
                     player_id,
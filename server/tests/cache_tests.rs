@@ -1,6 +1,7 @@
 // tests/cache_tests.rs
 
-use biotonic_server::cache::{warm_all, get_item, ITEMS};
+use biotonic_server::cache::{get_item, get_unit_def, warm_all, warm_unit_defs, ITEMS};
+use biotonic_server::game::types::{Ability, UnitType};
 use dotenvy::dotenv;
 use sqlx::PgPool;
 
@@ -68,3 +69,27 @@ async fn test_get_missing_item() {
     let missing = get_item(-9999);
     assert!(missing.is_none(), "get_item should return None for missing id");
 }
+
+#[tokio::test]
+async fn test_warm_unit_defs_loads_seed_archetypes() {
+    dotenv().ok();
+
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL must be set in .env for tests");
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("DB connection failed");
+
+    let loaded = warm_unit_defs(&pool).await.expect("warm unit_defs");
+    assert!(loaded >= 4, "seed rows from migration 0020 should load");
+
+    let heavy = get_unit_def(&UnitType::HEAVY).expect("Heavy is seeded");
+    assert_eq!((heavy.atk, heavy.hp), (3, 3));
+    assert_eq!(heavy.cost.biomass, 3);
+
+    let ranged = get_unit_def(&UnitType::RANGED).expect("Ranged is seeded");
+    assert_eq!(ranged.abilities, vec![Ability::Volley]);
+
+    let seeder = get_unit_def(&UnitType::SEEDER).expect("Seeder is seeded");
+    assert_eq!((seeder.income.energy, seeder.income.biomass), (1, 1));
+}
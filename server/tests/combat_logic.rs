@@ -3,98 +3,443 @@
 //! Run with `cargo test -p biotonic-server --tests`.
 
 use biotonic_server::game::{
-    logic::resolve_turn,
-    types::{ResourcePool, TurnAction, Unit, UnitType},
+    logic::{resolve_turn, CombatEvent, RejectReason},
+    rng::CombatRng,
+    types::{Ability, BattleState, Position, ResourcePool, TurnAction, Unit, UnitType},
 };
 use uuid::Uuid;
 
-fn starter_pool() -> ResourcePool {
-    ResourcePool {
-        energy: 5,
-        biomass: 5,
-        gene_seeds: 2,
+mod common;
+
+fn battle() -> BattleState {
+    common::seed_unit_defs();
+    let mut state = BattleState::new(CombatRng::new(7));
+    state.p1 = Uuid::new_v4();
+    state.p2 = Uuid::new_v4();
+    state
+}
+
+fn play(unit_type: UnitType, lane: u8, row: u8) -> TurnAction {
+    TurnAction::PlayUnit {
+        unit_type,
+        pos: Position { lane, row },
     }
 }
 
+/// Run a deployment turn and return the server-minted ids per side,
+/// in the order the units were requested.
+fn deploy(
+    state: &mut BattleState,
+    p1: Vec<TurnAction>,
+    p2: Vec<TurnAction>,
+) -> (Vec<Uuid>, Vec<Uuid>) {
+    let res = resolve_turn(state, p1.clone(), p2.clone());
+    assert!(
+        res.rejected.is_empty(),
+        "deploy rejected: {:?}",
+        res.rejected
+    );
+    let ids = |units: &[Unit], wanted: &[TurnAction]| {
+        wanted
+            .iter()
+            .map(|a| match a {
+                TurnAction::PlayUnit { pos, .. } => {
+                    units.iter().find(|u| u.pos == *pos).unwrap().id
+                }
+                _ => unreachable!(),
+            })
+            .collect()
+    };
+    (ids(&state.units_p1, &p1), ids(&state.units_p2, &p2))
+}
+
 #[test]
 fn spawn_unit_pays_cost_and_adds_to_field() {
-    let mut pool1 = starter_pool();
-    let mut pool2 = starter_pool();
-    let mut units1 = Vec::new();
-    let mut units2 = Vec::new();
-
-    let light = Unit {
-        id: Uuid::new_v4(),
-        unit_type: UnitType::Light,
-        owner_id: Uuid::nil(),
-        hp: 0, // filled in by server on spawn
-    };
+    let mut state = battle();
 
     let res = resolve_turn(
-        vec![TurnAction::PlayUnit {
-            unit: light.clone(),
-        }],
+        &mut state,
+        vec![play(UnitType::LIGHT, 0, 0)],
         vec![TurnAction::Pass],
-        &mut pool1,
-        &mut pool2,
-        &mut units1,
-        &mut units2,
     );
 
     // Exactly one unit spawned and present on the field
     assert_eq!(res.spawned.len(), 1);
-    assert!(units1.iter().any(|u| u.id == light.id));
+    assert!(state.units_p1.iter().any(|u| u.id == res.spawned[0].id));
+
+    // Light costs 1 energy → pool has 4 before end-of-turn income
+    assert_eq!(state.pool_p1.energy, 4 + res.income_p1.energy);
+}
+
+#[test]
+fn server_mints_id_and_owner_from_seat() {
+    let mut state = battle();
+
+    let res = resolve_turn(
+        &mut state,
+        vec![play(UnitType::LIGHT, 0, 0)],
+        vec![play(UnitType::LIGHT, 0, 5)],
+    );
 
-    // Light costs 1 energy → pool now has 4
-    assert_eq!(pool1.energy, 4);
+    let mine = &state.units_p1[0];
+    let theirs = &state.units_p2[0];
+    assert_eq!(mine.owner_id, state.p1);
+    assert_eq!(theirs.owner_id, state.p2);
+    assert_ne!(mine.id, theirs.id);
+    assert!(!mine.id.is_nil());
+    assert_eq!(res.spawned.len(), 2);
+}
+
+#[test]
+fn spawn_outside_deploy_zone_is_rejected() {
+    let mut state = battle();
+    // Row 5 is p2's half of the default 6-row board.
+    let res = resolve_turn(
+        &mut state,
+        vec![play(UnitType::LIGHT, 0, 5)],
+        vec![TurnAction::Pass],
+    );
+
+    assert!(res.spawned.is_empty());
+    assert_eq!(res.rejected[0].reason, RejectReason::OutsideDeployZone);
+    assert_eq!(state.pool_p1.energy, 5 + res.income_p1.energy);
 }
 
 #[test]
 fn heavy_attack_destroys_light_unit() {
-    let mut pool1 = starter_pool();
-    let mut pool2 = starter_pool();
-    let mut units1 = Vec::new();
-    let mut units2 = Vec::new();
-
-    // Turn 0 – both players spawn a unit
-    let heavy = Unit {
-        id: Uuid::new_v4(),
-        unit_type: UnitType::Heavy,
-        owner_id: Uuid::nil(),
-        hp: 0,
-    };
-    let light = Unit {
-        id: Uuid::new_v4(),
-        unit_type: UnitType::Light,
-        owner_id: Uuid::nil(),
-        hp: 0,
-    };
-    resolve_turn(
-        vec![TurnAction::PlayUnit {
-            unit: heavy.clone(),
+    let mut state = battle();
+
+    // Turn 0 – both players spawn a unit on their front rows
+    let (p1, p2) = deploy(
+        &mut state,
+        vec![play(UnitType::HEAVY, 0, 2)],
+        vec![play(UnitType::LIGHT, 0, 3)],
+    );
+    let (heavy, light) = (p1[0], p2[0]);
+
+    // Turn 1 – heavy one-shots the light
+    let res = resolve_turn(
+        &mut state,
+        vec![TurnAction::Attack {
+            attacker_id: heavy,
+            defender_id: light,
         }],
-        vec![TurnAction::PlayUnit {
-            unit: light.clone(),
+        vec![TurnAction::Pass],
+    );
+
+    assert!(res.destroyed.contains(&light));
+    assert!(!state.units_p2.iter().any(|u| u.id == light));
+    assert_eq!(
+        res.outcomes[0].events,
+        vec![
+            CombatEvent::Damage {
+                source_id: heavy,
+                target_id: light,
+                amount: 3,
+                remaining_hp: 0,
+            },
+            CombatEvent::Destroyed { unit_id: light },
+        ]
+    );
+}
+
+#[test]
+fn attack_with_opponents_unit_is_rejected() {
+    let mut state = battle();
+    let (p1, p2) = deploy(
+        &mut state,
+        vec![play(UnitType::LIGHT, 0, 2)],
+        vec![play(UnitType::HEAVY, 0, 3)],
+    );
+
+    // p1 tries to make p2's heavy hit p1's own light.
+    let res = resolve_turn(
+        &mut state,
+        vec![TurnAction::Attack {
+            attacker_id: p2[0],
+            defender_id: p1[0],
         }],
-        &mut pool1,
-        &mut pool2,
-        &mut units1,
-        &mut units2,
+        vec![],
+    );
+
+    assert!(res.destroyed.is_empty());
+    assert_eq!(res.rejected.len(), 1);
+    assert_eq!(res.rejected[0].reason, RejectReason::NotYourUnit);
+    assert_eq!(state.units_p1.len(), 1);
+}
+
+#[test]
+fn dead_unit_loses_its_action() {
+    let mut state = battle();
+    // Light (speed 3) kills the Ranged (speed 2) before it can shoot back.
+    let (p1, p2) = deploy(
+        &mut state,
+        vec![play(UnitType::LIGHT, 2, 2)],
+        vec![play(UnitType::RANGED, 2, 3)],
     );
 
-    // Turn 1 – heavy one-shots the light
     let res = resolve_turn(
+        &mut state,
         vec![TurnAction::Attack {
-            attacker_id: heavy.id,
-            defender_id: light.id,
+            attacker_id: p1[0],
+            defender_id: p2[0],
+        }],
+        vec![TurnAction::Attack {
+            attacker_id: p2[0],
+            defender_id: p1[0],
+        }],
+    );
+
+    assert_eq!(res.destroyed, vec![p2[0]]);
+    assert_eq!(res.rejected[0].reason, RejectReason::UnitDestroyed);
+    assert_eq!(state.units_p1.len(), 1);
+}
+
+#[test]
+fn attack_out_of_range_is_rejected() {
+    let mut state = battle();
+    let (p1, p2) = deploy(
+        &mut state,
+        vec![play(UnitType::HEAVY, 0, 0)],
+        vec![play(UnitType::LIGHT, 0, 5)],
+    );
+
+    let res = resolve_turn(
+        &mut state,
+        vec![TurnAction::Attack {
+            attacker_id: p1[0],
+            defender_id: p2[0],
         }],
         vec![TurnAction::Pass],
-        &mut pool1,
-        &mut pool2,
-        &mut units1,
-        &mut units2,
     );
 
-    assert!(res.destroyed.contains(&light.id));
-    assert!(!units2.iter().any(|u| u.id == light.id));
+    assert!(res.destroyed.is_empty());
+    assert_eq!(res.rejected[0].reason, RejectReason::OutOfRange);
+    assert_eq!(state.units_p2[0].hp, 1);
+}
+
+#[test]
+fn move_respects_speed_and_reports_event() {
+    let mut state = battle();
+    let (p1, _) = deploy(&mut state, vec![play(UnitType::HEAVY, 1, 0)], vec![]);
+    let heavy = p1[0];
+    let start = Position { lane: 1, row: 0 };
+
+    // Heavy has speed 1: two cells is too far.
+    let res = resolve_turn(
+        &mut state,
+        vec![TurnAction::Move {
+            unit_id: heavy,
+            to: Position { lane: 1, row: 2 },
+        }],
+        vec![],
+    );
+    assert_eq!(res.rejected[0].reason, RejectReason::TooFar);
+    assert_eq!(state.units_p1[0].pos, start);
+
+    let near = Position { lane: 1, row: 1 };
+    let res = resolve_turn(
+        &mut state,
+        vec![TurnAction::Move {
+            unit_id: heavy,
+            to: near,
+        }],
+        vec![],
+    );
+    assert_eq!(state.units_p1[0].pos, near);
+    assert_eq!(
+        res.outcomes[0].events,
+        vec![CombatEvent::Moved {
+            unit_id: heavy,
+            from: start,
+            to: near,
+        }]
+    );
+}
+
+#[test]
+fn unit_acts_once_per_turn() {
+    let mut state = battle();
+    let (p1, _) = deploy(&mut state, vec![play(UnitType::LIGHT, 0, 0)], vec![]);
+
+    let res = resolve_turn(
+        &mut state,
+        vec![
+            TurnAction::Move {
+                unit_id: p1[0],
+                to: Position { lane: 0, row: 1 },
+            },
+            TurnAction::Move {
+                unit_id: p1[0],
+                to: Position { lane: 0, row: 2 },
+            },
+        ],
+        vec![],
+    );
+
+    assert_eq!(res.outcomes.len(), 1);
+    assert_eq!(res.rejected[0].reason, RejectReason::AlreadyActed);
+}
+
+#[test]
+fn volley_hits_every_enemy_in_lane() {
+    let mut state = battle();
+    let (_, p2) = deploy(
+        &mut state,
+        vec![play(UnitType::RANGED, 0, 1)],
+        vec![
+            play(UnitType::HEAVY, 0, 3),
+            play(UnitType::LIGHT, 0, 4),
+            play(UnitType::LIGHT, 1, 3),
+        ],
+    );
+    let ranged = state.units_p1[0].id;
+    let (heavy, light, off_lane) = (p2[0], p2[1], p2[2]);
+
+    let res = resolve_turn(
+        &mut state,
+        vec![TurnAction::UseAbility {
+            unit_id: ranged,
+            ability: Ability::Volley,
+            target_id: None,
+        }],
+        vec![],
+    );
+
+    assert_eq!(res.destroyed, vec![light]);
+    assert_eq!(state.units_p2.iter().find(|u| u.id == heavy).unwrap().hp, 2);
+    assert!(state.units_p2.iter().any(|u| u.id == off_lane));
+}
+
+#[test]
+fn ability_not_in_catalogue_is_rejected() {
+    let mut state = battle();
+    let (p1, _) = deploy(&mut state, vec![play(UnitType::HEAVY, 0, 0)], vec![]);
+
+    let res = resolve_turn(
+        &mut state,
+        vec![TurnAction::UseAbility {
+            unit_id: p1[0],
+            ability: Ability::Volley,
+            target_id: None,
+        }],
+        vec![],
+    );
+
+    assert_eq!(res.rejected[0].reason, RejectReason::AbilityUnavailable);
+}
+
+#[test]
+fn same_seed_and_inputs_replay_identically() {
+    common::seed_unit_defs();
+    let replay = || {
+        let mut state = BattleState::new(CombatRng::new(1234));
+        let mut results = vec![serde_json::to_string(&resolve_turn(
+            &mut state,
+            vec![play(UnitType::LIGHT, 0, 2), play(UnitType::RANGED, 1, 2)],
+            vec![play(UnitType::LIGHT, 0, 3), play(UnitType::LIGHT, 1, 3)],
+        ))
+        .unwrap()];
+        // Ids are minted from the seeded RNG, so they match across replays too.
+        let (light, ranged) = (state.units_p1[0].id, state.units_p1[1].id);
+        let (foe_a, foe_b) = (state.units_p2[0].id, state.units_p2[1].id);
+        results.push(
+            serde_json::to_string(&resolve_turn(
+                &mut state,
+                vec![TurnAction::Attack {
+                    attacker_id: light,
+                    defender_id: foe_a,
+                }],
+                vec![
+                    TurnAction::Attack {
+                        attacker_id: foe_a,
+                        defender_id: light,
+                    },
+                    TurnAction::Attack {
+                        attacker_id: foe_b,
+                        defender_id: ranged,
+                    },
+                ],
+            ))
+            .unwrap(),
+        );
+        (results, serde_json::to_string(&state).unwrap())
+    };
+
+    assert_eq!(replay(), replay());
+}
+
+#[test]
+fn unit_type_missing_from_catalogue_cannot_spawn() {
+    let mut state = battle();
+    let res = resolve_turn(
+        &mut state,
+        vec![play(UnitType::new("Retired"), 0, 0)],
+        vec![],
+    );
+
+    assert!(res.spawned.is_empty());
+    assert_eq!(res.rejected[0].reason, RejectReason::UnknownUnitType);
+    assert!(state.units_p1.is_empty());
+}
+
+#[test]
+fn living_seeders_add_to_base_income() {
+    let mut state = battle();
+    let first = resolve_turn(&mut state, vec![play(UnitType::SEEDER, 0, 0)], vec![]);
+    // Seeder yields +1 energy / +1 biomass on top of the base trickle.
+    assert_eq!(first.income_p2, state.base_income);
+    assert_eq!(first.income_p1.energy, state.base_income.energy + 1);
+    assert_eq!(first.income_p1.biomass, state.base_income.biomass + 1);
+
+    let before = state.pool_p1.clone();
+    let res = resolve_turn(&mut state, vec![TurnAction::Pass], vec![]);
+    assert_eq!(state.pool_p1.energy, before.energy + res.income_p1.energy);
+    assert_eq!(res.income_p1.biomass, state.base_income.biomass + 1);
+}
+
+#[test]
+fn destroyed_seeder_yields_nothing() {
+    let mut state = battle();
+    let (p1, p2) = deploy(
+        &mut state,
+        vec![play(UnitType::SEEDER, 0, 2)],
+        vec![play(UnitType::HEAVY, 0, 3)],
+    );
+
+    let res = resolve_turn(
+        &mut state,
+        vec![],
+        vec![TurnAction::Attack {
+            attacker_id: p2[0],
+            defender_id: p1[0],
+        }],
+    );
+
+    assert!(res.destroyed.contains(&p1[0]));
+    assert_eq!(res.income_p1, state.base_income);
+}
+
+#[test]
+fn snapshot_without_income_restores_default_income() {
+    let state = battle();
+    let mut json = serde_json::to_value(&state).unwrap();
+    json.as_object_mut().unwrap().remove("base_income");
+    let restored: BattleState = serde_json::from_value(json).unwrap();
+    assert_eq!(restored.base_income, state.base_income);
+}
+
+#[test]
+fn pool_income_saturates() {
+    let mut pool = ResourcePool {
+        energy: u32::MAX - 1,
+        ..ResourcePool::default()
+    };
+    pool.add(&ResourcePool {
+        energy: 5,
+        biomass: 1,
+        gene_seeds: 0,
+    });
+    assert_eq!(pool.energy, u32::MAX);
+    assert_eq!(pool.biomass, 1);
 }
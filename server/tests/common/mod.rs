@@ -0,0 +1,259 @@
+//! Shared fixtures for the integration tests.
+//!
+//! Each test binary pulls in the whole module and uses only part of it.
+
+#![allow(dead_code)]
+
+use biotonic_server::{
+    cache::{UnitCost, UnitDef, UNIT_DEFS},
+    db::{
+        ledger_repo::{self, Account, Reason, SystemAccount},
+        treasury_repo::{self, Resources, TreasuryReason},
+    },
+    game::types::{Ability, ResourcePool, UnitType},
+};
+use chrono::Utc;
+use dotenvy::dotenv;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub async fn pool() -> PgPool {
+    dotenv().ok();
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set in .env for tests");
+    PgPool::connect(&url).await.expect("DB connection failed")
+}
+
+/// Like [`pool`], for suites that drive handlers behind `JwtAuth`: tokens
+/// from [`bearer`] must be signed with the same `secret`.
+pub async fn pool_with_jwt(secret: &str) -> PgPool {
+    std::env::set_var("JWT_SECRET", secret);
+    pool().await
+}
+
+/// Fresh user + player holding `credits`; returns (user_id, player_id).
+pub async fn account(db: &PgPool, credits: i64) -> (Uuid, Uuid) {
+    let tag = Uuid::new_v4();
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email) VALUES ($1) RETURNING id")
+        .bind(format!("{tag}@test.local"))
+        .fetch_one(db)
+        .await
+        .unwrap();
+    let player_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO players (user_id, nickname, credits) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(tag.to_string())
+    .bind(credits)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    (user_id, player_id)
+}
+
+/// Fresh player with no credits.
+pub async fn player(db: &PgPool) -> Uuid {
+    player_with_credits(db, 0).await
+}
+
+pub async fn player_with_credits(db: &PgPool, credits: i64) -> Uuid {
+    account(db, credits).await.1
+}
+
+/// `Authorization` header value for `player`, valid for ten minutes.
+pub fn bearer(secret: &str, user_id: Uuid, player_id: Uuid) -> String {
+    let claims = json!({
+        "sub": user_id.to_string(),
+        "pid": player_id.to_string(),
+        "exp": Utc::now().timestamp() as usize + 600,
+    });
+    let jwt = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap();
+    format!("Bearer {jwt}")
+}
+
+/// Fresh player holding `credits`; returns (player_id, bearer header).
+pub async fn authed_player(db: &PgPool, secret: &str, credits: i64) -> (Uuid, String) {
+    let (user_id, player_id) = account(db, credits).await;
+    (player_id, bearer(secret, user_id, player_id))
+}
+
+/// Credit `amount` through the ledger, so the cached balance reconciles.
+pub async fn grant(db: &PgPool, pid: Uuid, amount: i64) {
+    let mut tx = db.begin().await.unwrap();
+    ledger_repo::transfer(
+        &mut tx,
+        Account::System(SystemAccount::Admin),
+        Account::Player(pid),
+        amount,
+        Reason::Admin,
+        Some("test-grant"),
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+}
+
+pub async fn credits(db: &PgPool, pid: Uuid) -> i64 {
+    sqlx::query_scalar("SELECT credits FROM players WHERE id = $1")
+        .bind(pid)
+        .fetch_one(db)
+        .await
+        .unwrap()
+}
+
+/// Fresh item with a base price of 1.
+pub async fn item(db: &PgPool) -> i32 {
+    priced_item(db, 1).await
+}
+
+pub async fn priced_item(db: &PgPool, base_price: i32) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO items (name, description, base_price) VALUES ($1, NULL, $2) RETURNING id",
+    )
+    .bind(format!("test-item-{}", Uuid::new_v4()))
+    .bind(base_price)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+pub async fn give(db: &PgPool, pid: Uuid, item_id: i32, qty: i32) {
+    sqlx::query("INSERT INTO player_items (player_id, item_id, quantity) VALUES ($1, $2, $3)")
+        .bind(pid)
+        .bind(item_id)
+        .bind(qty)
+        .execute(db)
+        .await
+        .unwrap();
+}
+
+/// How many of `item_id` the player holds; 0 without a row.
+pub async fn held(db: &PgPool, pid: Uuid, item_id: i32) -> i32 {
+    sqlx::query_scalar("SELECT quantity FROM player_items WHERE player_id = $1 AND item_id = $2")
+        .bind(pid)
+        .bind(item_id)
+        .fetch_optional(db)
+        .await
+        .unwrap()
+        .unwrap_or(0)
+}
+
+/// An in-progress game with `p1` in the first seat.
+pub async fn game(db: &PgPool, p1: Uuid) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO games (player1_id, state) VALUES ($1, 'InProgress') RETURNING id",
+    )
+    .bind(p1)
+    .fetch_one(db)
+    .await
+    .unwrap()
+}
+
+/// Fresh faction with no members.
+pub async fn faction(db: &PgPool) -> Uuid {
+    sqlx::query_scalar("INSERT INTO factions (name) VALUES ($1) RETURNING id")
+        .bind(format!("test-faction-{}", Uuid::new_v4()))
+        .fetch_one(db)
+        .await
+        .unwrap()
+}
+
+pub async fn join(db: &PgPool, faction: Uuid, player: Uuid, role: &str) {
+    sqlx::query("INSERT INTO faction_members (faction_id, player_id, role) VALUES ($1, $2, $3)")
+        .bind(faction)
+        .bind(player)
+        .bind(role)
+        .execute(db)
+        .await
+        .unwrap();
+}
+
+/// Deposit `energy` into the faction treasury.
+pub async fn fund(db: &PgPool, faction: Uuid, energy: i64) {
+    let mut conn = db.acquire().await.unwrap();
+    treasury_repo::deposit(
+        &mut conn,
+        faction,
+        &Resources {
+            energy,
+            ..Resources::default()
+        },
+        TreasuryReason::Admin,
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+}
+
+fn cost(energy: u32, biomass: u32, gene_seeds: u32) -> UnitCost {
+    UnitCost {
+        energy,
+        biomass,
+        gene_seeds,
+    }
+}
+
+/// Install the seed archetypes from migrations 0020/0021 into the unit
+/// catalogue cache, so combat tests run without a database.
+pub fn seed_unit_defs() {
+    let defs = [
+        UnitDef {
+            id: UnitType::LIGHT,
+            cost: cost(1, 0, 0),
+            atk: 1,
+            hp: 1,
+            speed: 3,
+            range: 1,
+            abilities: vec![],
+            income: ResourcePool::default(),
+            requires_unlock: false,
+        },
+        UnitDef {
+            id: UnitType::RANGED,
+            cost: cost(2, 1, 0),
+            atk: 2,
+            hp: 1,
+            speed: 2,
+            range: 3,
+            abilities: vec![Ability::Volley],
+            income: ResourcePool::default(),
+            requires_unlock: false,
+        },
+        UnitDef {
+            id: UnitType::HEAVY,
+            cost: cost(0, 3, 0),
+            atk: 3,
+            hp: 3,
+            speed: 1,
+            range: 1,
+            abilities: vec![],
+            income: ResourcePool::default(),
+            requires_unlock: false,
+        },
+        UnitDef {
+            id: UnitType::SEEDER,
+            cost: cost(1, 0, 1),
+            atk: 0,
+            hp: 2,
+            speed: 1,
+            range: 1,
+            abilities: vec![Ability::Regrow],
+            income: ResourcePool {
+                energy: 1,
+                biomass: 1,
+                gene_seeds: 0,
+            },
+            requires_unlock: false,
+        },
+    ];
+    for def in defs {
+        UNIT_DEFS.insert(def.id.clone(), def);
+    }
+}
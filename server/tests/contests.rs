@@ -0,0 +1,440 @@
+//! Territory contests: declaration rules, strength checks, champion duels
+//! and the ownership history they leave behind.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use actix_web::{http::StatusCode, test, web, App};
+use biotonic_server::{
+    db::{
+        contest_repo::{self, ContestMethod, ContestStatus, Declaration},
+        land_repo, treasury_repo,
+    },
+    ecosystem::{
+        model::Biome,
+        territory::{attack_strength, attacker_wins, defense_strength, PayWith},
+        worldgen::Tile,
+    },
+    game::session::dispatch,
+    http::contests,
+    protocol::ClientMsg,
+};
+use chrono::Duration;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+
+use common::{authed_player, faction, fund, join, player, pool, pool_with_jwt};
+
+const SECRET: &str = "contests-test-secret";
+
+/// A faction with its leader.
+async fn led_faction(db: &PgPool) -> (Uuid, Uuid) {
+    let id = faction(db).await;
+    let leader = player(db).await;
+    join(db, id, leader, "leader").await;
+    (id, leader)
+}
+
+async fn owner(db: &PgPool, x: i32, y: i32) -> Option<Uuid> {
+    land_repo::owner_faction_for_tile(db, x, y).await.unwrap()
+}
+
+/// A private corner of the plane, far off the generated map, so runs never
+/// collide on tiles.
+fn origin() -> (i32, i32) {
+    let n = (Uuid::new_v4().as_u128() % 1_000_000) as i32;
+    (-1_000_000 - n * 10, 1_000_000 + n * 10)
+}
+
+fn tile(x: i32, y: i32) -> Tile {
+    Tile {
+        x,
+        y,
+        biome: Biome::Grassland,
+        rich: false,
+    }
+}
+
+/// Attacker holds `(x, y)`, defender holds `(x + 1, y)` next to it.
+struct Front {
+    attacker: Uuid,
+    attacker_leader: Uuid,
+    defender: Uuid,
+    defender_leader: Uuid,
+    x: i32,
+    y: i32,
+}
+
+impl Front {
+    fn attack(&self, energy: i64) -> Declaration {
+        Declaration {
+            faction_id: self.attacker,
+            x: self.x + 1,
+            y: self.y,
+            champion_id: self.attacker_leader,
+            energy,
+        }
+    }
+}
+
+async fn front(db: &PgPool) -> Front {
+    let (attacker, attacker_leader) = led_faction(db).await;
+    let (defender, defender_leader) = led_faction(db).await;
+    let (x, y) = origin();
+    land_repo::claim(
+        db,
+        &tile(x, y),
+        attacker,
+        attacker_leader,
+        PayWith::Treasury,
+    )
+    .await
+    .unwrap();
+    land_repo::claim(
+        db,
+        &tile(x + 1, y),
+        defender,
+        defender_leader,
+        PayWith::Treasury,
+    )
+    .await
+    .unwrap();
+    Front {
+        attacker,
+        attacker_leader,
+        defender,
+        defender_leader,
+        x,
+        y,
+    }
+}
+
+fn window() -> Duration {
+    Duration::hours(1)
+}
+
+fn max_duel() -> Duration {
+    Duration::hours(1)
+}
+
+#[test]
+fn attacker_must_beat_defense_outright() {
+    let attack = attack_strength(40, 1);
+    assert_eq!(attack, 22.0);
+    assert_eq!(defense_strength(0.0, 0), 10.0);
+    assert!(attack_strength(40, 2) > attack);
+    assert!(defense_strength(25.0, 1) > defense_strength(0.0, 1));
+    assert!(attacker_wins(attack, 21.0));
+    assert!(!attacker_wins(attack, attack));
+    assert_eq!(attack_strength(-5, -1), 0.0);
+}
+
+#[tokio::test]
+async fn unanswered_strength_check_moves_parcel_and_history() {
+    let db = pool().await;
+    let f = front(&db).await;
+    fund(&db, f.attacker, 40).await;
+
+    let c = contest_repo::declare(&db, f.attacker_leader, &f.attack(40), window())
+        .await
+        .unwrap();
+    assert_eq!(c.status, ContestStatus::Open);
+    assert_eq!(c.defender_faction_id, f.defender);
+    let t = treasury_repo::get(&db, f.attacker, 1).await.unwrap();
+    assert_eq!(t.balance.energy, 0);
+
+    let c = contest_repo::respond(&db, c.id, f.defender_leader, None)
+        .await
+        .unwrap();
+    assert_eq!(c.status, ContestStatus::Resolved);
+    assert_eq!(c.method, Some(ContestMethod::Strength));
+    assert_eq!(c.winner_faction_id, Some(f.attacker));
+    assert_eq!(c.attack_strength, Some(22.0));
+    assert_eq!(c.defense_strength, Some(10.0));
+    assert_eq!(owner(&db, f.x + 1, f.y).await, Some(f.attacker));
+
+    let history = land_repo::history(&db, f.x + 1, f.y).await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].reason, "claim");
+    assert_eq!(history[1].reason, "contest");
+    assert_eq!(history[1].from_faction_id, Some(f.defender));
+    assert_eq!(history[1].to_faction_id, Some(f.attacker));
+    assert_eq!(history[1].contest_id, Some(c.id));
+}
+
+#[tokio::test]
+async fn defensive_structures_hold_the_parcel() {
+    let db = pool().await;
+    let f = front(&db).await;
+    fund(&db, f.attacker, 40).await;
+    sqlx::query(
+        "INSERT INTO structures (owner_faction_id, type, x, y) VALUES ($1, 'Bunker', $2, $3)",
+    )
+    .bind(f.defender)
+    .bind(f.x + 1)
+    .bind(f.y)
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let c = contest_repo::declare(&db, f.attacker_leader, &f.attack(40), window())
+        .await
+        .unwrap();
+    let c = contest_repo::respond(&db, c.id, f.defender_leader, None)
+        .await
+        .unwrap();
+    assert_eq!(c.winner_faction_id, Some(f.defender));
+    assert_eq!(c.defense_strength, Some(35.0));
+    assert_eq!(owner(&db, f.x + 1, f.y).await, Some(f.defender));
+    assert_eq!(
+        land_repo::history(&db, f.x + 1, f.y).await.unwrap().len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn declarations_are_checked() {
+    let db = pool().await;
+    let f = front(&db).await;
+    let declare = |player, d: Declaration| {
+        let db = db.clone();
+        async move {
+            contest_repo::declare(&db, player, &d, window())
+                .await
+                .unwrap_err()
+                .to_string()
+        }
+    };
+
+    let member = player(&db).await;
+    join(&db, f.attacker, member, "member").await;
+    assert!(declare(member, f.attack(40)).await.contains("officers"));
+
+    let mut own = f.attack(40);
+    own.x = f.x;
+    assert!(declare(f.attacker_leader, own).await.contains("own parcel"));
+
+    let (other, other_leader) = led_faction(&db).await;
+    land_repo::claim(
+        &db,
+        &tile(f.x + 2, f.y),
+        other,
+        other_leader,
+        PayWith::Treasury,
+    )
+    .await
+    .unwrap();
+    let mut far = f.attack(40);
+    far.x = f.x + 2;
+    assert!(declare(f.attacker_leader, far).await.contains("border"));
+
+    assert!(declare(f.attacker_leader, f.attack(1))
+        .await
+        .contains("at least"));
+    assert!(declare(f.attacker_leader, f.attack(40))
+        .await
+        .contains("insufficient"));
+
+    fund(&db, f.attacker, 80).await;
+    contest_repo::declare(&db, f.attacker_leader, &f.attack(40), window())
+        .await
+        .unwrap();
+    assert!(declare(f.attacker_leader, f.attack(40))
+        .await
+        .contains("already contested"));
+}
+
+#[tokio::test]
+async fn champion_duel_decides_the_contest() {
+    let db = pool().await;
+    let f = front(&db).await;
+    fund(&db, f.attacker, 10).await;
+    let c = contest_repo::declare(&db, f.attacker_leader, &f.attack(10), window())
+        .await
+        .unwrap();
+
+    let c = contest_repo::respond(&db, c.id, f.defender_leader, Some(f.defender_leader))
+        .await
+        .unwrap();
+    assert_eq!(c.status, ContestStatus::Dueling);
+    assert_eq!(c.method, Some(ContestMethod::Duel));
+    let game_id = c.game_id.unwrap();
+    let (state, mode): (String, String) =
+        sqlx::query_as("SELECT state, mode FROM games WHERE id = $1")
+            .bind(game_id)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+    assert_eq!((state.as_str(), mode.as_str()), ("Lobby", "siege"));
+
+    // A lobby inside the window is left alone.
+    let done = contest_repo::resolve_due(&db, window(), max_duel())
+        .await
+        .unwrap();
+    assert!(done.iter().all(|d| d.id != c.id));
+
+    // The attacker’s champion wins despite the weak attack.
+    sqlx::query("UPDATE games SET state = 'Finished', winner_id = $2 WHERE id = $1")
+        .bind(game_id)
+        .bind(f.attacker_leader)
+        .execute(&db)
+        .await
+        .unwrap();
+    let done = contest_repo::resolve_due(&db, window(), max_duel())
+        .await
+        .unwrap();
+    let resolved = done.iter().find(|d| d.id == c.id).unwrap();
+    assert_eq!(resolved.status, ContestStatus::Resolved);
+    assert_eq!(resolved.winner_faction_id, Some(f.attacker));
+    assert_eq!(owner(&db, f.x + 1, f.y).await, Some(f.attacker));
+}
+
+#[tokio::test]
+async fn a_finished_duel_survives_the_closing_socket() {
+    let db = pool().await;
+    let f = front(&db).await;
+    fund(&db, f.attacker, 10).await;
+    let c = contest_repo::declare(&db, f.attacker_leader, &f.attack(10), window())
+        .await
+        .unwrap();
+    let c = contest_repo::respond(&db, c.id, f.defender_leader, Some(f.defender_leader))
+        .await
+        .unwrap();
+    let game_id = c.game_id.unwrap();
+    sqlx::query("UPDATE games SET state = 'Finished', winner_id = $2 WHERE id = $1")
+        .bind(game_id)
+        .bind(f.attacker_leader)
+        .execute(&db)
+        .await
+        .unwrap();
+
+    // The winner’s socket closes after GameOver.
+    let redis = redis::Client::open("redis://127.0.0.1/").unwrap();
+    let msg = ClientMsg::Disconnected {
+        game_id,
+        player_id: f.attacker_leader,
+    };
+    dispatch(db.clone(), redis, f.attacker_leader, msg)
+        .await
+        .unwrap();
+    let state: String = sqlx::query_scalar("SELECT state FROM games WHERE id = $1")
+        .bind(game_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(state, "Finished");
+
+    let done = contest_repo::resolve_due(&db, window(), max_duel())
+        .await
+        .unwrap();
+    let resolved = done.iter().find(|d| d.id == c.id).unwrap();
+    assert_eq!(resolved.method, Some(ContestMethod::Duel));
+    assert_eq!(resolved.winner_faction_id, Some(f.attacker));
+    assert_eq!(owner(&db, f.x + 1, f.y).await, Some(f.attacker));
+}
+
+#[tokio::test]
+async fn duel_with_one_champion_is_settled_at_the_hard_deadline() {
+    let db = pool().await;
+    let f = front(&db).await;
+    fund(&db, f.attacker, 10).await;
+    let c = contest_repo::declare(&db, f.attacker_leader, &f.attack(10), window())
+        .await
+        .unwrap();
+    let c = contest_repo::respond(&db, c.id, f.defender_leader, Some(f.defender_leader))
+        .await
+        .unwrap();
+
+    // The attacker’s champion joins; the defender’s never does.
+    sqlx::query("UPDATE games SET state = 'InProgress' WHERE id = $1")
+        .bind(c.game_id.unwrap())
+        .execute(&db)
+        .await
+        .unwrap();
+    let done = contest_repo::resolve_due(&db, window(), max_duel())
+        .await
+        .unwrap();
+    assert!(done.iter().all(|d| d.id != c.id));
+
+    sqlx::query("UPDATE contests SET duel_started_at = NOW() - INTERVAL '3 hours' WHERE id = $1")
+        .bind(c.id)
+        .execute(&db)
+        .await
+        .unwrap();
+    let done = contest_repo::resolve_due(&db, window(), max_duel())
+        .await
+        .unwrap();
+    let resolved = done.iter().find(|d| d.id == c.id).unwrap();
+    assert_eq!(resolved.status, ContestStatus::Resolved);
+    assert_eq!(resolved.method, Some(ContestMethod::Strength));
+    assert_eq!(resolved.winner_faction_id, Some(f.defender));
+    assert_eq!(owner(&db, f.x + 1, f.y).await, Some(f.defender));
+}
+
+#[tokio::test]
+async fn late_answers_are_refused_and_settled_by_strength() {
+    let db = pool().await;
+    let f = front(&db).await;
+    fund(&db, f.attacker, 10).await;
+    let c = contest_repo::declare(&db, f.attacker_leader, &f.attack(10), Duration::zero())
+        .await
+        .unwrap();
+
+    let err = contest_repo::respond(&db, c.id, f.defender_leader, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("window closed"));
+
+    let done = contest_repo::resolve_due(&db, window(), max_duel())
+        .await
+        .unwrap();
+    let resolved = done.iter().find(|d| d.id == c.id).unwrap();
+    assert_eq!(resolved.method, Some(ContestMethod::Strength));
+    // 10 energy + one bordering parcel (7) does not beat the base defense.
+    assert_eq!(resolved.winner_faction_id, Some(f.defender));
+    assert_eq!(owner(&db, f.x + 1, f.y).await, Some(f.defender));
+}
+
+#[actix_web::test]
+async fn contests_are_visible_to_their_factions_only() {
+    let db = pool_with_jwt(SECRET).await;
+    let f = front(&db).await;
+    fund(&db, f.attacker, 40).await;
+    let c = contest_repo::declare(&db, f.attacker_leader, &f.attack(40), window())
+        .await
+        .unwrap();
+    let (member, member_token) = authed_player(&db, SECRET, 0).await;
+    join(&db, f.defender, member, "member").await;
+    let (_, outsider_token) = authed_player(&db, SECRET, 0).await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db.clone()))
+            .app_data(web::Data::new(
+                redis::Client::open("redis://127.0.0.1/").unwrap(),
+            ))
+            .configure(contests::init_routes),
+    )
+    .await;
+    let uris = [
+        format!("/contests/{}", c.id),
+        format!("/contests/faction/{}", f.defender),
+    ];
+    for uri in &uris {
+        let anonymous = test::TestRequest::get().uri(uri).to_request();
+        let res = test::call_service(&app, anonymous).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        for (token, expected) in [
+            (&outsider_token, StatusCode::FORBIDDEN),
+            (&member_token, StatusCode::OK),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", token.clone()))
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), expected);
+        }
+    }
+}
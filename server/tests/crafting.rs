@@ -0,0 +1,144 @@
+//! Crafting: inputs consumed on start, outputs on claim, structure
+//! requirements and the worker’s `ready` sweep.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::db::craft_repo::{self, CraftStatus};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+
+use common::{give, held, item, player, pool};
+
+/// Recipe: `inputs` → `outputs`, taking `secs`, optionally at `structure`.
+async fn recipe(
+    db: &PgPool,
+    inputs: &[(i32, i32)],
+    outputs: &[(i32, i32)],
+    secs: i32,
+    structure: Option<&str>,
+) -> i32 {
+    let id: i32 = sqlx::query_scalar(
+        "INSERT INTO recipes (name, required_structure, craft_secs)
+         VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(format!("recipe-{}", Uuid::new_v4()))
+    .bind(structure)
+    .bind(secs)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    for (table, list) in [("recipe_inputs", inputs), ("recipe_outputs", outputs)] {
+        for &(item_id, qty) in list {
+            sqlx::query(&format!(
+                "INSERT INTO {table} (recipe_id, item_id, qty) VALUES ($1, $2, $3)"
+            ))
+            .bind(id)
+            .bind(item_id)
+            .bind(qty)
+            .execute(db)
+            .await
+            .unwrap();
+        }
+    }
+    id
+}
+
+#[tokio::test]
+async fn instant_recipe_consumes_inputs_and_claims_outputs() {
+    let db = pool().await;
+    let alice = player(&db).await;
+    let (herb, ore, tonic) = (item(&db).await, item(&db).await, item(&db).await);
+    give(&db, alice, herb, 3).await;
+    give(&db, alice, ore, 1).await;
+    let r = recipe(&db, &[(herb, 2), (ore, 1)], &[(tonic, 1)], 0, None).await;
+
+    let job = craft_repo::start(&db, alice, r, None).await.unwrap();
+    assert_eq!(job.status, CraftStatus::Ready);
+    assert_eq!(held(&db, alice, herb).await, 1);
+    assert_eq!(held(&db, alice, ore).await, 0);
+
+    let claimed = craft_repo::claim(&db, alice, job.id).await.unwrap();
+    assert_eq!(claimed.job.status, CraftStatus::Claimed);
+    assert_eq!(held(&db, alice, tonic).await, 1);
+    assert!(craft_repo::claim(&db, alice, job.id).await.is_err());
+
+    // Not enough herbs for a second run: nothing is taken.
+    assert!(craft_repo::start(&db, alice, r, None).await.is_err());
+    assert_eq!(held(&db, alice, herb).await, 1);
+}
+
+#[tokio::test]
+async fn long_craft_waits_for_worker_sweep() {
+    let db = pool().await;
+    let alice = player(&db).await;
+    let (herb, tonic) = (item(&db).await, item(&db).await);
+    give(&db, alice, herb, 1).await;
+    let r = recipe(&db, &[(herb, 1)], &[(tonic, 2)], 3600, None).await;
+
+    let job = craft_repo::start(&db, alice, r, None).await.unwrap();
+    assert_eq!(job.status, CraftStatus::InProgress);
+    assert!(craft_repo::claim(&db, alice, job.id).await.is_err());
+
+    sqlx::query("UPDATE craft_jobs SET ready_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(job.id)
+        .execute(&db)
+        .await
+        .unwrap();
+    let done = craft_repo::finish_due(&db).await.unwrap();
+    let mine = done.iter().find(|j| j.id == job.id).expect("job swept");
+    assert_eq!(mine.status, CraftStatus::Ready);
+
+    let pending = craft_repo::status(&db, alice).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    craft_repo::claim(&db, alice, job.id).await.unwrap();
+    assert_eq!(held(&db, alice, tonic).await, 2);
+    assert!(craft_repo::status(&db, alice).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn required_structure_must_be_owned() {
+    let db = pool().await;
+    let (alice, bob) = (player(&db).await, player(&db).await);
+    let (herb, tonic) = (item(&db).await, item(&db).await);
+    give(&db, alice, herb, 2).await;
+    let r = recipe(&db, &[(herb, 1)], &[(tonic, 1)], 0, Some("Lab")).await;
+
+    // A parcel at a coordinate no other test uses.
+    let (x, y) = (rand_coord(), rand_coord());
+    sqlx::query("INSERT INTO land_parcels (biome_type, x, y) VALUES ('forest', $1, $2)")
+        .bind(x)
+        .bind(y)
+        .execute(&db)
+        .await
+        .unwrap();
+    let lab: i32 = sqlx::query_scalar(
+        "INSERT INTO structures (owner_player_id, type, x, y) VALUES ($1, 'Lab', $2, $3)
+         RETURNING id",
+    )
+    .bind(bob)
+    .bind(x)
+    .bind(y)
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    assert!(craft_repo::start(&db, alice, r, None).await.is_err());
+    assert!(craft_repo::start(&db, alice, r, Some(lab)).await.is_err());
+    assert_eq!(held(&db, alice, herb).await, 2);
+
+    sqlx::query("UPDATE structures SET owner_player_id = $1 WHERE id = $2")
+        .bind(alice)
+        .bind(lab)
+        .execute(&db)
+        .await
+        .unwrap();
+    let job = craft_repo::start(&db, alice, r, Some(lab)).await.unwrap();
+    assert_eq!(job.structure_id, Some(lab));
+    assert_eq!(held(&db, alice, herb).await, 1);
+}
+
+fn rand_coord() -> i32 {
+    1_000_000 + (Uuid::new_v4().as_u128() % 1_000_000) as i32
+}
@@ -0,0 +1,139 @@
+//! Biome model: bounded evolution, structure effects, neighbour spread,
+//! threshold events and parcel yields.
+
+use biotonic_server::ecosystem::model::{
+    average, events, parcel_yield, step, Biome, EcoEventKind, ParcelState, Resource, TileMods,
+    BLIGHT_POLLUTION, DROUGHT_AT,
+};
+
+fn state(fertility: f64, biomass: f64, pollution: f64, population: f64) -> ParcelState {
+    ParcelState {
+        fertility,
+        biomass,
+        pollution,
+        population,
+    }
+}
+
+#[test]
+fn biome_names_are_case_insensitive() {
+    assert_eq!(Biome::parse("Forest"), Biome::Forest);
+    assert_eq!(Biome::parse("DESERT"), Biome::Desert);
+    assert_eq!(Biome::parse("volcano"), Biome::Grassland);
+}
+
+#[test]
+fn state_stays_in_bounds_over_many_ticks() {
+    for biome in [
+        Biome::Forest,
+        Biome::Grassland,
+        Biome::Desert,
+        Biome::Wetland,
+        Biome::Tundra,
+    ] {
+        let rules = biome.rules();
+        let dirty = TileMods {
+            pollution: 0.05,
+            fertility: -0.02,
+            growth_mult: 1.0,
+        };
+        let mut s = state(0.5, 10.0, 0.0, 10.0);
+        for _ in 0..500 {
+            s = step(&s, &rules, &dirty, None);
+            assert!((0.0..=1.0).contains(&s.fertility));
+            assert!((0.0..=1.0).contains(&s.pollution));
+            assert!((0.0..=rules.capacity).contains(&s.biomass));
+            assert!(s.population >= 0.0);
+        }
+    }
+}
+
+#[test]
+fn clean_parcel_grows_toward_capacity() {
+    let rules = Biome::Wetland.rules();
+    let mut s = state(0.8, 5.0, 0.0, 0.0);
+    for _ in 0..300 {
+        s = step(&s, &rules, &TileMods::default(), None);
+    }
+    assert!(s.biomass > rules.capacity * 0.8);
+}
+
+#[test]
+fn structures_change_rates() {
+    let rules = Biome::Forest.rules();
+    let s = state(0.6, 50.0, 0.1, 20.0);
+    let plain = step(&s, &rules, &TileMods::default(), None);
+
+    let mut polluter = TileMods::default();
+    polluter.add(&TileMods {
+        pollution: 0.05,
+        fertility: -0.01,
+        growth_mult: 1.0,
+    });
+    let dirty = step(&s, &rules, &polluter, None);
+    assert!(dirty.pollution > plain.pollution);
+    assert!(dirty.fertility < plain.fertility);
+
+    let greenhouse = TileMods {
+        growth_mult: 1.5,
+        ..TileMods::default()
+    };
+    assert!(step(&s, &rules, &greenhouse, None).biomass > plain.biomass);
+}
+
+#[test]
+fn pollution_spreads_from_neighbours() {
+    let rules = Biome::Grassland.rules();
+    let clean = state(0.6, 30.0, 0.0, 10.0);
+    let dirty = state(0.6, 30.0, 0.8, 10.0);
+    let around = average([&dirty, &dirty]).unwrap();
+    let alone = step(&clean, &rules, &TileMods::default(), None);
+    let beside = step(&clean, &rules, &TileMods::default(), Some(&around));
+    assert!(beside.pollution > alone.pollution);
+    assert!(average(std::iter::empty()).is_none());
+}
+
+#[test]
+fn events_fire_on_threshold_crossings_only() {
+    let rules = Biome::Forest.rules();
+    let before = state(0.5, 80.0, 0.5, 20.0);
+
+    let bloom = state(0.5, rules.capacity * 0.95, 0.5, 20.0);
+    assert_eq!(events(&before, &bloom, &rules), vec![EcoEventKind::Bloom]);
+    assert!(events(&bloom, &bloom, &rules).is_empty());
+
+    let blight = state(0.5, 80.0, BLIGHT_POLLUTION, 20.0);
+    assert_eq!(events(&before, &blight, &rules), vec![EcoEventKind::Blight]);
+    let crash = state(0.5, 80.0, 0.5, 5.0);
+    assert_eq!(events(&before, &crash, &rules), vec![EcoEventKind::Blight]);
+
+    let drought = state(DROUGHT_AT - 0.01, 80.0, 0.5, 20.0);
+    assert_eq!(
+        events(&before, &drought, &rules),
+        vec![EcoEventKind::Drought]
+    );
+    assert!(events(&drought, &drought, &rules).is_empty());
+}
+
+#[test]
+fn yields_follow_biome_structures_and_pollution() {
+    let clean = state(0.6, 30.0, 0.0, 10.0);
+    assert_eq!(
+        parcel_yield(Biome::Desert, &clean, 1.0),
+        (Resource::Energy, 5)
+    );
+    assert_eq!(
+        parcel_yield(Biome::Forest, &clean, 1.0),
+        (Resource::Biomass, 5)
+    );
+    assert_eq!(
+        parcel_yield(Biome::Wetland, &clean, 1.0),
+        (Resource::GeneSeeds, 3)
+    );
+
+    let (_, boosted) = parcel_yield(Biome::Desert, &clean, 1.5 * 1.2);
+    assert_eq!(boosted, 9);
+
+    let fouled = state(0.6, 30.0, 1.0, 10.0);
+    assert_eq!(parcel_yield(Biome::Desert, &fouled, 1.5).1, 0);
+}
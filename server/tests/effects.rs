@@ -0,0 +1,324 @@
+//! Item effects: out-of-duel use through `effects::use_item`, duel
+//! loadouts, and `TurnAction::UseItem` resolution.
+//!
+//! DB tests need `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::{
+    cache::{UnitCost, UnitDef, UNIT_DEFS},
+    effects::{self, Applied, Effect},
+    game::{
+        logic::{resolve_turn, CombatEvent, RejectReason},
+        rng::CombatRng,
+        types::{BattleState, ItemStack, Position, ResourcePool, TurnAction, UnitType},
+    },
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+
+use common::{credits, game, give, held, player, pool};
+
+/// New item with `effects`, `qty` of which `owner` holds.
+async fn item(db: &PgPool, owner: Uuid, qty: i32, effects: serde_json::Value) -> i32 {
+    let id: i32 = sqlx::query_scalar(
+        "INSERT INTO items (name, description, base_price, effects)
+         VALUES ($1, NULL, 10, $2) RETURNING id",
+    )
+    .bind(format!("effect-{}", Uuid::new_v4()))
+    .bind(effects)
+    .fetch_one(db)
+    .await
+    .unwrap();
+    give(db, owner, id, qty).await;
+    id
+}
+
+#[test]
+fn effects_parse_and_refuse_mixing() {
+    let parsed = effects::parse(json!([{ "kind": "grant_credits", "amount": 5 }])).unwrap();
+    assert_eq!(parsed, vec![Effect::GrantCredits { amount: 5 }]);
+    assert!(effects::parse(json!([{ "kind": "teleport" }])).is_err());
+    assert!(effects::parse(json!([
+        { "kind": "grant_credits", "amount": 5 },
+        { "kind": "heal_unit", "hp": 1 }
+    ]))
+    .is_err());
+}
+
+#[tokio::test]
+async fn grant_credits_pays_from_rewards_and_consumes_the_stack() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let it = item(
+        &db,
+        me,
+        2,
+        json!([{ "kind": "grant_credits", "amount": 25 }]),
+    )
+    .await;
+
+    let out = effects::use_item(&db, me, it, 2, None).await.unwrap();
+    assert_eq!((out.used, out.remaining), (2, 0));
+    assert_eq!(
+        out.applied,
+        vec![Applied::CreditsGranted {
+            amount: 50,
+            balance: 50
+        }]
+    );
+    assert_eq!(credits(&db, me).await, 50);
+
+    // Stack is gone; another use fails without paying out.
+    assert!(effects::use_item(&db, me, it, 1, None).await.is_err());
+    assert_eq!(credits(&db, me).await, 50);
+}
+
+#[tokio::test]
+async fn failed_effect_rolls_back_the_debit() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let it = item(&db, me, 1, json!([{ "kind": "repair_structure", "hp": 3 }])).await;
+
+    // No target structure: nothing is consumed.
+    assert!(effects::use_item(&db, me, it, 1, None).await.is_err());
+    let left: i32 = sqlx::query_scalar(
+        "SELECT quantity FROM player_items WHERE player_id = $1 AND item_id = $2",
+    )
+    .bind(me)
+    .bind(it)
+    .fetch_one(&db)
+    .await
+    .unwrap();
+    assert_eq!(left, 1);
+}
+
+#[tokio::test]
+async fn repair_structure_is_capped_at_max_hp() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let it = item(
+        &db,
+        me,
+        1,
+        json!([{ "kind": "repair_structure", "hp": 10 }]),
+    )
+    .await;
+    let sid: i32 = sqlx::query_scalar(
+        "INSERT INTO structures (owner_player_id, type, x, y, stats)
+         VALUES ($1, 'wall', 0, 0, '{\"hp\": 4, \"max_hp\": 8}') RETURNING id",
+    )
+    .bind(me)
+    .fetch_one(&db)
+    .await
+    .unwrap();
+
+    let out = effects::use_item(&db, me, it, 1, Some(sid)).await.unwrap();
+    assert_eq!(
+        out.applied,
+        vec![Applied::StructureRepaired {
+            structure_id: sid,
+            hp: 8,
+            max_hp: Some(8)
+        }]
+    );
+}
+
+#[tokio::test]
+async fn duel_items_and_boosts_feed_the_loadout() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let boost = item(
+        &db,
+        me,
+        1,
+        json!([{ "kind": "duel_boost", "energy": 3, "gene_seeds": 1 }]),
+    )
+    .await;
+    let potion = item(&db, me, 2, json!([{ "kind": "heal_unit", "hp": 2 }])).await;
+    let unlock = item(
+        &db,
+        me,
+        1,
+        json!([{ "kind": "unlock_unit", "unit_type": "Heavy" }]),
+    )
+    .await;
+
+    // Duel-only items are refused outside a duel.
+    assert!(effects::use_item(&db, me, potion, 1, None).await.is_err());
+
+    effects::use_item(&db, me, boost, 1, None).await.unwrap();
+    let out = effects::use_item(&db, me, unlock, 1, None).await.unwrap();
+    assert_eq!(
+        out.applied,
+        vec![Applied::UnitUnlocked {
+            unit_type: UnitType::HEAVY,
+            newly: true
+        }]
+    );
+
+    let duel = game(&db, me).await;
+    let loadout = effects::take_loadout(&db, duel, me).await.unwrap();
+    assert_eq!(
+        loadout.boost,
+        ResourcePool {
+            energy: 3,
+            biomass: 0,
+            gene_seeds: 1
+        }
+    );
+    assert_eq!(loadout.unlocked, vec![UnitType::HEAVY]);
+    assert_eq!(
+        loadout.items,
+        vec![ItemStack {
+            item_id: potion,
+            qty: 2,
+            effects: vec![Effect::HealUnit { hp: 2 }],
+        }]
+    );
+
+    // The boost is spent by the first duel, and the items are escrowed.
+    let next = game(&db, me).await;
+    let again = effects::take_loadout(&db, next, me).await.unwrap();
+    assert_eq!(again.boost, ResourcePool::default());
+    assert!(again.items.is_empty());
+    assert_eq!(held(&db, me, potion).await, 0);
+}
+
+#[tokio::test]
+async fn escrowed_duel_items_are_spent_or_returned() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let potion = item(&db, me, 3, json!([{ "kind": "heal_unit", "hp": 2 }])).await;
+    let duel = game(&db, me).await;
+
+    let loadout = effects::take_loadout(&db, duel, me).await.unwrap();
+    assert_eq!(loadout.items[0].qty, 3);
+    assert_eq!(
+        held(&db, me, potion).await,
+        0,
+        "nothing left to sell mid-game"
+    );
+
+    effects::consume_duel_item(&db, duel, me, potion)
+        .await
+        .unwrap();
+    effects::release_duel_items(&db, duel).await.unwrap();
+    assert_eq!(held(&db, me, potion).await, 2);
+
+    // Escrow is emptied by the release.
+    assert!(effects::consume_duel_item(&db, duel, me, potion)
+        .await
+        .is_err());
+}
+
+fn battle() -> BattleState {
+    common::seed_unit_defs();
+    let mut state = BattleState::new(CombatRng::new(11));
+    state.p1 = Uuid::new_v4();
+    state.p2 = Uuid::new_v4();
+    state
+}
+
+#[test]
+fn use_item_in_duel_applies_effects_and_spends_the_stack() {
+    let mut state = battle();
+    state.items_p1 = vec![ItemStack {
+        item_id: 7,
+        qty: 1,
+        effects: vec![Effect::Resources {
+            energy: 4,
+            biomass: 0,
+            gene_seeds: 0,
+        }],
+    }];
+    let before = state.pool_p1.energy;
+    let use_it = TurnAction::UseItem {
+        item_id: 7,
+        target_id: None,
+    };
+
+    let res = resolve_turn(&mut state, vec![use_it.clone()], vec![]);
+    assert!(res.rejected.is_empty());
+    assert_eq!(
+        res.outcomes[0].events[0],
+        CombatEvent::ItemUsed {
+            item_id: 7,
+            remaining: 0
+        }
+    );
+    assert_eq!(state.pool_p1.energy, before + 4 + state.base_income.energy);
+
+    let res = resolve_turn(&mut state, vec![use_it], vec![]);
+    assert_eq!(res.rejected[0].reason, RejectReason::NoSuchItem);
+}
+
+#[test]
+fn heal_item_needs_a_wounded_friendly_target() {
+    let mut state = battle();
+    let res = resolve_turn(
+        &mut state,
+        vec![TurnAction::PlayUnit {
+            unit_type: UnitType::HEAVY,
+            pos: Position { lane: 0, row: 0 },
+        }],
+        vec![],
+    );
+    let heavy = res.spawned[0].id;
+    state.items_p1 = vec![ItemStack {
+        item_id: 9,
+        qty: 2,
+        effects: vec![Effect::HealUnit { hp: 5 }],
+    }];
+    let heal = TurnAction::UseItem {
+        item_id: 9,
+        target_id: Some(heavy),
+    };
+
+    // Full hp: refused, nothing spent.
+    let res = resolve_turn(&mut state, vec![heal.clone()], vec![]);
+    assert_eq!(res.rejected[0].reason, RejectReason::NoEffect);
+    assert_eq!(state.items_p1[0].qty, 2);
+
+    state.units_p1[0].hp = 1;
+    let res = resolve_turn(&mut state, vec![heal], vec![]);
+    assert!(res.outcomes[0].events.contains(&CombatEvent::Restored {
+        target_id: heavy,
+        amount: 2
+    }));
+    assert_eq!(state.units_p1[0].hp, 3);
+    assert_eq!(state.items_p1[0].qty, 1);
+}
+
+#[test]
+fn locked_unit_types_need_an_unlock() {
+    let mut state = battle();
+    let locked = UnitType::new("EffectsTestLocked");
+    UNIT_DEFS.insert(
+        locked.clone(),
+        UnitDef {
+            id: locked.clone(),
+            cost: UnitCost::default(),
+            atk: 1,
+            hp: 1,
+            speed: 1,
+            range: 1,
+            abilities: vec![],
+            income: ResourcePool::default(),
+            requires_unlock: true,
+        },
+    );
+    let play = |lane| TurnAction::PlayUnit {
+        unit_type: locked.clone(),
+        pos: Position { lane, row: 0 },
+    };
+
+    let res = resolve_turn(&mut state, vec![play(0)], vec![]);
+    assert_eq!(res.rejected[0].reason, RejectReason::UnitLocked);
+
+    state.unlocked_p1.push(locked.clone());
+    let res = resolve_turn(&mut state, vec![play(1)], vec![]);
+    assert!(res.rejected.is_empty());
+    assert_eq!(res.spawned.len(), 1);
+}
@@ -0,0 +1,115 @@
+//! `Idempotency-Key` middleware: replays, mismatched bodies and requests
+//! without a key, exercised through the shop purchase endpoint.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use actix_web::{http::StatusCode, test, web, App};
+use biotonic_server::http::{idempotency, shop};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+
+use common::{authed_player, credits, grant, pool_with_jwt, priced_item};
+
+const SECRET: &str = "idempotency-test-secret";
+
+/// Fresh player holding `credits`; returns (player_id, bearer header).
+async fn player(db: &PgPool, credits: i64) -> (Uuid, String) {
+    let (pid, token) = authed_player(db, SECRET, 0).await;
+    grant(db, pid, credits).await;
+    (pid, token)
+}
+
+fn buy(token: &str, key: Option<&str>, item_id: i32, quantity: i32) -> test::TestRequest {
+    let mut req = test::TestRequest::post()
+        .uri("/shop/buy")
+        .insert_header(("Authorization", token.to_owned()))
+        .set_json(json!({ "item_id": item_id, "quantity": quantity }));
+    if let Some(key) = key {
+        req = req.insert_header((idempotency::HEADER, key.to_owned()));
+    }
+    req
+}
+
+#[actix_web::test]
+async fn retry_replays_the_stored_response_and_charges_once() {
+    let db = pool_with_jwt(SECRET).await;
+    let (me, token) = player(&db, 1_000).await;
+    let it = priced_item(&db, 10).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db.clone()))
+            .configure(shop::init_routes),
+    )
+    .await;
+
+    let first = test::call_service(&app, buy(&token, Some("buy-1"), it, 2).to_request()).await;
+    assert_eq!(first.status(), StatusCode::OK);
+    assert!(first.headers().get(idempotency::REPLAYED_HEADER).is_none());
+    let after_first = credits(&db, me).await;
+    assert!(after_first < 1_000);
+
+    let retry = test::call_service(&app, buy(&token, Some("buy-1"), it, 2).to_request()).await;
+    assert_eq!(retry.status(), StatusCode::OK);
+    assert_eq!(
+        retry.headers().get(idempotency::REPLAYED_HEADER).unwrap(),
+        "true"
+    );
+    assert_eq!(test::read_body(retry).await, "purchased");
+    assert_eq!(credits(&db, me).await, after_first);
+}
+
+#[actix_web::test]
+async fn reused_key_with_a_different_body_is_rejected() {
+    let db = pool_with_jwt(SECRET).await;
+    let (me, token) = player(&db, 1_000).await;
+    let it = priced_item(&db, 10).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db.clone()))
+            .configure(shop::init_routes),
+    )
+    .await;
+
+    let first = test::call_service(&app, buy(&token, Some("buy-2"), it, 1).to_request()).await;
+    assert_eq!(first.status(), StatusCode::OK);
+    let after_first = credits(&db, me).await;
+
+    let other = test::call_service(&app, buy(&token, Some("buy-2"), it, 5).to_request()).await;
+    assert_eq!(other.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(credits(&db, me).await, after_first);
+}
+
+#[actix_web::test]
+async fn keys_are_per_player_and_optional() {
+    let db = pool_with_jwt(SECRET).await;
+    let (alice, alice_token) = player(&db, 1_000).await;
+    let (bob, bob_token) = player(&db, 1_000).await;
+    let it = priced_item(&db, 10).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db.clone()))
+            .configure(shop::init_routes),
+    )
+    .await;
+
+    // Same key from two players: both run.
+    for token in [&alice_token, &bob_token] {
+        let res = test::call_service(&app, buy(token, Some("shared"), it, 1).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(idempotency::REPLAYED_HEADER).is_none());
+    }
+    assert!(credits(&db, bob).await < 1_000);
+
+    // Without a key every request runs.
+    let mut last = credits(&db, alice).await;
+    for _ in 0..2 {
+        let res = test::call_service(&app, buy(&alice_token, None, it, 1).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let now = credits(&db, alice).await;
+        assert!(now < last);
+        last = now;
+    }
+}
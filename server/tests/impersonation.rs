@@ -0,0 +1,219 @@
+//! Mutating endpoints act as the JWT's player only: a body that names an
+//! actor is refused and nothing changes for the player it names.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use actix_web::{http::StatusCode, test, web, App};
+use biotonic_server::http::{factions, land, shop, structures, trades};
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+
+use common::{authed_player, credits, item, pool_with_jwt};
+
+const SECRET: &str = "impersonation-test-secret";
+
+macro_rules! app {
+    ($db:expr) => {
+        test::init_service(
+            App::new()
+                .app_data(web::Data::new($db.clone()))
+                .app_data(web::Data::new(
+                    redis::Client::open("redis://127.0.0.1/").unwrap(),
+                ))
+                .configure(shop::init_routes)
+                .configure(trades::init_routes)
+                .configure(factions::init_routes)
+                .configure(land::init_routes)
+                .configure(structures::init_routes),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+async fn buy_for_another_player_is_refused() {
+    let db = pool_with_jwt(SECRET).await;
+    let (_me, token) = authed_player(&db, SECRET, 1_000).await;
+    let (victim, _) = authed_player(&db, SECRET, 1_000).await;
+    let item = item(&db).await;
+    let app = app!(db);
+
+    let req = test::TestRequest::post()
+        .uri("/shop/buy")
+        .insert_header(("Authorization", token))
+        .set_json(json!({ "player_id": victim, "item_id": item, "quantity": 1 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(credits(&db, victim).await, 1_000);
+}
+
+#[actix_web::test]
+async fn buy_charges_the_token_holder() {
+    let db = pool_with_jwt(SECRET).await;
+    let (me, token) = authed_player(&db, SECRET, 1_000_000).await;
+    let item = item(&db).await;
+    let app = app!(db);
+
+    let req = test::TestRequest::post()
+        .uri("/shop/buy")
+        .insert_header(("Authorization", token))
+        .set_json(json!({ "item_id": item, "quantity": 1 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(credits(&db, me).await < 1_000_000);
+}
+
+#[actix_web::test]
+async fn missing_token_is_unauthorized() {
+    let db = pool_with_jwt(SECRET).await;
+    let item = item(&db).await;
+    let app = app!(db);
+
+    let req = test::TestRequest::post()
+        .uri("/shop/buy")
+        .set_json(json!({ "item_id": item, "quantity": 1 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn trade_cannot_spend_someone_elses_items() {
+    let db = pool_with_jwt(SECRET).await;
+    let (me, token) = authed_player(&db, SECRET, 0).await;
+    let (victim, _) = authed_player(&db, SECRET, 0).await;
+    let item = item(&db).await;
+    sqlx::query("INSERT INTO player_items (player_id, item_id, quantity) VALUES ($1, $2, 5)")
+        .bind(victim)
+        .bind(item)
+        .execute(&db)
+        .await
+        .unwrap();
+    let app = app!(db);
+
+    let req = test::TestRequest::post()
+        .uri("/trades/offers")
+        .insert_header(("Authorization", token))
+        .set_json(json!({
+            "proposer_id": victim, "to_player": me,
+            "offer": { "items": [{ "item_id": item, "qty": 5 }] }
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let left: i32 = sqlx::query_scalar(
+        "SELECT quantity FROM player_items WHERE player_id = $1 AND item_id = $2",
+    )
+    .bind(victim)
+    .bind(item)
+    .fetch_one(&db)
+    .await
+    .unwrap();
+    assert_eq!(left, 5);
+}
+
+#[actix_web::test]
+async fn faction_founder_comes_from_token() {
+    let db = pool_with_jwt(SECRET).await;
+    let (me, token) = authed_player(&db, SECRET, 0).await;
+    let (victim, _) = authed_player(&db, SECRET, 0).await;
+    let app = app!(db);
+
+    let forged = test::TestRequest::post()
+        .uri("/factions/create")
+        .insert_header(("Authorization", token.clone()))
+        .set_json(json!({ "name": format!("f-{}", Uuid::new_v4()), "founder_id": victim }))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, forged).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let name = format!("f-{}", Uuid::new_v4());
+    let ok = test::TestRequest::post()
+        .uri("/factions/create")
+        .insert_header(("Authorization", token))
+        .set_json(json!({ "name": name }))
+        .to_request();
+    assert_eq!(test::call_service(&app, ok).await.status(), StatusCode::OK);
+
+    let role: String = sqlx::query_scalar(
+        "SELECT fm.role FROM faction_members fm JOIN factions f ON f.id = fm.faction_id
+          WHERE f.name = $1 AND fm.player_id = $2",
+    )
+    .bind(&name)
+    .bind(me)
+    .fetch_one(&db)
+    .await
+    .unwrap();
+    assert_eq!(role, "leader");
+}
+
+#[actix_web::test]
+async fn promote_with_actor_id_is_refused() {
+    let db = pool_with_jwt(SECRET).await;
+    let (_me, token) = authed_player(&db, SECRET, 0).await;
+    let app = app!(db);
+
+    let req = test::TestRequest::post()
+        .uri("/factions/promote")
+        .insert_header(("Authorization", token))
+        .set_json(json!({
+            "faction_id": Uuid::new_v4(),
+            "actor_id": Uuid::new_v4(),
+            "target_id": Uuid::new_v4()
+        }))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[actix_web::test]
+async fn build_with_player_id_is_refused() {
+    let db = pool_with_jwt(SECRET).await;
+    let (_me, token) = authed_player(&db, SECRET, 0).await;
+    let (victim, _) = authed_player(&db, SECRET, 0).await;
+    let app = app!(db);
+
+    let req = test::TestRequest::post()
+        .uri("/structures/build")
+        .insert_header(("Authorization", token))
+        .set_json(json!({ "player_id": victim, "type": "Hut", "x": 0, "y": 0 }))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[actix_web::test]
+async fn claim_for_a_foreign_faction_is_forbidden() {
+    let db = pool_with_jwt(SECRET).await;
+    let (_me, token) = authed_player(&db, SECRET, 0).await;
+    let fid: Uuid = sqlx::query_scalar("INSERT INTO factions (name) VALUES ($1) RETURNING id")
+        .bind(format!("f-{}", Uuid::new_v4()))
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    let app = app!(db);
+
+    let req = test::TestRequest::post()
+        .uri("/land/claim")
+        .insert_header(("Authorization", token))
+        .set_json(json!({ "faction_id": fid, "x": 90_001, "y": 90_001, "biome_type": "Forest" }))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        StatusCode::FORBIDDEN
+    );
+}
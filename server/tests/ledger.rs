@@ -0,0 +1,172 @@
+//! Double-entry credit ledger: balanced pairs, cached balances, the
+//! append-only guard, drift detection and the per-player endpoint.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use actix_web::{http::StatusCode, test, web, App};
+use biotonic_server::{
+    db::ledger_repo::{self, Account, Reason, SystemAccount},
+    http::ledger,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+
+use common::{authed_player, credits, grant, pool_with_jwt};
+
+const SECRET: &str = "ledger-test-secret";
+
+#[tokio::test]
+async fn transfer_writes_a_balanced_pair_and_updates_the_cache() {
+    let db = pool_with_jwt(SECRET).await;
+    let (alice, _) = authed_player(&db, SECRET, 0).await;
+    let (bob, _) = authed_player(&db, SECRET, 0).await;
+    grant(&db, alice, 100).await;
+
+    let mut tx = db.begin().await.unwrap();
+    ledger_repo::transfer(
+        &mut tx,
+        Account::Player(alice),
+        Account::Player(bob),
+        30,
+        Reason::Trade,
+        Some("ref-1"),
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(
+        (credits(&db, alice).await, credits(&db, bob).await),
+        (70, 30)
+    );
+
+    let entries = ledger_repo::entries(&db, alice, None, 10).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].amount, -30);
+    assert_eq!(entries[0].reason, "trade");
+    assert_eq!(entries[0].ref_id.as_deref(), Some("ref-1"));
+    assert_eq!(entries[0].counterparty, Some(bob.to_string()));
+    assert_eq!(entries[1].counterparty.as_deref(), Some("admin"));
+
+    let sum: i64 =
+        sqlx::query_scalar("SELECT SUM(amount)::BIGINT FROM credit_ledger WHERE txn_id = $1")
+            .bind(entries[0].txn_id)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+    assert_eq!(sum, 0);
+
+    let drift = ledger_repo::find_drift(&db).await.unwrap();
+    assert!(!drift
+        .iter()
+        .any(|d| d.player_id == Some(alice) || d.player_id == Some(bob)));
+}
+
+#[tokio::test]
+async fn overdraw_is_refused() {
+    let db = pool_with_jwt(SECRET).await;
+    let (alice, _) = authed_player(&db, SECRET, 0).await;
+    grant(&db, alice, 10).await;
+
+    let mut tx = db.begin().await.unwrap();
+    let res = ledger_repo::transfer(
+        &mut tx,
+        Account::Player(alice),
+        Account::System(SystemAccount::Shop),
+        11,
+        Reason::Shop,
+        None,
+    )
+    .await;
+    assert!(res.is_err());
+    drop(tx);
+    assert_eq!(credits(&db, alice).await, 10);
+}
+
+#[tokio::test]
+async fn ledger_rows_cannot_be_edited_or_left_unbalanced() {
+    let db = pool_with_jwt(SECRET).await;
+    let (alice, _) = authed_player(&db, SECRET, 0).await;
+    grant(&db, alice, 5).await;
+
+    let edit = sqlx::query("UPDATE credit_ledger SET amount = 500 WHERE player_id = $1")
+        .bind(alice)
+        .execute(&db)
+        .await;
+    assert!(edit.is_err());
+    let delete = sqlx::query("DELETE FROM credit_ledger WHERE player_id = $1")
+        .bind(alice)
+        .execute(&db)
+        .await;
+    assert!(delete.is_err());
+
+    let mut tx = db.begin().await.unwrap();
+    sqlx::query(
+        "INSERT INTO credit_ledger (txn_id, player_id, amount, reason) VALUES ($1, $2, 7, 'admin')",
+    )
+    .bind(Uuid::new_v4())
+    .bind(alice)
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+    assert!(tx.commit().await.is_err());
+}
+
+#[tokio::test]
+async fn reconciliation_flags_cached_balance_drift() {
+    let db = pool_with_jwt(SECRET).await;
+    let (alice, _) = authed_player(&db, SECRET, 0).await;
+    grant(&db, alice, 20).await;
+    sqlx::query("UPDATE players SET credits = credits + 5 WHERE id = $1")
+        .bind(alice)
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let drift = biotonic_server::ledger::reconcile(&db).await.unwrap();
+    let mine = drift
+        .iter()
+        .find(|d| d.player_id == Some(alice))
+        .expect("drift flagged");
+    assert_eq!((mine.cached, mine.ledger), (25, 20));
+
+    let flagged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ledger_drift WHERE player_id = $1")
+        .bind(alice)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(flagged, 1);
+}
+
+#[actix_web::test]
+async fn ledger_endpoint_is_owner_only() {
+    let db = pool_with_jwt(SECRET).await;
+    let (alice, token) = authed_player(&db, SECRET, 0).await;
+    let (bob, _) = authed_player(&db, SECRET, 0).await;
+    grant(&db, alice, 3).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db.clone()))
+            .configure(ledger::init_routes),
+    )
+    .await;
+
+    let other = test::TestRequest::get()
+        .uri(&format!("/players/{bob}/ledger"))
+        .insert_header(("Authorization", token.clone()))
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, other).await.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    let own = test::TestRequest::get()
+        .uri(&format!("/players/{alice}/ledger"))
+        .insert_header(("Authorization", token))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, own).await;
+    assert_eq!(body[0]["amount"], 3);
+    assert_eq!(body[0]["reason"], "admin");
+}
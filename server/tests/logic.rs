@@ -2,17 +2,17 @@
 
 use biotonic_server::game::{
     logic::{resolve_turn, CombatResult},
+    rng::CombatRng,
     scoring,
-    types::{ResourcePool, TurnAction, Unit, UnitType},
+    types::{BattleState, Position, TurnAction, Unit, UnitType},
 };
 use uuid::Uuid;
 
-fn fresh_pool() -> ResourcePool {
-    ResourcePool {
-        energy: 5,
-        biomass: 5,
-        gene_seeds: 2,
-    }
+mod common;
+
+fn fresh_battle() -> BattleState {
+    common::seed_unit_defs();
+    BattleState::new(CombatRng::new(0))
 }
 
 #[test]
@@ -20,42 +20,32 @@ fn play_unit_spawns_and_consumes_resources() {
     let p1 = Uuid::new_v4();
 
     // Player 1 plays a Light unit; Player 2 passes.
-    let light = Unit {
-        id: Uuid::new_v4(),
-        unit_type: UnitType::Light,
-        owner_id: p1,
-        hp: 0, // will be filled in by logic
-    };
     let actions_p1 = vec![TurnAction::PlayUnit {
-        unit: light.clone(),
+        unit_type: UnitType::LIGHT,
+        pos: Position { lane: 0, row: 0 },
     }];
     let actions_p2 = vec![TurnAction::Pass];
 
     // Mutable battle state
-    let mut pool1 = fresh_pool();
-    let mut pool2 = fresh_pool();
-    let mut units1 = Vec::new();
-    let mut units2 = Vec::new();
+    let mut state = fresh_battle();
+    state.p1 = p1;
 
     let CombatResult {
-        spawned, applied, ..
-    } = resolve_turn(
-        actions_p1,
-        actions_p2,
-        &mut pool1,
-        &mut pool2,
-        &mut units1,
-        &mut units2,
-    );
+        spawned,
+        applied,
+        income_p1,
+        ..
+    } = resolve_turn(&mut state, actions_p1, actions_p2);
 
     // Assertions
     assert_eq!(spawned.len(), 1, "one unit should spawn");
-    assert_eq!(units1.len(), 1, "unit now on battlefield");
+    assert_eq!(state.units_p1.len(), 1, "unit now on battlefield");
+    assert_eq!(spawned[0].owner_id, p1, "owner comes from the seat");
     assert!(applied
         .iter()
         .any(|a| matches!(a, TurnAction::PlayUnit { .. })));
-    // A Light unit costs 1 energy: pool should shrink.
-    assert_eq!(pool1.energy, 4);
+    // A Light unit costs 1 energy: pool should shrink (before income).
+    assert_eq!(state.pool_p1.energy, 4 + income_p1.energy);
 }
 
 #[test]
@@ -66,15 +56,17 @@ fn attack_kills_defender() {
     // Pre-existing units
     let attacker = Unit {
         id: Uuid::new_v4(),
-        unit_type: UnitType::Light, // atk 1
+        unit_type: UnitType::LIGHT, // atk 1
         owner_id: p1,
         hp: 1,
+        pos: Position { lane: 0, row: 2 },
     };
     let defender = Unit {
         id: Uuid::new_v4(),
-        unit_type: UnitType::Light, // hp 1
+        unit_type: UnitType::LIGHT, // hp 1
         owner_id: p2,
         hp: 1,
+        pos: Position { lane: 0, row: 3 },
     };
 
     let actions_p1 = vec![TurnAction::Attack {
@@ -83,25 +75,20 @@ fn attack_kills_defender() {
     }];
     let actions_p2 = vec![TurnAction::Pass];
 
-    let mut pool1 = fresh_pool();
-    let mut pool2 = fresh_pool();
-    let mut units1 = vec![attacker];
-    let mut units2 = vec![defender.clone()];
-
-    let res = resolve_turn(
-        actions_p1,
-        actions_p2,
-        &mut pool1,
-        &mut pool2,
-        &mut units1,
-        &mut units2,
-    );
+    let mut state = fresh_battle();
+    state.units_p1 = vec![attacker];
+    state.units_p2 = vec![defender.clone()];
+
+    let res = resolve_turn(&mut state, actions_p1, actions_p2);
 
     assert!(
         res.destroyed.contains(&defender.id),
         "defender should be destroyed"
     );
-    assert!(units2.is_empty(), "defender removed from battlefield");
+    assert!(
+        state.units_p2.is_empty(),
+        "defender removed from battlefield"
+    );
 }
 
 #[test]
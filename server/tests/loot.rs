@@ -0,0 +1,155 @@
+//! Seeded loot rolls: reproducible, weighted, merged by item; and the
+//! payout that records only what fits under `max_stack`.
+//!
+//! The DB test needs `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::{
+    db::{loot_repo, trade_repo::BundleItem},
+    game::{
+        loot::{loot_seed, LootEntry, LootTable, Outcome},
+        rng::CombatRng,
+        victory::GameMode,
+    },
+};
+use uuid::Uuid;
+
+mod common;
+
+use common::{game, give, held, item, player, pool};
+
+const SECRET: &[u8] = b"test-loot-secret";
+
+fn entry(item_id: Option<i32>, weight: u32, qty_min: i32, qty_max: i32) -> LootEntry {
+    LootEntry {
+        item_id,
+        weight,
+        qty_min,
+        qty_max,
+    }
+}
+
+fn table(rolls: u32, entries: Vec<LootEntry>) -> LootTable {
+    LootTable {
+        id: 1,
+        credits_min: 10,
+        credits_max: 20,
+        rolls,
+        entries,
+    }
+}
+
+#[test]
+fn same_seed_same_roll() {
+    let t = table(
+        5,
+        vec![
+            entry(Some(1), 3, 1, 4),
+            entry(Some(2), 1, 1, 1),
+            entry(None, 2, 1, 1),
+        ],
+    );
+    let seed = loot_seed(SECRET, Uuid::new_v4(), Uuid::new_v4());
+    let a = t.roll(&mut CombatRng::new(seed));
+    let b = t.roll(&mut CombatRng::new(seed));
+    assert_eq!(a, b);
+    assert!((10..=20).contains(&a.credits));
+}
+
+#[test]
+fn seats_get_their_own_seed() {
+    let game = Uuid::new_v4();
+    let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+    assert_ne!(loot_seed(SECRET, game, p1), loot_seed(SECRET, game, p2));
+    assert_eq!(loot_seed(SECRET, game, p1), loot_seed(SECRET, game, p1));
+}
+
+#[test]
+fn seeds_depend_on_the_server_secret() {
+    let (game, p1) = (Uuid::new_v4(), Uuid::new_v4());
+    assert_ne!(loot_seed(SECRET, game, p1), loot_seed(b"other", game, p1));
+}
+
+#[test]
+fn drops_are_merged_and_sorted() {
+    let t = table(4, vec![entry(Some(9), 1, 2, 2)]);
+    let roll = t.roll(&mut CombatRng::new(7));
+    assert_eq!(roll.items, vec![BundleItem { item_id: 9, qty: 8 }]);
+
+    let t = table(3, vec![entry(Some(5), 1, 1, 1), entry(Some(2), 1, 1, 1)]);
+    let roll = t.roll(&mut CombatRng::new(11));
+    assert!(roll.items.windows(2).all(|w| w[0].item_id < w[1].item_id));
+    assert_eq!(roll.items.iter().map(|x| x.qty).sum::<i32>(), 3);
+}
+
+#[test]
+fn empty_slots_and_empty_tables_drop_nothing() {
+    let roll = table(10, vec![entry(None, 1, 1, 1)]).roll(&mut CombatRng::new(1));
+    assert!(roll.items.is_empty());
+    let roll = table(10, vec![]).roll(&mut CombatRng::new(1));
+    assert!(roll.items.is_empty());
+}
+
+#[test]
+fn outcome_is_from_the_seat_point_of_view() {
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    assert_eq!(Outcome::for_player(a, Some(a)), Outcome::Win);
+    assert_eq!(Outcome::for_player(b, Some(a)), Outcome::Loss);
+    assert_eq!(Outcome::for_player(a, None), Outcome::Draw);
+}
+
+#[tokio::test]
+async fn award_records_only_what_fits_under_max_stack() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let it = item(&db).await;
+    sqlx::query("UPDATE items SET max_stack = 3 WHERE id = $1")
+        .bind(it)
+        .execute(&db)
+        .await
+        .unwrap();
+    give(&db, me, it, 2).await;
+
+    // A one-rating band of its own, so no other table is more specific.
+    let rating = 1_000_000 + (Uuid::new_v4().as_u128() % 1_000_000) as i32;
+    let table: i32 = sqlx::query_scalar(
+        "INSERT INTO loot_tables (mode, outcome, min_rating, max_rating, rolls)
+         VALUES ('duel', 'win', $1, $1, 1) RETURNING id",
+    )
+    .bind(rating)
+    .fetch_one(&db)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO loot_entries (table_id, item_id, weight, qty_min, qty_max)
+         VALUES ($1, $2, 1, 5, 5)",
+    )
+    .bind(table)
+    .bind(it)
+    .execute(&db)
+    .await
+    .unwrap();
+
+    let duel = game(&db, me).await;
+    let mut conn = db.acquire().await.unwrap();
+    let reward = loot_repo::award(&mut conn, duel, me, GameMode::Duel, Outcome::Win, rating)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        reward.items,
+        vec![BundleItem {
+            item_id: it,
+            qty: 1
+        }]
+    );
+    assert_eq!(held(&db, me, it).await, 3);
+
+    let recorded: serde_json::Value =
+        sqlx::query_scalar("SELECT items FROM match_rewards WHERE game_id = $1 AND player_id = $2")
+            .bind(duel)
+            .bind(me)
+            .fetch_one(&db)
+            .await
+            .unwrap();
+    assert_eq!(recorded, serde_json::to_value(&reward.items).unwrap());
+}
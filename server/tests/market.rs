@@ -0,0 +1,215 @@
+//! Order-book matching: escrow, partial fills, price improvement, fees,
+//! cancellation and depth.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::db::market_repo::{self, fee_for, Level, OrderSide, OrderStatus};
+
+mod common;
+
+use common::{credits, give, held, item, player_with_credits, pool};
+
+const FEE_BPS: u32 = 500; // 5 %
+
+#[test]
+fn fee_rounds_down() {
+    assert_eq!(fee_for(1_000, 200), 20);
+    assert_eq!(fee_for(49, 200), 0);
+    assert_eq!(fee_for(1_000, 0), 0);
+}
+
+#[tokio::test]
+async fn resting_sell_is_partially_filled_at_its_own_price() {
+    let db = pool().await;
+    let (seller, buyer) = (
+        player_with_credits(&db, 0).await,
+        player_with_credits(&db, 1_000).await,
+    );
+    let ore = item(&db).await;
+    give(&db, seller, ore, 10).await;
+    let sink_before = market_repo::fees_collected(&db).await.unwrap();
+
+    let ask = market_repo::place(&db, seller, ore, OrderSide::Sell, 20, 10, FEE_BPS)
+        .await
+        .unwrap();
+    assert!(ask.fills.is_empty());
+    assert_eq!(held(&db, seller, ore).await, 0);
+
+    // Buyer bids higher than the ask: fills at 20, gets 4 * 5 refunded.
+    let bid = market_repo::place(&db, buyer, ore, OrderSide::Buy, 25, 4, FEE_BPS)
+        .await
+        .unwrap();
+    assert_eq!(bid.order.status, OrderStatus::Filled);
+    assert_eq!(bid.fills.len(), 1);
+    let fill = &bid.fills[0];
+    assert_eq!((fill.qty, fill.price, fill.fee), (4, 20, 4));
+    assert_eq!((fill.buyer_id, fill.seller_id), (buyer, seller));
+
+    assert_eq!(credits(&db, buyer).await, 1_000 - 80);
+    assert_eq!(held(&db, buyer, ore).await, 4);
+    assert_eq!(credits(&db, seller).await, 76);
+    assert!(market_repo::fees_collected(&db).await.unwrap() >= sink_before + 4);
+
+    let (price, fee): (i64, i64) = sqlx::query_as("SELECT price, fee FROM trades WHERE id = $1")
+        .bind(fill.trade_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!((price, fee), (80, 4));
+
+    let open = market_repo::list_open(&db, seller).await.unwrap();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].remaining, 6);
+}
+
+#[tokio::test]
+async fn taker_sweeps_levels_best_price_first() {
+    let db = pool().await;
+    let (b1, b2, seller) = (
+        player_with_credits(&db, 1_000).await,
+        player_with_credits(&db, 1_000).await,
+        player_with_credits(&db, 0).await,
+    );
+    let ore = item(&db).await;
+    give(&db, seller, ore, 5).await;
+
+    market_repo::place(&db, b1, ore, OrderSide::Buy, 10, 3, FEE_BPS)
+        .await
+        .unwrap();
+    market_repo::place(&db, b2, ore, OrderSide::Buy, 12, 3, FEE_BPS)
+        .await
+        .unwrap();
+    assert_eq!(credits(&db, b2).await, 1_000 - 36);
+
+    let placed = market_repo::place(&db, seller, ore, OrderSide::Sell, 9, 5, FEE_BPS)
+        .await
+        .unwrap();
+    let got: Vec<_> = placed
+        .fills
+        .iter()
+        .map(|f| (f.buyer_id, f.qty, f.price))
+        .collect();
+    assert_eq!(got, vec![(b2, 3, 12), (b1, 2, 10)]);
+    assert_eq!(placed.order.status, OrderStatus::Filled);
+
+    // 36 + 20 gross, 5 % fee floored per fill (1 + 1).
+    assert_eq!(credits(&db, seller).await, 56 - 2);
+    assert_eq!(held(&db, b1, ore).await, 2);
+
+    let depth = market_repo::depth(&db, ore, 10).await.unwrap();
+    assert_eq!(
+        depth.bids,
+        vec![Level {
+            price: 10,
+            qty: 1,
+            orders: 1
+        }]
+    );
+    assert!(depth.asks.is_empty());
+}
+
+#[tokio::test]
+async fn own_orders_are_not_matched() {
+    let db = pool().await;
+    let me = player_with_credits(&db, 100).await;
+    let ore = item(&db).await;
+    give(&db, me, ore, 1).await;
+
+    market_repo::place(&db, me, ore, OrderSide::Sell, 5, 1, FEE_BPS)
+        .await
+        .unwrap();
+    let bid = market_repo::place(&db, me, ore, OrderSide::Buy, 5, 1, FEE_BPS)
+        .await
+        .unwrap();
+    assert!(bid.fills.is_empty());
+    assert_eq!(bid.order.status, OrderStatus::Open);
+}
+
+#[tokio::test]
+async fn cancel_refunds_the_unfilled_escrow() {
+    let db = pool().await;
+    let (buyer, seller) = (
+        player_with_credits(&db, 100).await,
+        player_with_credits(&db, 0).await,
+    );
+    let ore = item(&db).await;
+    give(&db, seller, ore, 2).await;
+
+    let bid = market_repo::place(&db, buyer, ore, OrderSide::Buy, 10, 5, FEE_BPS)
+        .await
+        .unwrap();
+    market_repo::place(&db, seller, ore, OrderSide::Sell, 10, 2, FEE_BPS)
+        .await
+        .unwrap();
+    assert_eq!(credits(&db, buyer).await, 50);
+
+    assert!(market_repo::cancel(&db, seller, bid.order.id)
+        .await
+        .is_err());
+    let cancelled = market_repo::cancel(&db, buyer, bid.order.id).await.unwrap();
+    assert_eq!(cancelled.status, OrderStatus::Cancelled);
+    assert_eq!(credits(&db, buyer).await, 80);
+    assert!(market_repo::cancel(&db, buyer, bid.order.id).await.is_err());
+}
+
+#[tokio::test]
+async fn orders_without_escrow_are_refused() {
+    let db = pool().await;
+    let me = player_with_credits(&db, 10).await;
+    let ore = item(&db).await;
+
+    assert!(
+        market_repo::place(&db, me, ore, OrderSide::Buy, 11, 1, FEE_BPS)
+            .await
+            .is_err()
+    );
+    assert!(
+        market_repo::place(&db, me, ore, OrderSide::Sell, 1, 1, FEE_BPS)
+            .await
+            .is_err()
+    );
+    assert!(
+        market_repo::place(&db, me, ore, OrderSide::Buy, 0, 1, FEE_BPS)
+            .await
+            .is_err()
+    );
+    assert_eq!(credits(&db, me).await, 10);
+    assert!(market_repo::list_open(&db, me).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn resting_buys_past_max_stack_are_cancelled_not_filled() {
+    let db = pool().await;
+    let (seller, buyer) = (
+        player_with_credits(&db, 0).await,
+        player_with_credits(&db, 1_000).await,
+    );
+    let ore = item(&db).await;
+    sqlx::query("UPDATE items SET max_stack = 5 WHERE id = $1")
+        .bind(ore)
+        .execute(&db)
+        .await
+        .unwrap();
+    give(&db, seller, ore, 8).await;
+
+    // Each bid fits on its own; together they would overflow the stack.
+    let first = market_repo::place(&db, buyer, ore, OrderSide::Buy, 10, 4, FEE_BPS)
+        .await
+        .unwrap();
+    let second = market_repo::place(&db, buyer, ore, OrderSide::Buy, 10, 4, FEE_BPS)
+        .await
+        .unwrap();
+
+    let ask = market_repo::place(&db, seller, ore, OrderSide::Sell, 10, 8, FEE_BPS)
+        .await
+        .unwrap();
+    assert_eq!(ask.fills.len(), 1);
+    assert_eq!(ask.order.remaining, 4);
+    assert_eq!(held(&db, buyer, ore).await, 4);
+    // The second bid was withdrawn and refunded.
+    assert_eq!(credits(&db, buyer).await, 1_000 - 40);
+    let open = market_repo::list_open(&db, buyer).await.unwrap();
+    assert!(open
+        .iter()
+        .all(|o| o.id != first.order.id && o.id != second.order.id));
+}
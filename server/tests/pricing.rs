@@ -0,0 +1,235 @@
+//! Shop price curves: bounds, volume response, single-query catalogue,
+//! price-history snapshots and per-unit pricing of shop trades.
+//!
+//! DB tests need `DATABASE_URL` (see `.env.example`).
+
+use actix_web::{http::StatusCode, test, web, App};
+use biotonic_server::{
+    db::market_repo::OrderSide,
+    http::shop,
+    pricing::{self, Curve},
+};
+use serde_json::json;
+
+mod common;
+
+use common::{authed_player, credits, give, grant, held, player, pool, pool_with_jwt, priced_item};
+
+const SECRET: &str = "pricing-test-secret";
+
+fn trade(path: &str, token: &str, item_id: i32, quantity: i32) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri(path)
+        .insert_header(("Authorization", token.to_owned()))
+        .set_json(json!({ "item_id": item_id, "quantity": quantity }))
+}
+
+#[test]
+fn no_volume_means_base_price() {
+    assert_eq!(Curve::default_for(100).price(100, 0.0), 100);
+}
+
+#[test]
+fn price_is_bounded_by_floor_and_ceiling() {
+    let c = Curve {
+        floor: 50,
+        ceiling: 150,
+        elasticity: 0.1,
+        decay: 0.0,
+    };
+    assert_eq!(c.price(100, 3.0), 130);
+    assert_eq!(c.price(100, 1_000_000.0), 150);
+    assert_eq!(c.price(100, -1_000_000.0), 50);
+}
+
+#[test]
+fn curve_validation() {
+    let ok = Curve::default_for(10);
+    assert!(ok.validate().is_ok());
+    assert!(Curve { floor: 0, ..ok }.validate().is_err());
+    assert!(Curve { ceiling: 1, ..ok }.validate().is_err());
+    assert!(Curve {
+        elasticity: -1.0,
+        ..ok
+    }
+    .validate()
+    .is_err());
+    assert!(Curve {
+        decay: f64::NAN,
+        ..ok
+    }
+    .validate()
+    .is_err());
+}
+
+#[test]
+fn multi_unit_trades_walk_the_curve() {
+    let c = Curve::default_for(10);
+    assert_eq!(c.total(10, 0.0, OrderSide::Buy, 1), 10);
+    // Bulk buys climb to the ceiling instead of paying the opening price.
+    assert!(c.total(10, 0.0, OrderSide::Buy, 150) > 150 * 10);
+    assert_eq!(c.total(10, 1e9, OrderSide::Buy, 1_000), 40 * 1_000);
+    assert_eq!(c.total(10, -1e9, OrderSide::Sell, 1_000), 5 * 1_000);
+    assert_eq!(c.total(10, 0.0, OrderSide::Sell, 0), 0);
+
+    // Selling straight back never returns more than the buy cost.
+    for base in [1, 10, 100] {
+        let c = Curve::default_for(base);
+        for net in [-200.0, -3.5, 0.0, 7.0, 400.0] {
+            for qty in [1, 2, 17, 150, 999] {
+                let paid = c.total(base, net, OrderSide::Buy, qty);
+                let back = c.total(base, net + qty as f64, OrderSide::Sell, qty);
+                assert!(back <= paid, "base {base} net {net} qty {qty}");
+            }
+        }
+    }
+}
+
+#[actix_web::test]
+async fn buying_and_selling_back_never_profits() {
+    let db = pool_with_jwt(SECRET).await;
+    let (me, token) = authed_player(&db, SECRET, 0).await;
+    grant(&db, me, 10_000).await;
+    let it = priced_item(&db, 10).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db.clone()))
+            .configure(shop::init_routes),
+    )
+    .await;
+
+    for _ in 0..3 {
+        let before = credits(&db, me).await;
+        let res = test::call_service(&app, trade("/shop/buy", &token, it, 150).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        // Selling the whole stack removes it.
+        let res = test::call_service(&app, trade("/shop/sell", &token, it, 150).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(held(&db, me, it).await, 0);
+        assert!(credits(&db, me).await < before);
+    }
+}
+
+#[actix_web::test]
+async fn selling_more_than_held_is_refused() {
+    let db = pool_with_jwt(SECRET).await;
+    let (me, token) = authed_player(&db, SECRET, 0).await;
+    let it = priced_item(&db, 10).await;
+    give(&db, me, it, 2).await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db.clone()))
+            .configure(shop::init_routes),
+    )
+    .await;
+
+    let res = test::call_service(&app, trade("/shop/sell", &token, it, 3).to_request()).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(held(&db, me, it).await, 2);
+    assert_eq!(credits(&db, me).await, 0);
+}
+
+#[tokio::test]
+async fn recent_buys_raise_price_and_sells_lower_it() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let it = priced_item(&db, 100).await;
+    pricing::set_curve(
+        &db,
+        it,
+        &Curve {
+            floor: 80,
+            ceiling: 200,
+            elasticity: 0.01,
+            decay: 0.0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut conn = db.acquire().await.unwrap();
+    assert_eq!(
+        pricing::quote(&mut conn, it).await.unwrap().unwrap().price,
+        100
+    );
+
+    pricing::record(&mut conn, it, me, OrderSide::Buy, 30, 100)
+        .await
+        .unwrap();
+    assert_eq!(
+        pricing::quote(&mut conn, it).await.unwrap().unwrap().price,
+        130
+    );
+
+    pricing::record(&mut conn, it, me, OrderSide::Sell, 60, 129)
+        .await
+        .unwrap();
+    assert_eq!(
+        pricing::quote(&mut conn, it).await.unwrap().unwrap().price,
+        80
+    );
+
+    assert!(pricing::quote(&mut conn, -1).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn old_volume_decays_toward_base() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let it = priced_item(&db, 100).await;
+    pricing::set_curve(
+        &db,
+        it,
+        &Curve {
+            floor: 1,
+            ceiling: 1_000,
+            elasticity: 0.01,
+            decay: 1.0,
+        },
+    )
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO shop_transactions (item_id, player_id, side, qty, unit_price, created_at)
+         VALUES ($1, $2, 'buy', 100, 100, NOW() - INTERVAL '10 hours')",
+    )
+    .bind(it)
+    .bind(me)
+    .execute(&db)
+    .await
+    .unwrap();
+
+    // 100 × e^-10 ≈ 0.005 units of net volume: back at base.
+    let mut conn = db.acquire().await.unwrap();
+    assert_eq!(
+        pricing::quote(&mut conn, it).await.unwrap().unwrap().price,
+        100
+    );
+}
+
+#[tokio::test]
+async fn catalogue_matches_single_quotes_and_snapshots_feed_history() {
+    let db = pool().await;
+    let me = player(&db).await;
+    let it = priced_item(&db, 40).await;
+    let mut conn = db.acquire().await.unwrap();
+    pricing::record(&mut conn, it, me, OrderSide::Buy, 10, 40)
+        .await
+        .unwrap();
+
+    let single = pricing::quote(&mut conn, it).await.unwrap().unwrap().price;
+    let listed = pricing::catalogue(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|q| q.item_id == it)
+        .unwrap()
+        .price;
+    assert_eq!(single, listed);
+    assert!(single > 40);
+
+    assert!(pricing::snapshot(&db).await.unwrap() >= 1);
+    let hist = pricing::history(&db, it, 10).await.unwrap();
+    assert_eq!(hist.len(), 1);
+    assert_eq!(hist[0].price, single);
+}
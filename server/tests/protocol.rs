@@ -0,0 +1,93 @@
+//! Wire-format checks for server → client frames.
+
+use biotonic_server::protocol::{ClientMsg, ErrorCode, ServerMsg, TurnDeadline};
+use chrono::Utc;
+use uuid::Uuid;
+
+#[test]
+fn game_start_carries_turn_deadline() {
+    let deadline = TurnDeadline {
+        turn: 0,
+        expires_at: Utc::now(),
+        seconds: 60,
+    };
+    let msg = ServerMsg::GameStart {
+        game_id: Uuid::new_v4(),
+        turn: 0,
+        deadline: Some(deadline.clone()),
+    };
+
+    let json = serde_json::to_value(&msg).unwrap();
+    assert_eq!(json["type"], "GameStart");
+    assert_eq!(json["deadline"]["seconds"], 60);
+
+    let back: ServerMsg = serde_json::from_value(json).unwrap();
+    assert!(matches!(back, ServerMsg::GameStart { deadline: Some(d), .. } if d == deadline));
+}
+
+#[test]
+fn snapshot_frames_without_deadline_still_parse() {
+    // TurnResult stored in Redis before turn clocks existed.
+    let json = serde_json::json!({
+        "type": "TurnResult",
+        "game_id": Uuid::new_v4(),
+        "turn": 3,
+        "result": {
+            "applied": [], "spawned": [], "destroyed": [], "outcomes": [],
+            "rejected": [], "breaches": [],
+            "income_p1": { "energy": 0, "biomass": 0, "gene_seeds": 0 },
+            "income_p2": { "energy": 0, "biomass": 0, "gene_seeds": 0 }
+        }
+    });
+
+    let msg: ServerMsg = serde_json::from_value(json).unwrap();
+    assert!(matches!(msg, ServerMsg::TurnResult { deadline: None, .. }));
+}
+
+#[test]
+fn error_frame_echoes_request_id() {
+    let msg = ServerMsg::Error {
+        code: ErrorCode::StaleTurn,
+        message: "expected turn 2, got 1".into(),
+        request_id: Some("req-42".into()),
+    };
+    let json = serde_json::to_value(&msg).unwrap();
+    assert_eq!(json["type"], "Error");
+    assert_eq!(json["code"], "stale_turn");
+    assert_eq!(json["request_id"], "req-42");
+
+    let bare = ServerMsg::Error {
+        code: ErrorCode::RateLimited,
+        message: "too many messages".into(),
+        request_id: None,
+    };
+    let json = serde_json::to_value(&bare).unwrap();
+    assert_eq!(json["code"], "rate_limited");
+    assert!(json.get("request_id").is_none());
+}
+
+#[test]
+fn client_request_id_is_optional() {
+    let game_id = Uuid::new_v4();
+    let player_id = Uuid::new_v4();
+    let with: ClientMsg = serde_json::from_value(serde_json::json!({
+        "type": "Ready", "game_id": game_id, "player_id": player_id, "request_id": "r1"
+    }))
+    .unwrap();
+    assert_eq!(with.request_id(), Some("r1"));
+    assert_eq!(with.game_id(), game_id);
+
+    let without: ClientMsg = serde_json::from_value(serde_json::json!({
+        "type": "Ready", "game_id": game_id, "player_id": player_id
+    }))
+    .unwrap();
+    assert_eq!(without.request_id(), None);
+}
+
+#[test]
+fn clients_cannot_send_internal_disconnect() {
+    let res = serde_json::from_value::<ClientMsg>(serde_json::json!({
+        "type": "Disconnected", "game_id": Uuid::new_v4(), "player_id": Uuid::new_v4()
+    }));
+    assert!(res.is_err());
+}
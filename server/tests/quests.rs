@@ -0,0 +1,111 @@
+//! Daily rotation and quest / achievement progress and claims.
+//!
+//! The DB tests need `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::{
+    cache,
+    db::quest_repo::{self, QuestKind},
+    progression::{daily_rotation, resets_at, EventKind, PlayerEvent},
+};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use uuid::Uuid;
+
+mod common;
+
+use common::{player, pool};
+
+fn day(d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+}
+
+fn noon(d: u32) -> DateTime<Utc> {
+    day(d).and_hms_opt(12, 0, 0).unwrap().and_utc()
+}
+
+#[test]
+fn rotation_is_a_stable_daily_subset() {
+    let ids: Vec<String> = (0..8).map(|i| format!("q{i}")).collect();
+    let today = daily_rotation(&ids, day(1), 3);
+    assert_eq!(today.len(), 3);
+    assert!(today.iter().all(|q| ids.contains(q)));
+    assert!(today.windows(2).all(|w| w[0] < w[1]));
+
+    // Same answer regardless of input order.
+    let mut shuffled = ids.clone();
+    shuffled.reverse();
+    assert_eq!(daily_rotation(&shuffled, day(1), 3), today);
+
+    // Rotates across days.
+    assert!((2..=10).any(|d| daily_rotation(&ids, day(d), 3) != today));
+
+    // Never more than the pool.
+    assert_eq!(daily_rotation(&ids[..2], day(1), 3).len(), 2);
+}
+
+#[test]
+fn rotation_resets_at_utc_midnight() {
+    let now = Utc.with_ymd_and_hms(2026, 3, 1, 23, 59, 0).unwrap();
+    assert_eq!(
+        resets_at(now),
+        Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn achievement_progress_and_single_claim() {
+    let db = pool().await;
+    let alice = player(&db).await;
+    let id = format!("test-{}", Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO quests (id, kind, name, event, target, reward_credits)
+         VALUES ($1, 'achievement', 'Test', 'item_crafted', 2, 75)",
+    )
+    .bind(&id)
+    .execute(&db)
+    .await
+    .unwrap();
+    cache::warm_quests(&db).await.unwrap();
+
+    let ev = PlayerEvent {
+        player: alice,
+        kind: EventKind::ItemCrafted,
+        amount: 1,
+        at: noon(1),
+    };
+    quest_repo::record(&db, &ev, 3).await.unwrap();
+    assert!(
+        quest_repo::claim(&db, alice, QuestKind::Achievement, &id, day(1), 3)
+            .await
+            .is_err()
+    );
+
+    // Achievements span periods; progress caps at the target.
+    let ev = PlayerEvent {
+        amount: 5,
+        at: noon(2),
+        ..ev
+    };
+    quest_repo::record(&db, &ev, 3).await.unwrap();
+    let list = quest_repo::list(&db, alice, QuestKind::Achievement, day(2), 3)
+        .await
+        .unwrap();
+    let mine = list.iter().find(|q| q.quest.id == id).unwrap();
+    assert_eq!(mine.progress, 2);
+    assert_eq!(mine.completed_at, Some(noon(2)));
+
+    let claimed = quest_repo::claim(&db, alice, QuestKind::Achievement, &id, day(2), 3)
+        .await
+        .unwrap();
+    assert!(claimed.claimed_at.is_some());
+    let credits: i64 = sqlx::query_scalar("SELECT credits FROM players WHERE id = $1")
+        .bind(alice)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(credits, 75);
+    assert!(
+        quest_repo::claim(&db, alice, QuestKind::Achievement, &id, day(2), 3)
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,115 @@
+//! Turn submission and turn-clock rules enforced by the game session.
+//!
+//! The result tests need `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::{
+    game::{
+        session::{persist_result, submit_turn, timeout_end, Submission, TimeoutEnd, ABANDONED},
+        types::{Side, TurnAction},
+    },
+    protocol::ErrorCode,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod common;
+
+use common::{game, player, pool};
+
+async fn rating(db: &PgPool, player: Uuid) -> i32 {
+    sqlx::query_scalar("SELECT elo_rating FROM players WHERE id = $1")
+        .bind(player)
+        .fetch_one(db)
+        .await
+        .unwrap()
+}
+
+#[test]
+fn accepts_current_turn() {
+    let mut pending = None;
+    assert_eq!(
+        submit_turn(&mut pending, 2, 2, vec![TurnAction::Pass]),
+        Ok(Submission::Accepted)
+    );
+    assert_eq!(pending, Some((2, vec![TurnAction::Pass])));
+}
+
+#[test]
+fn rejects_stale_and_future_turns() {
+    let mut pending = None;
+    for t in [0, 3] {
+        let err = submit_turn(&mut pending, 2, t, vec![TurnAction::Pass]).unwrap_err();
+        assert_eq!(err.0, ErrorCode::StaleTurn);
+    }
+    assert_eq!(pending, None, "refused turns are not stored");
+}
+
+#[test]
+fn retry_of_resolved_turn_is_answered() {
+    let mut pending = None;
+    assert_eq!(
+        submit_turn(&mut pending, 2, 1, vec![TurnAction::Pass]),
+        Ok(Submission::Resolved)
+    );
+    assert_eq!(pending, None, "the retry is not queued for the next turn");
+}
+
+#[test]
+fn identical_resend_is_idempotent() {
+    let mut pending = None;
+    submit_turn(&mut pending, 0, 0, vec![TurnAction::Pass]).unwrap();
+    assert!(submit_turn(&mut pending, 0, 0, vec![TurnAction::Pass]).is_ok());
+    assert_eq!(pending, Some((0, vec![TurnAction::Pass])));
+}
+
+#[test]
+fn different_resend_does_not_overwrite() {
+    let mut pending = None;
+    submit_turn(&mut pending, 0, 0, vec![TurnAction::Pass]).unwrap();
+
+    let err = submit_turn(&mut pending, 0, 0, vec![]).unwrap_err();
+    assert_eq!(err.0, ErrorCode::TurnAlreadySubmitted);
+    assert_eq!(pending, Some((0, vec![TurnAction::Pass])));
+}
+
+#[test]
+fn lone_repeated_timeouts_forfeit() {
+    assert_eq!(timeout_end(2, 3, 3), Some(TimeoutEnd::Forfeit(Side::P2)));
+    assert_eq!(timeout_end(3, 0, 3), Some(TimeoutEnd::Forfeit(Side::P1)));
+    assert_eq!(timeout_end(2, 2, 3), None);
+}
+
+#[test]
+fn both_seats_timing_out_abandons_the_game() {
+    assert_eq!(timeout_end(3, 3, 3), Some(TimeoutEnd::Abandoned));
+}
+
+#[tokio::test]
+async fn abandoned_game_gives_no_rewards() {
+    let db = pool().await;
+    let (a, b) = (player(&db).await, player(&db).await);
+    let (ra, rb) = (rating(&db, a).await, rating(&db, b).await);
+    let gid = game(&db, a).await;
+
+    let rewards = persist_result(&db, gid, None, a, b, Some(ABANDONED), &[])
+        .await
+        .unwrap();
+    assert!(rewards.is_empty());
+
+    let (state, winner, d1, d2): (String, Option<Uuid>, i32, i32) = sqlx::query_as(
+        "SELECT state, winner_id, player1_elo_delta, player2_elo_delta FROM games WHERE id = $1",
+    )
+    .bind(gid)
+    .fetch_one(&db)
+    .await
+    .unwrap();
+    assert_eq!((state.as_str(), winner, d1, d2), ("Finished", None, 0, 0));
+    assert_eq!((rating(&db, a).await, rating(&db, b).await), (ra, rb));
+
+    let paid: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM match_rewards WHERE game_id = $1")
+        .bind(gid)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+    assert_eq!(paid, 0);
+}
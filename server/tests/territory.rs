@@ -0,0 +1,133 @@
+//! Land expansion: free first claim, border rule, costs scaled by territory
+//! and tile value, paid from the treasury or the player’s credits.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::{
+    db::{
+        land_repo::{self, ClaimOutcome},
+        treasury_repo::{self, Resources, TreasuryReason},
+    },
+    ecosystem::{
+        model::Biome,
+        territory::{claim_cost, tile_value, PayWith},
+        worldgen::Tile,
+    },
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+mod common;
+
+use common::{credits, faction, player_with_credits, pool};
+
+/// A private corner of the plane, far off the generated map, so runs never
+/// collide on tiles.
+fn origin() -> (i32, i32) {
+    let n = (Uuid::new_v4().as_u128() % 1_000_000) as i32;
+    (1_000_000 + n * 10, -1_000_000 - n * 10)
+}
+
+fn tile(x: i32, y: i32) -> Tile {
+    Tile {
+        x,
+        y,
+        biome: Biome::Forest,
+        rich: false,
+    }
+}
+
+#[test]
+fn cost_scales_with_territory_and_value() {
+    assert_eq!(claim_cost(Biome::Forest, false, 0).credits, 0);
+    let one = claim_cost(Biome::Forest, false, 1);
+    let two = claim_cost(Biome::Forest, false, 2);
+    assert_eq!(two.credits, one.credits * 2);
+    assert_eq!(two.resources.energy, one.resources.energy * 2);
+    assert!(
+        claim_cost(Biome::Wetland, false, 1).credits > claim_cost(Biome::Tundra, false, 1).credits
+    );
+    assert_eq!(
+        tile_value(Biome::Desert, true),
+        2 * tile_value(Biome::Desert, false)
+    );
+}
+
+#[tokio::test]
+async fn first_claim_is_free_and_later_ones_must_border() {
+    let db = pool().await;
+    let (f, p) = (faction(&db).await, player_with_credits(&db, 0).await);
+    let (x, y) = origin();
+
+    let first = land_repo::claim(&db, &tile(x, y), f, p, PayWith::Treasury)
+        .await
+        .unwrap();
+    let ClaimOutcome::Claimed { paid, .. } = first else {
+        panic!("{first:?}");
+    };
+    assert_eq!(paid.resources, Resources::default());
+
+    let again = land_repo::claim(&db, &tile(x, y), f, p, PayWith::Treasury)
+        .await
+        .unwrap();
+    assert!(matches!(again, ClaimOutcome::AlreadyOwned { .. }));
+
+    let err = land_repo::claim(&db, &tile(x + 2, y), f, p, PayWith::Treasury)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("border"));
+    let diagonal = land_repo::claim(&db, &tile(x + 1, y + 1), f, p, PayWith::Treasury).await;
+    assert!(diagonal.is_err());
+
+    let rival = faction(&db).await;
+    let taken = land_repo::claim(&db, &tile(x, y), rival, p, PayWith::Treasury)
+        .await
+        .unwrap();
+    assert_eq!(taken, ClaimOutcome::Taken);
+}
+
+#[tokio::test]
+async fn expansion_is_paid_from_treasury_or_credits() {
+    let db = pool().await;
+    let (f, p) = (faction(&db).await, player_with_credits(&db, 1_000).await);
+    let (x, y) = origin();
+    land_repo::claim(&db, &tile(x, y), f, p, PayWith::Treasury)
+        .await
+        .unwrap();
+
+    // Empty treasury: the second parcel cannot be paid for.
+    let err = land_repo::claim(&db, &tile(x + 1, y), f, p, PayWith::Treasury)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("insufficient"));
+
+    let cost = claim_cost(Biome::Forest, false, 1);
+    let mut conn = db.acquire().await.unwrap();
+    treasury_repo::deposit(
+        &mut conn,
+        f,
+        &cost.resources,
+        TreasuryReason::Admin,
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+    drop(conn);
+    let second = land_repo::claim(&db, &tile(x + 1, y), f, p, PayWith::Treasury)
+        .await
+        .unwrap();
+    assert!(
+        matches!(second, ClaimOutcome::Claimed { paid, .. } if paid.resources == cost.resources)
+    );
+    let t = treasury_repo::get(&db, f, 1).await.unwrap();
+    assert_eq!(t.balance, Resources::default());
+
+    // Third parcel, paid by the player at twice the price.
+    let third = land_repo::claim(&db, &tile(x, y + 1), f, p, PayWith::Credits)
+        .await
+        .unwrap();
+    let expected = claim_cost(Biome::Forest, false, 2).credits;
+    assert!(matches!(third, ClaimOutcome::Claimed { paid, .. } if paid.credits == expected));
+    assert_eq!(credits(&db, p).await, 1_000 - expected);
+}
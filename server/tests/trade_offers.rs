@@ -0,0 +1,346 @@
+//! Trade-offer lifecycle: escrow on propose, atomic settle on accept,
+//! refunds on cancel / counter / expiry; soulbound and max-stack rules.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::db::trade_repo::{self, Bundle, BundleItem, TradeStatus};
+use chrono::Duration;
+
+mod common;
+
+use common::{credits, give, held, item, player_with_credits, pool};
+
+fn items(list: &[(i32, i32)]) -> Vec<BundleItem> {
+    list.iter()
+        .map(|&(item_id, qty)| BundleItem { item_id, qty })
+        .collect()
+}
+
+fn hour() -> Duration {
+    Duration::hours(1)
+}
+
+#[tokio::test]
+async fn propose_escrows_and_accept_settles_both_bundles() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 100).await,
+        player_with_credits(&db, 500).await,
+    );
+    let (sword, herb, ore) = (item(&db).await, item(&db).await, item(&db).await);
+    give(&db, alice, sword, 2).await;
+    give(&db, alice, herb, 5).await;
+    give(&db, bob, ore, 3).await;
+
+    let offer = Bundle {
+        credits: 40,
+        items: items(&[(sword, 2), (herb, 3)]),
+    };
+    let ask = Bundle {
+        credits: 200,
+        items: items(&[(ore, 3)]),
+    };
+    let o = trade_repo::propose(&db, alice, bob, &offer, &ask, hour())
+        .await
+        .unwrap();
+
+    // Escrow: alice's side is gone, bob is untouched.
+    assert_eq!(credits(&db, alice).await, 60);
+    assert_eq!(
+        (held(&db, alice, sword).await, held(&db, alice, herb).await),
+        (0, 2)
+    );
+    assert_eq!(credits(&db, bob).await, 500);
+
+    let done = trade_repo::accept(&db, bob, o.id).await.unwrap();
+    assert_eq!(done.status, TradeStatus::Accepted);
+
+    assert_eq!(credits(&db, alice).await, 260);
+    assert_eq!(held(&db, alice, ore).await, 3);
+    assert_eq!(credits(&db, bob).await, 340);
+    assert_eq!(
+        (held(&db, bob, sword).await, held(&db, bob, herb).await),
+        (2, 3)
+    );
+    assert_eq!(held(&db, bob, ore).await, 0);
+}
+
+#[tokio::test]
+async fn propose_without_the_goods_changes_nothing() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 10).await,
+        player_with_credits(&db, 0).await,
+    );
+    let herb = item(&db).await;
+    give(&db, alice, herb, 1).await;
+
+    let offer = Bundle {
+        credits: 10,
+        items: items(&[(herb, 2)]),
+    };
+    assert!(
+        trade_repo::propose(&db, alice, bob, &offer, &Bundle::default(), hour())
+            .await
+            .is_err()
+    );
+    assert_eq!(credits(&db, alice).await, 10);
+    assert_eq!(held(&db, alice, herb).await, 1);
+}
+
+#[tokio::test]
+async fn failed_accept_keeps_offer_open_and_escrow_held() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 50).await,
+        player_with_credits(&db, 10).await,
+    );
+    let ask = Bundle {
+        credits: 100,
+        items: vec![],
+    };
+    let offer = Bundle {
+        credits: 50,
+        items: vec![],
+    };
+    let o = trade_repo::propose(&db, alice, bob, &offer, &ask, hour())
+        .await
+        .unwrap();
+
+    assert!(trade_repo::accept(&db, bob, o.id).await.is_err());
+    assert_eq!(
+        (credits(&db, alice).await, credits(&db, bob).await),
+        (0, 10)
+    );
+    assert_eq!(trade_repo::list_open(&db, bob).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn only_the_right_party_may_act() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 10).await,
+        player_with_credits(&db, 0).await,
+    );
+    let offer = Bundle {
+        credits: 10,
+        items: vec![],
+    };
+    let o = trade_repo::propose(&db, alice, bob, &offer, &Bundle::default(), hour())
+        .await
+        .unwrap();
+
+    assert!(trade_repo::accept(&db, alice, o.id).await.is_err());
+    assert!(trade_repo::cancel(&db, bob, o.id).await.is_err());
+
+    let cancelled = trade_repo::cancel(&db, alice, o.id).await.unwrap();
+    assert_eq!(cancelled.status, TradeStatus::Cancelled);
+    assert_eq!(credits(&db, alice).await, 10);
+    assert!(trade_repo::accept(&db, bob, o.id).await.is_err());
+}
+
+#[tokio::test]
+async fn counter_refunds_original_and_escrows_reply() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 100).await,
+        player_with_credits(&db, 30).await,
+    );
+    let herb = item(&db).await;
+    give(&db, bob, herb, 4).await;
+
+    let o = trade_repo::propose(
+        &db,
+        alice,
+        bob,
+        &Bundle {
+            credits: 100,
+            items: vec![],
+        },
+        &Bundle {
+            credits: 0,
+            items: items(&[(herb, 4)]),
+        },
+        hour(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(credits(&db, alice).await, 0);
+
+    let (original, reply) = trade_repo::counter(
+        &db,
+        bob,
+        o.id,
+        &Bundle {
+            credits: 0,
+            items: items(&[(herb, 2)]),
+        },
+        &Bundle {
+            credits: 80,
+            items: vec![],
+        },
+        hour(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(original.status, TradeStatus::Countered);
+    assert_eq!(reply.parent_id, Some(o.id));
+    assert_eq!((reply.proposer_id, reply.recipient_id), (bob, alice));
+    assert_eq!(credits(&db, alice).await, 100);
+    assert_eq!(held(&db, bob, herb).await, 2);
+
+    trade_repo::accept(&db, alice, reply.id).await.unwrap();
+    assert_eq!(credits(&db, alice).await, 20);
+    assert_eq!(held(&db, alice, herb).await, 2);
+    assert_eq!(credits(&db, bob).await, 110);
+}
+
+#[tokio::test]
+async fn expired_offers_are_refunded_and_cannot_be_accepted() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 25).await,
+        player_with_credits(&db, 0).await,
+    );
+    let herb = item(&db).await;
+    give(&db, alice, herb, 1).await;
+
+    let o = trade_repo::propose(
+        &db,
+        alice,
+        bob,
+        &Bundle {
+            credits: 25,
+            items: items(&[(herb, 1)]),
+        },
+        &Bundle::default(),
+        Duration::seconds(-1),
+    )
+    .await
+    .unwrap();
+
+    assert!(trade_repo::accept(&db, bob, o.id).await.is_err());
+
+    let expired = trade_repo::expire_due(&db).await.unwrap();
+    let mine = expired.iter().find(|x| x.id == o.id).expect("offer swept");
+    assert_eq!(mine.status, TradeStatus::Expired);
+    assert_eq!(credits(&db, alice).await, 25);
+    assert_eq!(held(&db, alice, herb).await, 1);
+}
+
+#[tokio::test]
+async fn bundles_are_validated() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 0).await,
+        player_with_credits(&db, 0).await,
+    );
+    let herb = item(&db).await;
+
+    let one_herb = Bundle {
+        credits: 0,
+        items: items(&[(herb, 1)]),
+    };
+    let nothing = Bundle::default();
+
+    assert!(
+        trade_repo::propose(&db, alice, alice, &nothing, &one_herb, hour())
+            .await
+            .is_err()
+    );
+    assert!(
+        trade_repo::propose(&db, alice, bob, &nothing, &nothing, hour())
+            .await
+            .is_err()
+    );
+
+    let negative = Bundle {
+        credits: -5,
+        items: vec![],
+    };
+    assert!(negative.normalized().is_err());
+    let zero_qty = Bundle {
+        credits: 0,
+        items: items(&[(herb, 0)]),
+    };
+    assert!(zero_qty.normalized().is_err());
+
+    let merged = Bundle {
+        credits: 0,
+        items: items(&[(herb, 1), (herb, 2)]),
+    }
+    .normalized()
+    .unwrap();
+    assert_eq!(merged.items, items(&[(herb, 3)]));
+}
+
+#[tokio::test]
+async fn soulbound_items_cannot_be_offered_or_asked() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 0).await,
+        player_with_credits(&db, 0).await,
+    );
+    let (relic, herb) = (item(&db).await, item(&db).await);
+    sqlx::query("UPDATE items SET soulbound = TRUE WHERE id = $1")
+        .bind(relic)
+        .execute(&db)
+        .await
+        .unwrap();
+    give(&db, alice, relic, 1).await;
+    give(&db, alice, herb, 1).await;
+
+    let relic_only = Bundle {
+        credits: 0,
+        items: items(&[(relic, 1)]),
+    };
+    let herb_only = Bundle {
+        credits: 0,
+        items: items(&[(herb, 1)]),
+    };
+    assert!(
+        trade_repo::propose(&db, alice, bob, &relic_only, &Bundle::default(), hour())
+            .await
+            .is_err()
+    );
+    assert!(
+        trade_repo::propose(&db, alice, bob, &herb_only, &relic_only, hour())
+            .await
+            .is_err()
+    );
+    // Nothing left in escrow.
+    assert_eq!(held(&db, alice, relic).await, 1);
+    assert_eq!(held(&db, alice, herb).await, 1);
+}
+
+#[tokio::test]
+async fn accept_respects_max_stack() {
+    let db = pool().await;
+    let (alice, bob) = (
+        player_with_credits(&db, 0).await,
+        player_with_credits(&db, 0).await,
+    );
+    let herb = item(&db).await;
+    sqlx::query("UPDATE items SET max_stack = 5 WHERE id = $1")
+        .bind(herb)
+        .execute(&db)
+        .await
+        .unwrap();
+    give(&db, alice, herb, 3).await;
+    give(&db, bob, herb, 4).await;
+
+    let offer = Bundle {
+        credits: 0,
+        items: items(&[(herb, 3)]),
+    };
+    let o = trade_repo::propose(&db, alice, bob, &offer, &Bundle::default(), hour())
+        .await
+        .unwrap();
+    assert!(trade_repo::accept(&db, bob, o.id).await.is_err());
+    assert_eq!(held(&db, bob, herb).await, 4);
+
+    // Escrow is still refunded in full on cancel.
+    trade_repo::cancel(&db, alice, o.id).await.unwrap();
+    assert_eq!(held(&db, alice, herb).await, 3);
+}
@@ -0,0 +1,105 @@
+//! Faction treasuries: deposits, spends that may not overdraw, and the
+//! per-tick income view.
+//!
+//! Needs `DATABASE_URL` (see `.env.example`).
+
+use biotonic_server::db::treasury_repo::{self, Resources, TreasuryReason};
+use chrono::{Duration, Utc};
+
+mod common;
+
+use common::{faction, pool};
+
+fn res(energy: i64, biomass: i64, gene_seeds: i64) -> Resources {
+    Resources {
+        energy,
+        biomass,
+        gene_seeds,
+    }
+}
+
+#[tokio::test]
+async fn yields_accumulate_per_tick() {
+    let db = pool().await;
+    let f = faction(&db).await;
+    let t0 = Utc::now() - Duration::minutes(2);
+    let t1 = t0 + Duration::minutes(1);
+
+    let mut conn = db.acquire().await.unwrap();
+    for (at, amount) in [(t0, res(5, 2, 0)), (t1, res(5, 0, 3))] {
+        treasury_repo::deposit(&mut conn, f, &amount, TreasuryReason::Yield, None, at)
+            .await
+            .unwrap();
+    }
+    drop(conn);
+
+    let t = treasury_repo::get(&db, f, 10).await.unwrap();
+    assert_eq!(t.balance, res(10, 2, 3));
+    assert_eq!(t.income.len(), 2);
+    assert_eq!(t.income[0].resources, res(5, 0, 3));
+    assert_eq!(t.income[1].resources, res(5, 2, 0));
+
+    let last = treasury_repo::get(&db, f, 1).await.unwrap();
+    assert_eq!(last.income.len(), 1);
+}
+
+#[tokio::test]
+async fn spend_cannot_overdraw() {
+    let db = pool().await;
+    let f = faction(&db).await;
+    let mut conn = db.acquire().await.unwrap();
+
+    let err = treasury_repo::spend(&mut conn, f, &res(1, 0, 0), TreasuryReason::War, None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("insufficient"));
+
+    treasury_repo::deposit(
+        &mut conn,
+        f,
+        &res(20, 10, 0),
+        TreasuryReason::Admin,
+        None,
+        Utc::now(),
+    )
+    .await
+    .unwrap();
+    assert!(treasury_repo::spend(
+        &mut conn,
+        f,
+        &res(5, 11, 0),
+        TreasuryReason::Structure,
+        None
+    )
+    .await
+    .is_err());
+    treasury_repo::spend(
+        &mut conn,
+        f,
+        &res(20, 10, 0),
+        TreasuryReason::Structure,
+        None,
+    )
+    .await
+    .unwrap();
+    drop(conn);
+
+    let t = treasury_repo::get(&db, f, 10).await.unwrap();
+    assert_eq!(t.balance, Resources::default());
+    assert!(t.income.is_empty());
+}
+
+#[tokio::test]
+async fn structure_costs_come_from_the_table() {
+    let db = pool().await;
+    let mut conn = db.acquire().await.unwrap();
+    let cost = treasury_repo::structure_cost(&mut conn, "Extractor")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!cost.is_zero());
+    let unknown = treasury_repo::structure_cost(&mut conn, "Statue")
+        .await
+        .unwrap();
+    assert!(unknown.is_none());
+}
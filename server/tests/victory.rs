@@ -0,0 +1,148 @@
+//! Victory rules, tiebreakers and the combat hooks that feed them.
+
+use biotonic_server::game::{
+    logic::{resolve_turn, CombatEvent},
+    rng::CombatRng,
+    types::{BattleState, Position, Side, TurnAction, Unit, UnitType, STARTING_CORE_HP},
+    victory::{judge, GameMode},
+};
+use uuid::Uuid;
+
+mod common;
+
+fn battle() -> BattleState {
+    common::seed_unit_defs();
+    let mut state = BattleState::new(CombatRng::new(3));
+    state.p1 = Uuid::new_v4();
+    state.p2 = Uuid::new_v4();
+    state
+}
+
+fn unit(owner_id: Uuid, unit_type: UnitType, hp: u32, lane: u8, row: u8) -> Unit {
+    Unit {
+        id: Uuid::new_v4(),
+        unit_type,
+        owner_id,
+        hp,
+        pos: Position { lane, row },
+    }
+}
+
+#[test]
+fn duel_elimination_decides_at_final_turn_only() {
+    let mut state = battle();
+    state.units_p1 = vec![unit(state.p1, UnitType::LIGHT, 1, 0, 1)];
+
+    let rules = GameMode::Duel.rules();
+    assert_eq!(judge(&rules, &state, false).winner, None);
+
+    let v = judge(&rules, &state, true);
+    assert_eq!(v.winner, Some(Side::P1));
+    assert_eq!(v.rule, Some("elimination"));
+    assert_eq!(v.scores.len(), 2);
+    assert_eq!((v.scores[0].p1, v.scores[0].p2), (1, 0));
+}
+
+#[test]
+fn duel_total_hp_breaks_tie() {
+    let mut state = battle();
+    state.units_p1 = vec![unit(state.p1, UnitType::HEAVY, 3, 0, 1)];
+    state.units_p2 = vec![unit(state.p2, UnitType::LIGHT, 1, 0, 4)];
+
+    let v = judge(&GameMode::Duel.rules(), &state, true);
+    assert_eq!(v.winner, Some(Side::P1));
+    assert_eq!(v.rule, Some("total_hp"));
+}
+
+#[test]
+fn even_board_is_a_draw() {
+    let state = battle();
+    let v = judge(&GameMode::Duel.rules(), &state, true);
+    assert_eq!(v.winner, None);
+    assert_eq!(v.rule, None);
+}
+
+#[test]
+fn siege_core_kill_is_sudden_death() {
+    let mut state = battle();
+    state.core_hp_p2 = 0;
+    state.units_p2 = vec![unit(state.p2, UnitType::HEAVY, 3, 0, 4)];
+
+    let v = judge(&GameMode::Siege.rules(), &state, false);
+    assert_eq!(v.winner, Some(Side::P1));
+    assert_eq!(v.rule, Some("core_hp"));
+}
+
+#[test]
+fn unit_on_enemy_back_row_damages_core() {
+    let mut state = battle();
+    let back_row = state.board.rows - 1;
+    let heavy = unit(state.p1, UnitType::HEAVY, 3, 2, back_row);
+    let heavy_id = heavy.id;
+    state.units_p1 = vec![heavy];
+
+    let res = resolve_turn(&mut state, vec![TurnAction::Pass], vec![TurnAction::Pass]);
+
+    assert_eq!(state.core_hp_p2, STARTING_CORE_HP - 3);
+    assert_eq!(state.core_hp_p1, STARTING_CORE_HP);
+    assert_eq!(
+        res.breaches,
+        vec![CombatEvent::CoreDamage {
+            source_id: heavy_id,
+            core: Side::P2,
+            amount: 3,
+            remaining_hp: STARTING_CORE_HP - 3,
+        }]
+    );
+}
+
+#[test]
+fn kills_score_points_for_the_acting_side() {
+    let mut state = battle();
+    let attacker = unit(state.p1, UnitType::HEAVY, 3, 0, 2);
+    let defender = unit(state.p2, UnitType::LIGHT, 1, 0, 3);
+    let action = TurnAction::Attack {
+        attacker_id: attacker.id,
+        defender_id: defender.id,
+    };
+    state.units_p1 = vec![attacker];
+    state.units_p2 = vec![defender];
+
+    resolve_turn(&mut state, vec![action], vec![TurnAction::Pass]);
+
+    assert_eq!((state.points_p1, state.points_p2), (1, 0));
+}
+
+#[test]
+fn skirmish_ends_when_target_reached() {
+    let mut state = battle();
+    state.points_p2 = 5;
+    state.units_p1 = vec![unit(state.p1, UnitType::HEAVY, 3, 0, 1)];
+
+    let v = judge(&GameMode::Skirmish.rules(), &state, false);
+    assert_eq!(v.winner, Some(Side::P2));
+    assert_eq!(v.rule, Some("points"));
+}
+
+#[test]
+fn mode_round_trips_through_its_db_name() {
+    for mode in [GameMode::Duel, GameMode::Siege, GameMode::Skirmish] {
+        assert_eq!(GameMode::parse(mode.as_str()), Some(mode));
+    }
+    assert_eq!(GameMode::parse("capture_the_flag"), None);
+}
+
+#[test]
+fn snapshot_without_cores_restores_full_cores() {
+    let state = battle();
+    let mut json = serde_json::to_value(&state).unwrap();
+    let obj = json.as_object_mut().unwrap();
+    for key in ["core_hp_p1", "core_hp_p2", "points_p1", "points_p2"] {
+        obj.remove(key);
+    }
+    let restored: BattleState = serde_json::from_value(json).unwrap();
+    assert_eq!(restored.core_hp_p1, STARTING_CORE_HP);
+    assert_eq!(restored.core_hp_p2, STARTING_CORE_HP);
+    assert_eq!(restored.points_p1 + restored.points_p2, 0);
+    assert_eq!(judge(&GameMode::Siege.rules(), &restored, false).winner, None);
+}
@@ -0,0 +1,74 @@
+//! Seeded world map: reproducible, bounded, varied and mostly contiguous.
+
+use std::collections::HashSet;
+
+use biotonic_server::ecosystem::{
+    model::Biome,
+    worldgen::{biome_for, WorldGen, RICH_PER_MILLE},
+};
+
+fn tiles(world: &WorldGen) -> Vec<(i32, i32, Biome, bool)> {
+    (0..world.height)
+        .flat_map(|y| (0..world.width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let t = world.tile(x, y).unwrap();
+            (x, y, t.biome, t.rich)
+        })
+        .collect()
+}
+
+#[test]
+fn same_seed_same_map() {
+    let a = WorldGen::new(7, 64, 64);
+    let b = WorldGen::new(7, 64, 64);
+    assert_eq!(tiles(&a), tiles(&b));
+    assert_ne!(tiles(&a), tiles(&WorldGen::new(8, 64, 64)));
+}
+
+#[test]
+fn tiles_off_the_map_do_not_exist() {
+    let world = WorldGen::new(1, 32, 16);
+    assert!(world.tile(0, 0).is_some());
+    assert!(world.tile(31, 15).is_some());
+    assert!(world.tile(-1, 0).is_none());
+    assert!(world.tile(32, 0).is_none());
+    assert!(world.tile(0, 16).is_none());
+}
+
+#[test]
+fn map_has_every_biome_in_contiguous_regions() {
+    let world = WorldGen::new(42, 128, 128);
+    let all = tiles(&world);
+    let biomes: HashSet<Biome> = all.iter().map(|t| t.2).collect();
+    assert_eq!(biomes.len(), 5, "biomes present: {biomes:?}");
+
+    let same = all
+        .iter()
+        .filter(|&&(x, y, b, _)| world.tile(x + 1, y).is_none_or(|n| n.biome == b))
+        .count();
+    assert!(
+        same * 10 > all.len() * 8,
+        "only {same} of {} match",
+        all.len()
+    );
+}
+
+#[test]
+fn rich_tiles_are_rare() {
+    let world = WorldGen::new(42, 128, 128);
+    let rich = tiles(&world).iter().filter(|t| t.3).count() as u64;
+    let expected = 128 * 128 * RICH_PER_MILLE / 1000;
+    assert!(
+        rich > expected / 2 && rich < expected * 2,
+        "{rich} rich tiles"
+    );
+}
+
+#[test]
+fn climate_picks_biome() {
+    assert_eq!(biome_for(0.5, 0.9), Biome::Wetland);
+    assert_eq!(biome_for(0.1, 0.5), Biome::Tundra);
+    assert_eq!(biome_for(0.8, 0.1), Biome::Desert);
+    assert_eq!(biome_for(0.5, 0.5), Biome::Forest);
+    assert_eq!(biome_for(0.5, 0.3), Biome::Grassland);
+}
@@ -0,0 +1,76 @@
+//! WebSocket handshake authentication and per-message identity checks.
+
+use actix_web::test::TestRequest;
+use biotonic_server::{
+    game::session::{dispatch, DispatchErr},
+    http::auth::JwtAuth,
+    protocol::ClientMsg,
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use uuid::Uuid;
+
+const SECRET: &str = "ws-auth-test-secret";
+
+fn token(player_id: Uuid) -> String {
+    std::env::set_var("JWT_SECRET", SECRET);
+    let claims = serde_json::json!({
+        "sub": Uuid::new_v4().to_string(),
+        "pid": player_id.to_string(),
+        "exp": chrono::Utc::now().timestamp() as usize + 600,
+    });
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(SECRET.as_bytes()),
+    )
+    .unwrap()
+}
+
+#[test]
+fn token_from_query_param() {
+    let pid = Uuid::new_v4();
+    let req = TestRequest::get()
+        .uri(&format!("/ws/?token={}", token(pid)))
+        .to_http_request();
+    assert_eq!(JwtAuth::from_ws_request(&req).unwrap().player_id, pid);
+}
+
+#[test]
+fn token_from_subprotocol() {
+    let pid = Uuid::new_v4();
+    let req = TestRequest::get()
+        .uri("/ws/")
+        .insert_header(("Sec-WebSocket-Protocol", format!("bearer, {}", token(pid))))
+        .to_http_request();
+    assert_eq!(JwtAuth::from_ws_request(&req).unwrap().player_id, pid);
+}
+
+#[test]
+fn missing_or_forged_token_is_refused() {
+    let _ = token(Uuid::new_v4()); // sets JWT_SECRET
+    let bare = TestRequest::get()
+        .uri(&format!("/ws/?player_id={}", Uuid::new_v4()))
+        .to_http_request();
+    assert!(JwtAuth::from_ws_request(&bare).is_err());
+
+    let forged = TestRequest::get()
+        .uri("/ws/?token=eyJhbGciOiJIUzI1NiJ9.e30.bogus")
+        .to_http_request();
+    assert!(JwtAuth::from_ws_request(&forged).is_err());
+}
+
+#[tokio::test]
+async fn dispatch_rejects_foreign_player_id() {
+    // Neither handle is touched: the identity check runs first.
+    let db = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+    let redis = redis::Client::open("redis://127.0.0.1").unwrap();
+
+    let me = Uuid::new_v4();
+    let msg = ClientMsg::Ready {
+        game_id: Uuid::new_v4(),
+        player_id: Uuid::new_v4(),
+        request_id: None,
+    };
+    let err = dispatch(db, redis, me, msg).await.unwrap_err();
+    assert!(matches!(err, DispatchErr::NotAuthenticatedPlayer));
+}